@@ -0,0 +1,11 @@
+-- Create audit_log Table
+-- Every change to orders is recorded along with the request that made it
+CREATE TABLE audit_log(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   request_id TEXT NOT NULL,
+   action TEXT NOT NULL,
+   details jsonb NOT NULL,
+   recorded_at timestamptz NOT NULL
+);
+CREATE INDEX audit_log_request_id_idx ON audit_log (request_id);
@@ -0,0 +1,11 @@
+-- Create rate_limit_buckets Table
+-- Token buckets shared by every instance when rate limits are kept in Postgres
+CREATE TABLE rate_limit_buckets(
+   group_name TEXT NOT NULL,
+   client_key TEXT NOT NULL,
+   tokens DOUBLE PRECISION NOT NULL,
+   -- Whether the last request took a token, i.e. was let through
+   allowed BOOLEAN NOT NULL,
+   updated_at timestamptz NOT NULL,
+   PRIMARY KEY (group_name, client_key)
+);
@@ -0,0 +1,14 @@
+-- Add modifiers, notes and prices to orders
+-- The price is fixed when the order is placed, so later menu changes don't alter open bills
+ALTER TABLE orders ADD COLUMN modifiers TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE orders ADD COLUMN note TEXT;
+ALTER TABLE orders ADD COLUMN unit_price_cents BIGINT NOT NULL DEFAULT 0;
+-- Orders placed before prices existed are charged the base price
+UPDATE orders SET unit_price_cents = CASE item
+   WHEN 'hamburger' THEN 850
+   WHEN 'fries' THEN 350
+   WHEN 'cola' THEN 250
+   WHEN 'water' THEN 150
+   ELSE 0
+END;
+ALTER TABLE orders ALTER COLUMN unit_price_cents DROP DEFAULT;
@@ -0,0 +1,9 @@
+-- Add the variant ordered, e.g. a large cola, to orders
+ALTER TABLE orders ADD COLUMN variant TEXT NOT NULL DEFAULT '';
+-- Orders placed before variants existed were for the default variant
+UPDATE orders SET variant = CASE item
+   WHEN 'hamburger' THEN 'single'
+   WHEN 'cola' THEN 'medium'
+   ELSE 'regular'
+END;
+ALTER TABLE orders ALTER COLUMN variant DROP DEFAULT;
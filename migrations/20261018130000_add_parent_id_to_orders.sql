@@ -0,0 +1,4 @@
+-- Link the components of a bundle to the bundle's line
+-- Voiding the bundle removes its components with it
+ALTER TABLE orders ADD COLUMN parent_id uuid REFERENCES orders (id) ON DELETE CASCADE;
+CREATE INDEX orders_parent_id_idx ON orders (parent_id);
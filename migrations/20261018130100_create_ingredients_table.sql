@@ -0,0 +1,9 @@
+-- Create ingredients Table
+-- Only ingredients listed here are tracked, anything else is assumed to be in stock
+CREATE TABLE ingredients(
+   name TEXT NOT NULL,
+   PRIMARY KEY (name),
+   stock integer NOT NULL CHECK (stock >= 0),
+   low_stock_threshold integer NOT NULL CHECK (low_stock_threshold >= 0),
+   updated_at timestamptz NOT NULL
+);
@@ -0,0 +1,8 @@
+-- Create item_availability Table
+-- An item listed here has been taken off ("86'd") until `restore_at`, or until put back if NULL
+CREATE TABLE item_availability(
+   item TEXT NOT NULL,
+   PRIMARY KEY (item),
+   unavailable_since timestamptz NOT NULL,
+   restore_at timestamptz
+);
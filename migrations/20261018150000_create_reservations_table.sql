@@ -0,0 +1,15 @@
+-- Create reservations Table
+-- `status` is `booked` until the party is seated, or `cancelled`
+CREATE TABLE reservations(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   table_no integer NOT NULL,
+   party_size integer NOT NULL CHECK (party_size > 0),
+   starts_at timestamptz NOT NULL,
+   ends_at timestamptz NOT NULL CHECK (ends_at > starts_at),
+   customer_name TEXT NOT NULL,
+   phone TEXT NOT NULL,
+   status TEXT NOT NULL,
+   created_at timestamptz NOT NULL
+);
+CREATE INDEX reservations_starts_at ON reservations (starts_at);
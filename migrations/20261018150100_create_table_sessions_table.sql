@@ -0,0 +1,14 @@
+-- Create table_sessions Table
+-- A party sitting at a table, from being seated until the table is closed
+CREATE TABLE table_sessions(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   table_no integer NOT NULL,
+   party_size integer NOT NULL CHECK (party_size > 0),
+   reservation_id uuid REFERENCES reservations (id),
+   opened_at timestamptz NOT NULL,
+   closed_at timestamptz
+);
+-- A table can only have one party at a time
+CREATE UNIQUE INDEX table_sessions_one_open_per_table ON table_sessions (table_no)
+   WHERE closed_at IS NULL;
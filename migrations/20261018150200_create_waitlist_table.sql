@@ -0,0 +1,15 @@
+-- Create waitlist Table
+-- `status` is `waiting` until the party is seated, or `left`
+CREATE TABLE waitlist(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   -- Arrival order, parties can join within the same instant
+   position bigserial NOT NULL,
+   party_size integer NOT NULL CHECK (party_size > 0),
+   customer_name TEXT NOT NULL,
+   phone TEXT NOT NULL,
+   quoted_wait_minutes integer NOT NULL,
+   status TEXT NOT NULL,
+   added_at timestamptz NOT NULL,
+   session_id uuid REFERENCES table_sessions (id)
+);
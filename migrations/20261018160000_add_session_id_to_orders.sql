@@ -0,0 +1,4 @@
+-- Link orders to the session of the party that placed them
+-- Orders placed while nobody is seated at the table have no session
+ALTER TABLE orders ADD COLUMN session_id uuid REFERENCES table_sessions (id);
+CREATE INDEX orders_session_id_idx ON orders (session_id);
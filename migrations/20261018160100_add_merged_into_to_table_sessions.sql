@@ -0,0 +1,2 @@
+-- A session merged into another is closed, and points at the one it was merged into
+ALTER TABLE table_sessions ADD COLUMN merged_into uuid REFERENCES table_sessions (id);
@@ -0,0 +1,7 @@
+-- Courses and fire control
+-- A line reaches the kitchen when it is fired, held lines have no `fired_at` yet
+ALTER TABLE orders ADD COLUMN course TEXT NOT NULL DEFAULT 'main';
+ALTER TABLE orders ADD COLUMN fired_at timestamptz;
+-- Everything ordered so far went straight to the kitchen
+UPDATE orders SET fired_at = placed_at;
+UPDATE orders SET course = 'drinks' WHERE item IN ('cola', 'water');
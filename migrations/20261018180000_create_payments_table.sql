@@ -0,0 +1,23 @@
+-- Create payments Table
+-- Money taken towards a session's bill. A refund is a row of its own pointing at the payment
+-- it gives money back for, with a negative amount so a session's balance is a plain sum
+CREATE TABLE payments(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   session_id uuid NOT NULL REFERENCES table_sessions (id),
+   tender TEXT NOT NULL CHECK (tender IN ('cash', 'card', 'voucher')),
+   amount_cents bigint NOT NULL,
+   tip_cents bigint NOT NULL CHECK (tip_cents >= 0),
+   -- What was handed over in cash, and what was given back
+   tendered_cents bigint NOT NULL,
+   change_cents bigint NOT NULL CHECK (change_cents >= 0),
+   -- Who took the payment, `NULL` for cash which staff count themselves
+   provider TEXT,
+   -- e.g. the card terminal's transaction id or a voucher code
+   reference TEXT,
+   refund_of uuid REFERENCES payments (id),
+   created_at timestamptz NOT NULL,
+   CHECK ((refund_of IS NULL) = (amount_cents > 0))
+);
+CREATE INDEX payments_session_id_idx ON payments (session_id);
+CREATE INDEX payments_refund_of_idx ON payments (refund_of);
@@ -0,0 +1,32 @@
+-- Create vouchers and discounts Tables
+-- Promotional voucher codes, e.g. `TENOFF`, each taking either a percentage or an amount off
+CREATE TABLE vouchers(
+   code TEXT NOT NULL,
+   PRIMARY KEY (code),
+   percent_off bigint CHECK (percent_off BETWEEN 1 AND 100),
+   amount_off_cents bigint CHECK (amount_off_cents > 0),
+   -- How many parties can use the code, any number when `NULL`
+   max_uses integer CHECK (max_uses > 0),
+   uses integer NOT NULL DEFAULT 0,
+   valid_until timestamptz,
+   CHECK ((percent_off IS NULL) <> (amount_off_cents IS NULL))
+);
+-- Vouchers and manual discounts given to a party, worked out when their bill is prepared
+CREATE TABLE discounts(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   session_id uuid NOT NULL REFERENCES table_sessions (id),
+   kind TEXT NOT NULL CHECK (kind IN ('voucher', 'manual')),
+   -- The voucher's code or the reason staff gave
+   name TEXT NOT NULL,
+   voucher_code TEXT REFERENCES vouchers (code),
+   percent_off bigint CHECK (percent_off BETWEEN 1 AND 100),
+   amount_off_cents bigint CHECK (amount_off_cents > 0),
+   -- Set when a manager signed off a discount above the approval threshold
+   manager_approved boolean NOT NULL DEFAULT false,
+   created_at timestamptz NOT NULL,
+   CHECK ((percent_off IS NULL) <> (amount_off_cents IS NULL))
+);
+CREATE INDEX discounts_session_id_idx ON discounts (session_id);
+-- A code only counts once per party
+CREATE UNIQUE INDEX discounts_one_voucher_per_session ON discounts (session_id, voucher_code);
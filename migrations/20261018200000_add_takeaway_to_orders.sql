@@ -0,0 +1,2 @@
+-- Takeaway orders are taxed differently from eating in
+ALTER TABLE orders ADD COLUMN takeaway BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,20 @@
+-- Create print_jobs Table
+-- Kitchen tickets waiting for, or sent to, their station's printer. Tickets are queued in the
+-- same transaction as the orders they are for, so an order is never placed without its ticket
+CREATE TABLE print_jobs(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   station TEXT NOT NULL CHECK (station IN ('grill', 'fryer', 'bar')),
+   table_no integer NOT NULL,
+   -- The ticket as it will be printed, rendered when it is sent to the printer
+   ticket jsonb NOT NULL,
+   status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'printed', 'failed')),
+   attempts integer NOT NULL DEFAULT 0,
+   -- Why the last attempt failed
+   last_error TEXT,
+   -- Failed attempts are retried from this time on, later after every failure
+   next_attempt_at timestamptz NOT NULL,
+   created_at timestamptz NOT NULL,
+   printed_at timestamptz
+);
+CREATE INDEX print_jobs_pending_idx ON print_jobs (next_attempt_at) WHERE status = 'pending';
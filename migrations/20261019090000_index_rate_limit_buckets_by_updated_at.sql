@@ -0,0 +1,3 @@
+-- Index rate_limit_buckets by updated_at
+-- Idle buckets are expired by when they were last used
+CREATE INDEX rate_limit_buckets_updated_at ON rate_limit_buckets (updated_at);
@@ -0,0 +1,5 @@
+-- Add status to payments
+-- Card and voucher payments are recorded as pending before the provider is asked to charge or
+-- refund, then completed or failed once it answers, so a crash in between leaves a trace
+ALTER TABLE payments ADD COLUMN status TEXT NOT NULL DEFAULT 'completed'
+   CHECK (status IN ('pending', 'completed', 'failed'));
@@ -0,0 +1,38 @@
+-- Create promotions Table
+-- Discounts a bill qualifies for on its own, managed by managers rather than released with the code
+CREATE TABLE promotions(
+   name TEXT NOT NULL,
+   PRIMARY KEY (name),
+   -- Either a single item or every item of a menu category, e.g. `drinks`
+   target_item TEXT,
+   target_category TEXT,
+   -- How many units of the target have to be on the bill before anything comes off
+   min_quantity integer NOT NULL CHECK (min_quantity > 0),
+   -- Either of every `buy` units the cheapest `free` cost nothing, or a percentage off
+   buy integer CHECK (buy > 0),
+   free integer CHECK (free > 0 AND free < buy),
+   percent_off bigint CHECK (percent_off BETWEEN 1 AND 100),
+   -- When the lines have to be ordered in the restaurant's local time, at any time when `NULL`
+   days TEXT[] CHECK (days <@ ARRAY['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun']),
+   from_time time,
+   until_time time,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   CHECK ((target_item IS NULL) <> (target_category IS NULL)),
+   CHECK ((buy IS NULL) = (free IS NULL)),
+   CHECK ((buy IS NULL) <> (percent_off IS NULL)),
+   CHECK ((days IS NULL) = (from_time IS NULL) AND (days IS NULL) = (until_time IS NULL))
+);
+-- The promotions that used to be built in
+INSERT INTO promotions (
+   name, target_item, target_category, min_quantity, buy, free, percent_off,
+   days, from_time, until_time, created_at
+)
+VALUES
+   (
+      '2-for-1 fries tuesdays', 'fries', NULL, 2, 2, 1, NULL,
+      ARRAY['Tue'], '11:00', '02:00', '2026-10-19 00:00:00+00'
+   ),
+   (
+      '10% off a round of 6 drinks', NULL, 'drinks', 6, NULL, NULL, 10,
+      NULL, NULL, NULL, '2026-10-19 00:00:01+00'
+   );
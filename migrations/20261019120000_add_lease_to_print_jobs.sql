@@ -0,0 +1,9 @@
+-- Add a lease to print_jobs
+-- A ticket is claimed as `printing` until `leased_until` while it is sent to its printer, so
+-- nothing stays locked meanwhile. A claim that runs out, because the instance sending it
+-- stopped, makes the ticket due again
+ALTER TABLE print_jobs DROP CONSTRAINT print_jobs_status_check;
+ALTER TABLE print_jobs ADD CONSTRAINT print_jobs_status_check
+   CHECK (status IN ('pending', 'printing', 'printed', 'failed'));
+ALTER TABLE print_jobs ADD COLUMN leased_until timestamptz;
+CREATE INDEX print_jobs_printing_idx ON print_jobs (leased_until) WHERE status = 'printing';
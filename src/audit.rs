@@ -0,0 +1,35 @@
+use crate::request_id::RequestId;
+use chrono::Utc;
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+/*
+ * Audit records are written in the same transaction as the change they describe,
+ * so there is never a change without a record or a record without a change.
+ */
+#[tracing::instrument(name = "Recording audit entry", skip(transaction, request_id, details))]
+pub async fn record_audit(
+    transaction: &mut Transaction<'_, Postgres>,
+    request_id: &RequestId,
+    action: &str,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+    INSERT INTO audit_log (id, request_id, action, details, recorded_at)
+    VALUES ($1, $2, $3, $4, $5)
+    "#,
+        Uuid::new_v4(),
+        request_id.as_ref(),
+        action,
+        details,
+        Utc::now()
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::configuration::AuthSettings;
+use actix_web::dev::Payload;
+use actix_web::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use derive_more::Display;
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+
+// What a member of staff may do, a manager may do everything the kitchen can
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Kitchen,
+    Manager,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Staff {
+    pub name: String,
+    pub role: Role,
+}
+
+/*
+ * The staff tokens of the `auth` settings, looked up by their hash so the tokens
+ * themselves are not kept around.
+ */
+pub struct StaffTokens(HashMap<String, Staff>);
+
+impl StaffTokens {
+    pub fn new(settings: &AuthSettings) -> Self {
+        Self(
+            settings
+                .staff
+                .iter()
+                .map(|member| {
+                    (
+                        hash(member.token.expose_secret()),
+                        Staff {
+                            name: member.name.clone(),
+                            role: member.role,
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    // Who sent the request, going by their `Authorization: Bearer <token>` header
+    fn authenticate(&self, req: &HttpRequest) -> Option<&Staff> {
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))?;
+        self.0.get(&hash(token.trim()))
+    }
+}
+
+fn hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Display)]
+pub enum AuthError {
+    #[display(fmt = "A staff token is needed.")]
+    Unauthenticated,
+    #[display(fmt = "Only a {:?} may do this.", _0)]
+    Forbidden(Role),
+}
+
+// A missing or unknown token is a 401, a token whose role is not enough a 403
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Unauthenticated => StatusCode::UNAUTHORIZED,
+            AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let AuthError::Unauthenticated = self {
+            response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+        }
+        response.json(serde_json::json!({ "error": self.to_string() }))
+    }
+}
+
+fn authorize(req: &HttpRequest, role: Role) -> Result<Staff, AuthError> {
+    let staff = req
+        .app_data::<web::Data<StaffTokens>>()
+        .and_then(|tokens| tokens.authenticate(req))
+        .ok_or(AuthError::Unauthenticated)?;
+    if staff.role < role {
+        return Err(AuthError::Forbidden(role));
+    }
+    Ok(staff.clone())
+}
+
+// Handlers taking a `Kitchen` argument are only run for kitchen staff and managers
+#[derive(Debug)]
+pub struct Kitchen(pub Staff);
+
+impl FromRequest for Kitchen {
+    type Error = AuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authorize(req, Role::Kitchen).map(Kitchen))
+    }
+}
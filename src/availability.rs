@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+/*
+ * Items the kitchen has taken off for now ("86 the fries"), kept in the database so every
+ * instance sees a change straight away. Flags past their restore time are simply ignored,
+ * so nothing needs to run to put an item back.
+ */
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EightySixedItem {
+    pub item: String,
+    // When the item comes back by itself, `None` until someone puts it back
+    pub restore_at: Option<DateTime<Utc>>,
+}
+
+// Items that are 86'd as of `now`
+#[tracing::instrument(name = "Retrieving 86'd items from the database", skip(pool))]
+pub async fn eighty_sixed_items(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+) -> Result<Vec<EightySixedItem>, sqlx::Error> {
+    sqlx::query_as!(
+        EightySixedItem,
+        r#"
+    SELECT item, restore_at FROM item_availability
+    WHERE restore_at IS NULL OR restore_at > $1
+    ORDER BY item
+    "#,
+        now
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Takes an item off from `now` until `restore_at`, or until it is put back when `None`
+#[tracing::instrument(name = "Marking an item as 86'd in the database", skip(pool))]
+pub async fn eighty_six(
+    pool: &PgPool,
+    item: &str,
+    now: DateTime<Utc>,
+    restore_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+    INSERT INTO item_availability (item, unavailable_since, restore_at)
+    VALUES ($1, $2, $3)
+    ON CONFLICT (item) DO UPDATE SET
+        unavailable_since = EXCLUDED.unavailable_since,
+        restore_at = EXCLUDED.restore_at
+    "#,
+        item,
+        now,
+        restore_at
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Putting an item back on in the database", skip(pool))]
+pub async fn restore(pool: &PgPool, item: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM item_availability WHERE item = $1", item)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    Ok(())
+}
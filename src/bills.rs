@@ -0,0 +1,93 @@
+use crate::configuration::TaxSettings;
+use crate::domain::{Bill, DatabaseResponse};
+use crate::promotions::{promotions, session_discounts};
+use crate::routes::query_table_request;
+use chrono_tz::Tz;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/*
+ * What a seated party owes: everything they ordered, less the promotions it qualifies for
+ * and the vouchers and discounts they were given, with tax. Payments are taken against this total.
+ */
+#[tracing::instrument(name = "Preparing a session's bill", skip(transaction, tax))]
+pub async fn session_bill(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+    table_no: i32,
+    timezone: Tz,
+    tax: &TaxSettings,
+) -> Result<Bill, sqlx::Error> {
+    let orders = sqlx::query_as!(
+        DatabaseResponse,
+        "SELECT * FROM orders WHERE session_id = $1",
+        session_id
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+    let adjustments: Vec<_> = session_discounts(&mut **transaction, &[session_id])
+        .await?
+        .iter()
+        .map(|discount| discount.adjustment())
+        .collect();
+    let promotions = promotions(&mut **transaction).await?;
+
+    Ok(Bill::new(table_no, orders)
+        .with_discounts(&promotions, &adjustments, timezone)
+        .with_tax(tax, timezone))
+}
+
+// Everything ordered at a table, with the discounts of whichever parties ordered it
+#[tracing::instrument(name = "Preparing a table's bill", skip(pool, tax))]
+pub async fn table_bill(
+    pool: &PgPool,
+    table_no: i32,
+    timezone: Tz,
+    tax: &TaxSettings,
+) -> Result<Bill, sqlx::Error> {
+    let orders = query_table_request(pool, &table_no).await?;
+    let mut session_ids: Vec<Uuid> = orders.iter().filter_map(|order| order.session_id).collect();
+    session_ids.sort_unstable();
+    session_ids.dedup();
+    let adjustments: Vec<_> = session_discounts(pool, &session_ids)
+        .await?
+        .iter()
+        .map(|discount| discount.adjustment())
+        .collect();
+    let promotions = promotions(pool).await?;
+
+    Ok(Bill::new(table_no, orders)
+        .with_discounts(&promotions, &adjustments, timezone)
+        .with_tax(tax, timezone))
+}
+
+// A party's bill, also once they have left, `None` when there is no such session
+pub async fn bill_for_session(
+    pool: &PgPool,
+    session_id: Uuid,
+    timezone: Tz,
+    tax: &TaxSettings,
+) -> Result<Option<Bill>, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let session = sqlx::query!(
+        "SELECT table_no FROM table_sessions WHERE id = $1",
+        session_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    let bill = match session {
+        Some(session) => Some(
+            session_bill(
+                &mut transaction,
+                session_id,
+                session.table_no,
+                timezone,
+                tax,
+            )
+            .await?,
+        ),
+        None => None,
+    };
+    transaction.commit().await?;
+    Ok(bill)
+}
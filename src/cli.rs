@@ -0,0 +1,113 @@
+// Commands understood by the `restaurant` binary
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    // Load and validate the configuration, then print it with secrets redacted
+    ConfigCheck,
+    // Anything using the database, which runs with telemetry set up
+    Run(Task),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Task {
+    // Start the HTTP server, this is what happens when no arguments are given
+    Serve,
+    // Apply pending migrations and exit, or only list them when `dry_run` is set
+    Migrate { dry_run: bool },
+}
+
+impl Command {
+    // Parses the command line arguments, excluding the binary name
+    pub fn parse<I>(args: I) -> Result<Command, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+
+        match args.next().as_deref() {
+            None | Some("serve") => match args.next() {
+                None => Ok(Command::Run(Task::Serve)),
+                Some(other) => Err(format!("{} is not a valid argument for serve.", other)),
+            },
+            Some("migrate") => {
+                let mut dry_run = false;
+                for arg in args {
+                    match arg.as_str() {
+                        "--dry-run" => dry_run = true,
+                        other => {
+                            return Err(format!("{} is not a valid argument for migrate.", other))
+                        }
+                    }
+                }
+                Ok(Command::Run(Task::Migrate { dry_run }))
+            }
+            Some("config") => match (args.next().as_deref(), args.next()) {
+                (Some("check"), None) => Ok(Command::ConfigCheck),
+                _ => Err("The only config command is `config check`.".to_string()),
+            },
+            Some(other) => Err(format!(
+                "{} is not a supported command. Use `serve`, `migrate [--dry-run]` or `config check`.",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{Command, Task};
+    use claim::assert_err;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn no_arguments_serves() {
+        assert_eq!(Command::parse(args(&[])), Ok(Command::Run(Task::Serve)));
+    }
+
+    #[test]
+    fn serve_is_parsed() {
+        assert_eq!(
+            Command::parse(args(&["serve"])),
+            Ok(Command::Run(Task::Serve))
+        );
+    }
+
+    #[test]
+    fn migrate_is_parsed() {
+        assert_eq!(
+            Command::parse(args(&["migrate"])),
+            Ok(Command::Run(Task::Migrate { dry_run: false }))
+        );
+    }
+
+    #[test]
+    fn migrate_dry_run_is_parsed() {
+        assert_eq!(
+            Command::parse(args(&["migrate", "--dry-run"])),
+            Ok(Command::Run(Task::Migrate { dry_run: true }))
+        );
+    }
+
+    #[test]
+    fn config_check_is_parsed() {
+        assert_eq!(
+            Command::parse(args(&["config", "check"])),
+            Ok(Command::ConfigCheck)
+        );
+    }
+
+    #[test]
+    fn unknown_commands_and_flags_are_rejected() {
+        for invalid in [
+            &["unicorn"][..],
+            &["config"][..],
+            &["config", "check", "--verbose"][..],
+            &["migrate", "--force"][..],
+            &["serve", "--dry-run"][..],
+        ] {
+            assert_err!(Command::parse(args(invalid)));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Duration, Utc};
+use std::sync::RwLock;
+
+/*
+ * Where the current time comes from, so anything depending on it can be tested at a
+ * chosen time instead of whenever the tests happen to run.
+ */
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+// The real time, used everywhere outside of tests
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+// A clock that stays where it is put
+pub struct FixedClock(RwLock<DateTime<Utc>>);
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self(RwLock::new(now))
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.0.write().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, FixedClock};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn a_fixed_clock_only_moves_when_told_to() {
+        let start = Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let clock = FixedClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::minutes(5));
+        assert_eq!(clock.now(), start + Duration::minutes(5));
+    }
+}
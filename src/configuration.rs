@@ -1,50 +1,591 @@
-use serde_aux::field_attributes::deserialize_number_from_string;
+use crate::auth::Role;
+use crate::domain::{rate_for, Dining, Station, TaxClass, TaxRate, TaxRounding};
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use secrecy::{ExposeSecret, Secret};
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
 use sqlx::postgres::PgConnectOptions;
 use sqlx::postgres::PgSslMode;
 use sqlx::ConnectOptions;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use subtle::ConstantTimeEq;
+use tracing_subscriber::EnvFilter;
 
-#[derive(serde::Deserialize, Clone)]
+#[derive(serde::Deserialize, Clone, Debug)]
 pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub runtime: RuntimeSettings,
+    #[serde(default)]
+    pub rate_limiting: RateLimitingSettings,
+    #[serde(default)]
+    pub auth: AuthSettings,
+    #[serde(default)]
+    pub reservations: ReservationSettings,
+    #[serde(default)]
+    pub promotions: PromotionSettings,
+    #[serde(default)]
+    pub tax: TaxSettings,
+    #[serde(default)]
+    pub receipts: ReceiptSettings,
+    #[serde(default)]
+    pub printing: PrintingSettings,
+    // Where the settings were loaded from, so they can be reloaded when the files change
+    #[serde(skip)]
+    pub source: Option<ConfigurationSource>,
 }
 
-#[derive(serde::Deserialize, Clone)]
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AuthSettings {
+    // Staff send their token as `Authorization: Bearer <token>` to use the operational endpoints
+    pub staff: Vec<StaffToken>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct StaffToken {
+    pub name: String,
+    pub role: Role,
+    pub token: Secret<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RateLimitingSettings {
+    pub backend: RateLimitBackend,
+    // Clients sending one of these keys get a bucket of their own, any other key is ignored
+    pub api_keys: Vec<Secret<String>>,
+    // Proxies whose X-Forwarded-For header is believed, other clients are known by their address
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+// Where token buckets are kept
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    // Buckets are local to each instance
+    #[default]
+    Memory,
+    // Buckets are shared by every instance using the same database
+    Postgres,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ReservationSettings {
+    // Bookings start on these boundaries, e.g. every 15 minutes
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub slot_minutes: i32,
+    // How long a table is kept for a booking, and how long a seated party is expected to stay
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub default_duration_minutes: i32,
+}
+
+impl Default for ReservationSettings {
+    fn default() -> Self {
+        Self {
+            slot_minutes: 15,
+            default_duration_minutes: 90,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PromotionSettings {
+    // Manual discounts worth more than this need a manager's code
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub manual_discount_approval_cents: i64,
+    /*
+     * Percentage discounts above this need a manager's code whatever they are worth when
+     * given, as they grow with everything the party orders afterwards
+     */
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub manual_discount_approval_percent: i64,
+    // Without one, nothing above the threshold can be approved
+    pub manager_code: Option<Secret<String>>,
+}
+
+impl Default for PromotionSettings {
+    fn default() -> Self {
+        Self {
+            manual_discount_approval_cents: 1000,
+            manual_discount_approval_percent: 10,
+            manager_code: None,
+        }
+    }
+}
+
+impl PromotionSettings {
+    pub fn approves(&self, code: Option<&str>) -> bool {
+        match (&self.manager_code, code) {
+            // Without a constant time comparison, response times would give the code away
+            (Some(manager_code), Some(code)) => manager_code
+                .expose_secret()
+                .as_bytes()
+                .ct_eq(code.as_bytes())
+                .into(),
+            _ => false,
+        }
+    }
+}
+
+/*
+ * The tax rules of the jurisdiction the restaurant is in. Bills carry no tax until rates
+ * are configured, and once they are every tax class needs a rate for eating in and takeaway.
+ */
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct TaxSettings {
+    // Shown with the breakdown on bills, e.g. `ES`
+    pub jurisdiction: String,
+    // Whether menu prices already contain tax or have it added on the bill
+    pub prices_include_tax: bool,
+    pub rounding: TaxRounding,
+    pub rates: Vec<TaxRate>,
+}
+
+impl TaxSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(rate) = self
+            .rates
+            .iter()
+            .find(|rate| rate.percent.is_sign_negative() || rate.percent >= 100.into())
+        {
+            return Err(format!(
+                "tax.rates {} must be at least 0 and below 100 percent, got {}.",
+                rate.name, rate.percent
+            ));
+        }
+        for (i, rate) in self.rates.iter().enumerate() {
+            if self.rates[..i].iter().any(|r| {
+                r.class == rate.class
+                    && r.dining == rate.dining
+                    && r.effective_from == rate.effective_from
+            }) {
+                return Err(format!(
+                    "tax.rates {} takes effect on {} alongside another rate for the same items.",
+                    rate.name, rate.effective_from
+                ));
+            }
+        }
+        if self.rates.is_empty() {
+            return Ok(());
+        }
+        // Rates are checked from the first day any of them applies
+        let first_day = self
+            .rates
+            .iter()
+            .map(|rate| rate.effective_from)
+            .min()
+            .unwrap_or(NaiveDate::MIN);
+        for class in TaxClass::ALL {
+            for dining in Dining::ALL {
+                if rate_for(&self.rates, class, dining, first_day).is_none() {
+                    return Err(format!(
+                        "tax.rates needs a rate for {:?} from {} for eating in and takeaway.",
+                        class, first_day
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// What is printed around the bill on a receipt
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ReceiptSettings {
+    // Centred above the bill, e.g. the restaurant's name and address
+    pub header: Vec<String>,
+    // Centred below the payments
+    pub footer: Vec<String>,
+    // Characters to a line, 42 on 80mm paper and 32 on 58mm paper
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub width: usize,
+}
+
+impl Default for ReceiptSettings {
+    fn default() -> Self {
+        Self {
+            header: vec![],
+            footer: vec!["Thank you for your visit!".to_string()],
+            width: 42,
+        }
+    }
+}
+
+// Where kitchen tickets are sent
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PrinterKind {
+    // Tickets are queued but nothing prints them
+    #[default]
+    None,
+    // Raw ESC/POS to a network printer per station
+    Tcp,
+    // A file per ticket under `directory`
+    File,
+}
+
+/*
+ * The kitchen's ticket printers. Tickets are queued when lines reach the kitchen and sent by
+ * a background worker, which retries a failing printer later and later until it gives up.
+ */
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PrintingSettings {
+    pub sink: PrinterKind,
+    // `host:port` of every station's printer, for `tcp`
+    pub printers: HashMap<Station, String>,
+    // For `file`
+    pub directory: PathBuf,
+    // Characters to a line of a ticket
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub width: usize,
+    // How often the queue is checked for tickets to print
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_milliseconds: u64,
+    // For connecting to a printer and sending it a ticket
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_milliseconds: u64,
+    // A ticket is given up on after failing this often
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_attempts: i32,
+    // The wait after the first failure, doubled after each one after it
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub retry_seconds: i64,
+}
+
+impl Default for PrintingSettings {
+    fn default() -> Self {
+        Self {
+            sink: PrinterKind::None,
+            printers: HashMap::new(),
+            directory: PathBuf::from("tickets"),
+            width: 42,
+            poll_interval_milliseconds: 1000,
+            timeout_milliseconds: 5000,
+            max_attempts: 5,
+            retry_seconds: 10,
+        }
+    }
+}
+
+impl PrintingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sink == PrinterKind::Tcp {
+            if let Some(station) = Station::ALL
+                .into_iter()
+                .find(|station| !self.printers.contains_key(station))
+            {
+                return Err(format!(
+                    "printing.printers needs an address for the {} printer.",
+                    station.as_str()
+                ));
+            }
+        }
+        if self.sink == PrinterKind::File && self.directory.as_os_str().is_empty() {
+            return Err("printing.directory is needed to print tickets to files.".into());
+        }
+        if !(24..=80).contains(&self.width) {
+            return Err(format!(
+                "printing.width must be between 24 and 80 characters, got {}.",
+                self.width
+            ));
+        }
+        if self.poll_interval_milliseconds == 0 || self.max_attempts < 1 || self.retry_seconds < 0 {
+            return Err(
+                "printing.poll_interval_milliseconds and printing.max_attempts must be at least 1, \
+                 printing.retry_seconds cannot be negative."
+                    .into(),
+            );
+        }
+        Ok(())
+    }
+
+    // How long to wait before trying a ticket again after it failed `attempts` times
+    pub fn retry_delay(&self, attempts: i32) -> chrono::Duration {
+        chrono::Duration::seconds(self.retry_seconds << (attempts - 1).clamp(0, 10))
+    }
+
+    /*
+     * How long a ticket is claimed for while it is sent, twice the printer timeout so a
+     * slow printer is not mistaken for an instance that stopped
+     */
+    pub fn lease(&self) -> chrono::Duration {
+        chrono::Duration::milliseconds(2 * self.timeout_milliseconds as i64)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigurationSource {
+    pub directory: PathBuf,
+    pub environment: Environment,
+}
+
+/*
+ * Settings managers may change mid-service. They are reloaded whenever the configuration
+ * files change, and can be changed through `/admin/settings`, without restarting.
+ */
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeSettings {
+    // Scales the preparation time given to new orders, e.g. 1.5 on a busy night
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub preparation_time_multiplier: f64,
+    // Same syntax as `RUST_LOG`, e.g. `info` or `info,sqlx=warn`
+    pub log_level: String,
+    // Token bucket limits per group of routes, routes in no group are not limited
+    pub rate_limits: Vec<RateLimit>,
+}
+
+/*
+ * A token bucket shared by a group of routes for each client.
+ * A client may make `burst` requests at once, after which tokens come back at `per_second`.
+ */
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub group: String,
+    // Route patterns as registered in `startup::run`, e.g. `/query_id/{id}`
+    pub routes: Vec<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub burst: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub per_second: f64,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            preparation_time_multiplier: 1.0,
+            log_level: "info".to_string(),
+            rate_limits: Vec::new(),
+        }
+    }
+}
+
+// Nobody should be told their fries take ten times longer than usual by accident
+const MAX_PREPARATION_TIME_MULTIPLIER: f64 = 10.0;
+
+impl RuntimeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.preparation_time_multiplier > 0.0
+            && self.preparation_time_multiplier <= MAX_PREPARATION_TIME_MULTIPLIER)
+        {
+            return Err(format!(
+                "runtime.preparation_time_multiplier must be above 0 and at most {}, got {}.",
+                MAX_PREPARATION_TIME_MULTIPLIER, self.preparation_time_multiplier
+            ));
+        }
+        EnvFilter::try_new(&self.log_level).map_err(|e| {
+            format!(
+                "runtime.log_level {} is not a valid filter: {}",
+                self.log_level, e
+            )
+        })?;
+        for (i, limit) in self.rate_limits.iter().enumerate() {
+            if limit.burst == 0 || limit.per_second <= 0.0 || limit.per_second.is_nan() {
+                return Err(format!(
+                    "runtime.rate_limits {} needs a burst of at least 1 and a positive per_second.",
+                    limit.group
+                ));
+            }
+            if self.rate_limits[..i].iter().any(|l| l.group == limit.group) {
+                return Err(format!(
+                    "runtime.rate_limits has more than one group named {}.",
+                    limit.group
+                ));
+            }
+            if let Some(route) = limit.routes.iter().find(|route| {
+                self.rate_limits[..i]
+                    .iter()
+                    .any(|l| l.routes.contains(route))
+            }) {
+                return Err(format!(
+                    "runtime.rate_limits lists {} in more than one group.",
+                    route
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // The limit applying to a route pattern, if any
+    pub fn rate_limit_for(&self, route: &str) -> Option<&RateLimit> {
+        self.rate_limits
+            .iter()
+            .find(|limit| limit.routes.iter().any(|r| r == route))
+    }
+
+    // Preparation times are whole minutes, and never drop to zero
+    pub fn scale_preparation_time(&self, minutes: i32) -> i32 {
+        ((minutes as f64 * self.preparation_time_multiplier).round() as i32).max(1)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct TelemetrySettings {
+    // Spans are only exported when a collector is configured
+    #[serde(default)]
+    pub otlp: Option<OtlpSettings>,
+    // Log fields whose values are replaced before anything is written
+    #[serde(default = "default_redacted_fields")]
+    pub redacted_fields: Vec<String>,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            otlp: None,
+            redacted_fields: default_redacted_fields(),
+        }
+    }
+}
+
+fn default_redacted_fields() -> Vec<String> {
+    [
+        "password",
+        "secret",
+        "token",
+        "api_key",
+        "customer_name",
+        "phone",
+        "phone_number",
+        "email",
+        "payment_reference",
+        "card_number",
+        "manager_code",
+    ]
+    .iter()
+    .map(|f| f.to_string())
+    .collect()
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct OtlpSettings {
+    // Base url of the collector's OTLP/HTTP receiver e.g. `http://localhost:4318`
+    pub endpoint: String,
+    // Fraction of new traces to record, between 0.0 and 1.0
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub sampling_ratio: f64,
+    pub service_name: String,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
 pub struct ApplicationSettings {
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub host: String,
+    // How long `/health/ready` waits on the database before reporting it as failing
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub readiness_timeout_milliseconds: u64,
+    // Serve `/metrics` on this port instead of the public one
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub admin_port: Option<u16>,
+    // How often the configuration files are checked for runtime settings changes
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub settings_reload_interval_milliseconds: u64,
+    // How long in-flight requests may take to finish once shutdown starts
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub shutdown_timeout_seconds: u64,
+    // Time between readiness failing and the server refusing new connections,
+    // giving load balancers a chance to stop routing traffic here
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub shutdown_drain_delay_milliseconds: u64,
+    // The restaurant's timezone, e.g. `Europe/London`, which menu schedules are in
+    pub timezone: Tz,
 }
 
-#[derive(serde::Deserialize, Clone)]
+#[derive(serde::Deserialize, Clone, Debug)]
 pub struct DatabaseSettings {
     pub username: String,
-    pub password: String,
+    pub password: Secret<String>,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub host: String,
     pub database_name: String,
     pub require_ssl: bool,
+    // Apply embedded migrations before serving instead of relying on `sqlx migrate`
+    pub migrate_on_startup: bool,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_connections: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub min_connections: u32,
+    // How long a request waits for a free connection before giving up
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub acquire_timeout_milliseconds: u64,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub idle_timeout_seconds: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub max_lifetime_seconds: Option<u64>,
+    // Postgres cancels any statement running longer than this, set on every connection
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub statement_timeout_milliseconds: Option<u64>,
+    // Shown in `pg_stat_activity` to tell our connections apart
+    pub application_name: String,
 }
 
+// Environment variable pointing at the configuration directory, `./configuration` by default
+pub const CONFIG_DIR_VARIABLE: &str = "APP_CONFIG_DIR";
+
+// File formats a configuration file may be written in, chosen by its extension
+pub(crate) const CONFIG_FILE_EXTENSIONS: [&str; 4] = ["yaml", "yml", "toml", "json"];
+
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
-    let base_path = std::env::current_dir().expect("Failed to determine the current directory");
-    let configuration_directory = base_path.join("configuration");
+    let configuration_directory = configuration_directory()?;
+    let environment = current_environment()?;
+    load_configuration(&configuration_directory, &environment)
+}
+
+// Where configuration files are read from
+pub fn configuration_directory() -> Result<PathBuf, config::ConfigError> {
+    match std::env::var(CONFIG_DIR_VARIABLE) {
+        Ok(directory) => Ok(PathBuf::from(directory)),
+        Err(_) => std::env::current_dir()
+            .map(|base_path| base_path.join("configuration"))
+            .map_err(|e| {
+                config::ConfigError::Message(format!(
+                    "Failed to determine the current directory: {}",
+                    e
+                ))
+            }),
+    }
+}
 
-    // Detect the running environment.
-    // Default to `local` if unspecified.
-    let environment: Environment = std::env::var("APP_ENVIRONMENT")
+// Detect the running environment.
+// Default to `local` if unspecified.
+pub fn current_environment() -> Result<Environment, config::ConfigError> {
+    std::env::var("APP_ENVIRONMENT")
         .unwrap_or_else(|_| "local".into())
         .try_into()
-        .expect("Failed to parse APP_ENVIRONMENT.");
-    let environment_filename = format!("{}.yaml", environment.as_str());
+        .map_err(config::ConfigError::Message)
+}
+
+/*
+ * Layers `base`, then the file named after the environment, then `APP_` environment variables.
+ * Both files may be written as YAML, TOML or JSON. The environment file is required so that
+ * a misspelt environment fails loudly instead of silently running on the base settings.
+ */
+pub fn load_configuration(
+    configuration_directory: &Path,
+    environment: &Environment,
+) -> Result<Settings, config::ConfigError> {
+    let base_file = find_config_file(configuration_directory, "base")?;
+    let environment_file = find_config_file(configuration_directory, environment.as_str())?;
+
     let settings = config::Config::builder()
-        .add_source(config::File::from(
-            configuration_directory.join("base.yaml"),
-        ))
-        .add_source(config::File::from(
-            configuration_directory.join(environment_filename),
-        ))
+        .add_source(config::File::from(base_file))
+        .add_source(config::File::from(environment_file))
         // Add in settings from environment variables (with a prefix of APP and '__' as separator)
         // E.g. `APP_APPLICATION__PORT=5001 would set `Settings.application.port`
         .add_source(
@@ -54,21 +595,106 @@ pub fn get_configuration() -> Result<Settings, config::ConfigError> {
         )
         .build()?;
 
-    settings.try_deserialize::<Settings>()
+    let mut settings = settings.try_deserialize::<Settings>()?;
+    settings.validate().map_err(config::ConfigError::Message)?;
+    settings.source = Some(ConfigurationSource {
+        directory: configuration_directory.to_path_buf(),
+        environment: environment.clone(),
+    });
+    Ok(settings)
 }
 
-/// The possible runtime environment for our application.
-pub enum Environment {
-    Local,
-    Production,
+// Exactly one file per name, so it is never ambiguous which one is in effect
+fn find_config_file(directory: &Path, name: &str) -> Result<PathBuf, config::ConfigError> {
+    let candidates: Vec<PathBuf> = CONFIG_FILE_EXTENSIONS
+        .iter()
+        .map(|extension| directory.join(format!("{}.{}", name, extension)))
+        .filter(|path| path.is_file())
+        .collect();
+
+    match candidates.as_slice() {
+        [file] => Ok(file.clone()),
+        [] => Err(config::ConfigError::Message(format!(
+            "No configuration file for `{}` in {}, expected one of {}.",
+            name,
+            directory.display(),
+            CONFIG_FILE_EXTENSIONS
+                .iter()
+                .map(|extension| format!("{}.{}", name, extension))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))),
+        _ => Err(config::ConfigError::Message(format!(
+            "Found several configuration files for `{}` in {}, keep only one.",
+            name,
+            directory.display()
+        ))),
+    }
 }
 
-impl Environment {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Environment::Local => "local",
-            Environment::Production => "production",
+impl Settings {
+    // Catch settings that deserialize fine but could never work
+    pub fn validate(&self) -> Result<(), String> {
+        self.runtime.validate()?;
+        if self.database.max_connections == 0 {
+            return Err("database.max_connections must be at least 1.".into());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            return Err(format!(
+                "database.min_connections ({}) cannot exceed database.max_connections ({}).",
+                self.database.min_connections, self.database.max_connections
+            ));
+        }
+        let reservations = &self.reservations;
+        if !(1..=60).contains(&reservations.slot_minutes)
+            || reservations.default_duration_minutes < reservations.slot_minutes
+            || reservations.default_duration_minutes % reservations.slot_minutes != 0
+        {
+            return Err(format!(
+                "reservations.default_duration_minutes ({}) must be a whole number of \
+                 reservations.slot_minutes ({}), which must be between 1 and 60.",
+                reservations.default_duration_minutes, reservations.slot_minutes
+            ));
+        }
+        if self.promotions.manual_discount_approval_cents < 0 {
+            return Err("promotions.manual_discount_approval_cents cannot be negative.".into());
+        }
+        if !(0..=100).contains(&self.promotions.manual_discount_approval_percent) {
+            return Err(
+                "promotions.manual_discount_approval_percent must be between 0 and 100.".into(),
+            );
+        }
+        self.tax.validate()?;
+        if !(24..=80).contains(&self.receipts.width) {
+            return Err(format!(
+                "receipts.width must be between 24 and 80 characters, got {}.",
+                self.receipts.width
+            ));
+        }
+        self.printing.validate()?;
+        if let Some(otlp) = &self.telemetry.otlp {
+            if !(0.0..=1.0).contains(&otlp.sampling_ratio) {
+                return Err(format!(
+                    "telemetry.otlp.sampling_ratio must be between 0.0 and 1.0, got {}.",
+                    otlp.sampling_ratio
+                ));
+            }
         }
+        Ok(())
+    }
+}
+
+/// The runtime environment for our application, e.g. `local`, `staging` or `production`.
+/// Any name works as long as a configuration file with that name exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment(String);
+
+// Environment names end up in file names, so they are kept to a safe set of characters
+const MAX_ENVIRONMENT_LENGTH: usize = 64;
+
+impl Environment {
+    pub fn as_str(&self) -> &str {
+        &self.0
     }
 }
 
@@ -76,13 +702,20 @@ impl TryFrom<String> for Environment {
     type Error = String;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
-        match s.to_lowercase().as_str() {
-            "local" => Ok(Self::Local),
-            "production" => Ok(Self::Production),
-            other => Err(format!(
-                "{} is not a supported environment. Use either `local` or `production`.",
-                other
-            )),
+        let name = s.trim().to_lowercase();
+        let is_empty = name.is_empty();
+        let is_too_long = name.len() > MAX_ENVIRONMENT_LENGTH;
+        let contains_forbidden_characters = !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if is_empty || is_too_long || contains_forbidden_characters {
+            Err(format!(
+                "{} is not a valid environment. Use letters, digits, `-` or `_`.",
+                s
+            ))
+        } else {
+            Ok(Self(name))
         }
     }
 }
@@ -98,14 +731,300 @@ impl DatabaseSettings {
         PgConnectOptions::new()
             .host(&self.host)
             .username(&self.username)
-            .password(&self.password)
+            .password(self.password.expose_secret())
             .port(self.port)
             .ssl_mode(ssl_mode)
+            .application_name(&self.application_name)
     }
 
     pub fn with_db(&self) -> PgConnectOptions {
-        self.without_db()
+        let options = self
+            .without_db()
             .database(&self.database_name)
-            .log_statements(log::LevelFilter::Trace)
+            .log_statements(log::LevelFilter::Trace);
+        match self.statement_timeout_milliseconds {
+            Some(timeout) => options.options([("statement_timeout", format!("{}ms", timeout))]),
+            None => options,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::{
+        load_configuration, Environment, PrintingSettings, PromotionSettings, RuntimeSettings,
+    };
+    use claim::{assert_err, assert_ok};
+    use tempfile::TempDir;
+
+    const BASE_TOML: &str = r#"
+[application]
+port = 8000
+host = "127.0.0.1"
+readiness_timeout_milliseconds = 2000
+shutdown_timeout_seconds = 30
+shutdown_drain_delay_milliseconds = 0
+settings_reload_interval_milliseconds = 2000
+timezone = "UTC"
+
+[database]
+host = "localhost"
+port = 5432
+username = "postgres"
+password = "password"
+database_name = "restaurant"
+require_ssl = false
+migrate_on_startup = false
+max_connections = 10
+min_connections = 0
+acquire_timeout_milliseconds = 2000
+application_name = "restaurant"
+"#;
+
+    // A fresh directory per test so tests can run in parallel
+    // Removed along with its files when the returned `TempDir` is dropped
+    fn configuration_directory(files: &[(&str, &str)]) -> TempDir {
+        let directory = TempDir::new().unwrap();
+        for (name, contents) in files {
+            std::fs::write(directory.path().join(name), contents).unwrap();
+        }
+        directory
+    }
+
+    fn environment(name: &str) -> Environment {
+        Environment::try_from(name.to_string()).unwrap()
+    }
+
+    #[test]
+    fn any_well_formed_environment_name_is_accepted() {
+        for name in ["local", "staging", "Test", "preview-feature_42"] {
+            assert_ok!(Environment::try_from(name.to_string()));
+        }
+    }
+
+    #[test]
+    fn environment_names_that_are_not_safe_file_names_are_rejected() {
+        for name in ["", "../production", "pre view", "prod.yaml"] {
+            assert_err!(Environment::try_from(name.to_string()));
+        }
+    }
+
+    #[test]
+    fn toml_and_json_files_are_layered() {
+        let directory = configuration_directory(&[
+            ("base.toml", BASE_TOML),
+            ("staging.json", r#"{"application": {"port": 9000}}"#),
+        ]);
+
+        let settings = load_configuration(directory.path(), &environment("staging")).unwrap();
+
+        assert_eq!(settings.application.port, 9000);
+        assert_eq!(settings.database.database_name, "restaurant");
+    }
+
+    #[test]
+    fn runtime_settings_default_when_absent() {
+        let directory =
+            configuration_directory(&[("base.toml", BASE_TOML), ("staging.json", "{}")]);
+
+        let settings = load_configuration(directory.path(), &environment("staging")).unwrap();
+
+        assert_eq!(settings.runtime, RuntimeSettings::default());
+    }
+
+    #[test]
+    fn invalid_runtime_settings_are_an_error() {
+        let directory = configuration_directory(&[
+            ("base.toml", BASE_TOML),
+            (
+                "staging.json",
+                r#"{"runtime": {"preparation_time_multiplier": 0}}"#,
+            ),
+        ]);
+
+        assert_err!(load_configuration(
+            directory.path(),
+            &environment("staging")
+        ));
+    }
+
+    #[test]
+    fn preparation_times_are_scaled_to_whole_minutes() {
+        let settings = RuntimeSettings {
+            preparation_time_multiplier: 1.5,
+            ..RuntimeSettings::default()
+        };
+        assert_eq!(settings.scale_preparation_time(5), 8);
+
+        let settings = RuntimeSettings {
+            preparation_time_multiplier: 0.01,
+            ..RuntimeSettings::default()
+        };
+        assert_eq!(settings.scale_preparation_time(5), 1);
+    }
+
+    #[test]
+    fn a_missing_environment_file_is_an_error() {
+        let directory = configuration_directory(&[("base.toml", BASE_TOML)]);
+
+        assert_err!(load_configuration(directory.path(), &environment("stagin")));
+    }
+
+    #[test]
+    fn two_files_for_the_same_name_are_an_error() {
+        let directory = configuration_directory(&[
+            ("base.toml", BASE_TOML),
+            ("staging.json", "{}"),
+            ("staging.yaml", "{}"),
+        ]);
+
+        assert_err!(load_configuration(
+            directory.path(),
+            &environment("staging")
+        ));
+    }
+
+    #[test]
+    fn more_min_than_max_connections_is_rejected() {
+        let directory = configuration_directory(&[
+            ("base.toml", BASE_TOML),
+            ("staging.json", r#"{"database": {"min_connections": 20}}"#),
+        ]);
+
+        assert_err!(load_configuration(
+            directory.path(),
+            &environment("staging")
+        ));
+    }
+
+    #[test]
+    fn reservation_durations_must_be_whole_slots() {
+        let directory = configuration_directory(&[
+            ("base.toml", BASE_TOML),
+            (
+                "staging.json",
+                r#"{"reservations": {"slot_minutes": 15, "default_duration_minutes": 100}}"#,
+            ),
+        ]);
+
+        assert_err!(load_configuration(
+            directory.path(),
+            &environment("staging")
+        ));
+    }
+
+    #[test]
+    fn every_tax_class_needs_a_rate_once_rates_are_configured() {
+        let food_only = r#"{"tax": {"rates": [
+            {"name": "VAT", "class": "food", "percent": "20", "effective_from": "2026-01-01"}
+        ]}}"#;
+        let both = r#"{"tax": {"rates": [
+            {"name": "VAT", "class": "food", "percent": "20", "effective_from": "2026-01-01"},
+            {"name": "VAT", "class": "drinks", "percent": "20", "effective_from": "2026-01-01"}
+        ]}}"#;
+
+        for (environment_file, valid) in [("{}", true), (food_only, false), (both, true)] {
+            let directory = configuration_directory(&[
+                ("base.toml", BASE_TOML),
+                ("staging.json", environment_file),
+            ]);
+
+            assert_eq!(
+                load_configuration(directory.path(), &environment("staging")).is_ok(),
+                valid,
+                "{}",
+                environment_file
+            );
+        }
+    }
+
+    #[test]
+    fn tax_rates_must_be_percentages_and_not_overlap() {
+        for rates in [
+            r#"[{"name": "VAT", "class": "food", "percent": "-1", "effective_from": "2026-01-01"},
+                {"name": "VAT", "class": "drinks", "percent": "20", "effective_from": "2026-01-01"}]"#,
+            r#"[{"name": "VAT", "class": "food", "percent": "20", "effective_from": "2026-01-01"},
+                {"name": "VAT", "class": "drinks", "percent": "20", "effective_from": "2026-01-01"},
+                {"name": "IVA", "class": "drinks", "percent": "21", "effective_from": "2026-01-01"}]"#,
+        ] {
+            let directory = configuration_directory(&[
+                ("base.toml", BASE_TOML),
+                (
+                    "staging.json",
+                    &format!(r#"{{"tax": {{"rates": {}}}}}"#, rates),
+                ),
+            ]);
+
+            assert_err!(load_configuration(
+                directory.path(),
+                &environment("staging")
+            ));
+        }
+    }
+
+    #[test]
+    fn receipts_must_fit_a_paper_roll() {
+        let directory = configuration_directory(&[
+            ("base.toml", BASE_TOML),
+            ("staging.json", r#"{"receipts": {"width": 12}}"#),
+        ]);
+
+        assert_err!(load_configuration(
+            directory.path(),
+            &environment("staging")
+        ));
+    }
+
+    #[test]
+    fn printing_over_tcp_needs_a_printer_for_every_station() {
+        let some = r#"{"printing": {"sink": "tcp", "printers": {"grill": "10.0.0.5:9100"}}}"#;
+        let all = r#"{"printing": {"sink": "tcp", "printers": {
+            "grill": "10.0.0.5:9100", "fryer": "10.0.0.6:9100", "bar": "10.0.0.7:9100"
+        }}}"#;
+
+        for (environment_file, valid) in [(some, false), (all, true)] {
+            let directory = configuration_directory(&[
+                ("base.toml", BASE_TOML),
+                ("staging.json", environment_file),
+            ]);
+
+            assert_eq!(
+                load_configuration(directory.path(), &environment("staging")).is_ok(),
+                valid,
+                "{}",
+                environment_file
+            );
+        }
+    }
+
+    #[test]
+    fn failed_tickets_are_retried_later_and_later() {
+        let printing = PrintingSettings::default();
+
+        assert_eq!(printing.retry_delay(1), chrono::Duration::seconds(10));
+        assert_eq!(printing.retry_delay(3), chrono::Duration::seconds(40));
+        assert_eq!(printing.retry_delay(50), chrono::Duration::seconds(10240));
+        assert_eq!(printing.lease(), chrono::Duration::seconds(10));
+    }
+
+    #[test]
+    fn only_the_configured_manager_code_approves_discounts() {
+        let directory = configuration_directory(&[
+            ("base.toml", BASE_TOML),
+            (
+                "staging.json",
+                r#"{"promotions": {"manager_code": "4321"}}"#,
+            ),
+        ]);
+
+        let promotions = load_configuration(directory.path(), &environment("staging"))
+            .unwrap()
+            .promotions;
+        assert_eq!(promotions.manual_discount_approval_cents, 1000);
+        assert_eq!(promotions.manual_discount_approval_percent, 10);
+        assert!(promotions.approves(Some("4321")));
+        assert!(!promotions.approves(Some("1234")));
+        assert!(!promotions.approves(None));
+        assert!(!PromotionSettings::default().approves(Some("")));
     }
 }
@@ -0,0 +1,340 @@
+use crate::configuration::TaxSettings;
+use crate::domain::discount::{Adjustment, DiscountKind, DiscountLine};
+use crate::domain::promotion::Promotion;
+use crate::domain::tax::{rate_for, tax_class, tax_lines, Dining, TaxLine};
+use crate::domain::DatabaseResponse;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+// One order on a bill, charged at the price it had when it was placed
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct BillLine {
+    pub item: String,
+    pub variant: String,
+    pub modifiers: Vec<String>,
+    pub quantity: i32,
+    pub unit_price_cents: i64,
+    pub total_cents: i64,
+    // What a bundle was made up of, empty for anything else
+    pub components: Vec<BillComponent>,
+    pub takeaway: bool,
+    // Decides which promotions the line counts towards and the tax rate it is charged
+    #[serde(skip)]
+    pub placed_at: DateTime<Utc>,
+    // The line's share of the bill's discounts, tax is charged on what is left
+    #[serde(skip)]
+    pub discount_cents: i64,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct BillComponent {
+    pub item: String,
+    pub variant: String,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Bill {
+    pub table_no: i32,
+    pub lines: Vec<BillLine>,
+    // What the lines add up to before discounts
+    pub subtotal_cents: i64,
+    pub discounts: Vec<DiscountLine>,
+    // Per rate, empty when no tax is configured
+    pub tax: Vec<TaxLine>,
+    // Part of `total_cents` either way, added to it when prices do not include tax
+    pub tax_cents: i64,
+    pub prices_include_tax: bool,
+    pub total_cents: i64,
+}
+
+/*
+ * Spreads a discount over the lines `covers` picks, in proportion to what is left of each,
+ * and returns how much of it came off. Shares are whole cents adding up to the discount.
+ */
+fn spread(lines: &mut [BillLine], amount_cents: i64, covers: impl Fn(&BillLine) -> bool) -> i64 {
+    let covered: Vec<&mut BillLine> = lines.iter_mut().filter(|line| covers(line)).collect();
+    let left: i64 = covered
+        .iter()
+        .map(|line| line.total_cents - line.discount_cents)
+        .sum();
+    let amount_cents = amount_cents.min(left);
+    if amount_cents <= 0 {
+        return 0;
+    }
+
+    let (mut counted, mut given) = (0i128, 0i128);
+    for line in covered {
+        counted += (line.total_cents - line.discount_cents) as i128;
+        let share = counted * amount_cents as i128 / left as i128;
+        line.discount_cents += (share - given) as i64;
+        given = share;
+    }
+    amount_cents
+}
+
+impl Bill {
+    /*
+     * Lines keep the order the orders were placed in.
+     * Components of a bundle are listed under the bundle's line rather than charged separately.
+     */
+    pub fn new(table_no: i32, mut orders: Vec<DatabaseResponse>) -> Self {
+        orders.sort_by_key(|order| order.placed_at);
+        let (components, orders): (Vec<DatabaseResponse>, Vec<DatabaseResponse>) = orders
+            .into_iter()
+            .partition(|order| order.parent_id.is_some());
+
+        let lines: Vec<BillLine> = orders
+            .into_iter()
+            .map(|order| BillLine {
+                total_cents: order.unit_price_cents * order.quantity as i64,
+                components: components
+                    .iter()
+                    .filter(|component| component.parent_id == Some(order.id))
+                    .map(|component| BillComponent {
+                        item: component.item.clone(),
+                        variant: component.variant.clone(),
+                    })
+                    .collect(),
+                item: order.item,
+                variant: order.variant,
+                modifiers: order.modifiers,
+                quantity: order.quantity,
+                unit_price_cents: order.unit_price_cents,
+                takeaway: order.takeaway,
+                placed_at: order.placed_at,
+                discount_cents: 0,
+            })
+            .collect();
+        let total_cents = lines.iter().map(|line| line.total_cents).sum();
+
+        Self {
+            table_no,
+            lines,
+            subtotal_cents: total_cents,
+            discounts: vec![],
+            tax: vec![],
+            tax_cents: 0,
+            prices_include_tax: false,
+            total_cents,
+        }
+    }
+
+    /*
+     * Takes off the promotions the lines qualify for, then the party's vouchers and manual
+     * discounts in the order they were given. Percentages apply to what is left after the
+     * discounts before them, and a bill never goes below zero.
+     */
+    pub fn with_discounts(
+        mut self,
+        promotions: &[Promotion],
+        adjustments: &[Adjustment],
+        timezone: Tz,
+    ) -> Self {
+        let mut total = self.subtotal_cents;
+        for promotion in promotions {
+            let discount_cents = promotion.discount(&self.lines, timezone);
+            let amount_cents = spread(&mut self.lines, discount_cents, |line| {
+                promotion.covers(line, timezone)
+            });
+            if amount_cents > 0 {
+                total -= amount_cents;
+                self.discounts.push(DiscountLine {
+                    kind: DiscountKind::Promotion,
+                    name: promotion.name.to_string(),
+                    amount_cents,
+                });
+            }
+        }
+        for adjustment in adjustments {
+            let amount_cents = spread(&mut self.lines, adjustment.off.of(total), |_| true);
+            total -= amount_cents;
+            self.discounts.push(DiscountLine {
+                kind: adjustment.kind,
+                name: adjustment.name.clone(),
+                amount_cents,
+            });
+        }
+        self.total_cents = total;
+        self
+    }
+
+    // What is left after discounts, before any tax is added
+    pub fn discounted_cents(&self) -> i64 {
+        self.subtotal_cents
+            - self
+                .discounts
+                .iter()
+                .map(|discount| discount.amount_cents)
+                .sum::<i64>()
+    }
+
+    /*
+     * Charges every line at the rate for its tax class and whether it was taken away, as it
+     * stood on the day it was ordered. Applied after discounts, which lower what is taxed.
+     */
+    pub fn with_tax(mut self, settings: &TaxSettings, timezone: Tz) -> Self {
+        let charges: Vec<_> = self
+            .lines
+            .iter()
+            .filter_map(|line| {
+                let class = tax_class(&line.item)?;
+                let date = line.placed_at.with_timezone(&timezone).date_naive();
+                let rate = rate_for(&settings.rates, class, Dining::of(line.takeaway), date)?;
+                Some((rate, line.total_cents - line.discount_cents))
+            })
+            .collect();
+
+        self.tax = tax_lines(&charges, settings.prices_include_tax, settings.rounding);
+        self.tax_cents = self.tax.iter().map(|line| line.tax_cents).sum();
+        self.prices_include_tax = settings.prices_include_tax;
+        if !settings.prices_include_tax {
+            self.total_cents += self.tax_cents;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::TaxSettings;
+    use crate::domain::{
+        Adjustment, Bill, DatabaseResponse, DiscountKind, Off, TaxClass, TaxRate, TaxRounding,
+    };
+    use chrono::{Duration, NaiveDate, Utc};
+    use chrono_tz::Tz;
+    use uuid::Uuid;
+
+    fn order(
+        item: &str,
+        quantity: i32,
+        unit_price_cents: i64,
+        minutes_ago: i64,
+    ) -> DatabaseResponse {
+        DatabaseResponse {
+            id: Uuid::new_v4(),
+            table_no: 1,
+            item: item.to_string(),
+            variant: "regular".to_string(),
+            quantity,
+            preparation_time: 10,
+            modifiers: vec![],
+            note: None,
+            unit_price_cents,
+            parent_id: None,
+            session_id: None,
+            course: "main".to_string(),
+            fired_at: None,
+            takeaway: false,
+            placed_at: Utc::now() - Duration::minutes(minutes_ago),
+        }
+    }
+
+    #[test]
+    fn lines_are_charged_per_quantity_and_summed() {
+        let bill = Bill::new(
+            1,
+            vec![order("hamburger", 2, 950, 5), order("cola", 1, 250, 3)],
+        );
+
+        assert_eq!(bill.lines[0].total_cents, 1900);
+        assert_eq!(bill.lines[1].total_cents, 250);
+        assert_eq!(bill.total_cents, 2150);
+    }
+
+    #[test]
+    fn lines_are_listed_in_the_order_they_were_placed() {
+        let bill = Bill::new(
+            1,
+            vec![order("cola", 1, 250, 1), order("fries", 1, 350, 10)],
+        );
+
+        let items: Vec<&str> = bill.lines.iter().map(|l| l.item.as_str()).collect();
+        assert_eq!(items, vec!["fries", "cola"]);
+    }
+
+    #[test]
+    fn bundle_components_are_listed_under_the_bundle() {
+        let meal = order("burger meal", 1, 1200, 5);
+        let mut fries = order("fries", 1, 0, 5);
+        fries.parent_id = Some(meal.id);
+
+        let bill = Bill::new(1, vec![fries, meal, order("cola", 1, 250, 3)]);
+
+        assert_eq!(bill.lines.len(), 2);
+        assert_eq!(bill.lines[0].components[0].item, "fries");
+        assert_eq!(bill.total_cents, 1450);
+    }
+
+    #[test]
+    fn vouchers_and_manual_discounts_apply_to_what_is_left() {
+        let adjustments = vec![
+            Adjustment {
+                kind: DiscountKind::Voucher,
+                name: "TENOFF".to_string(),
+                off: Off::Percent(10),
+            },
+            Adjustment {
+                kind: DiscountKind::Manual,
+                name: "cold fries".to_string(),
+                off: Off::Cents(5000),
+            },
+        ];
+
+        let bill = Bill::new(1, vec![order("hamburger", 2, 1000, 5)]).with_discounts(
+            &[],
+            &adjustments,
+            Tz::UTC,
+        );
+
+        assert_eq!(bill.subtotal_cents, 2000);
+        assert_eq!(bill.discounts[0].amount_cents, 200);
+        // Capped at what was left of the bill
+        assert_eq!(bill.discounts[1].amount_cents, 1800);
+        assert_eq!(bill.total_cents, 0);
+    }
+
+    #[test]
+    fn tax_is_charged_on_each_lines_share_of_what_is_left_after_discounts() {
+        let adjustments = vec![Adjustment {
+            kind: DiscountKind::Manual,
+            name: "cold fries".to_string(),
+            off: Off::Cents(300),
+        }];
+        let tax = TaxSettings {
+            jurisdiction: "ES".to_string(),
+            prices_include_tax: false,
+            rounding: TaxRounding::default(),
+            rates: vec![TaxClass::Food, TaxClass::Drinks]
+                .into_iter()
+                .zip(["10", "21"])
+                .map(|(class, percent)| TaxRate {
+                    name: format!("IVA {}", percent),
+                    class,
+                    dining: None,
+                    percent: percent.parse().unwrap(),
+                    effective_from: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                })
+                .collect(),
+        };
+
+        let bill = Bill::new(
+            1,
+            vec![order("hamburger", 2, 1000, 5), order("cola", 2, 500, 3)],
+        )
+        .with_discounts(&[], &adjustments, Tz::UTC)
+        .with_tax(&tax, Tz::UTC);
+
+        // Two thirds of the discount come off the hamburgers
+        assert_eq!(bill.lines[0].discount_cents, 200);
+        assert_eq!(bill.lines[1].discount_cents, 100);
+        assert_eq!(bill.tax[0].tax_cents, 180);
+        assert_eq!(bill.tax[1].tax_cents, 189);
+        assert_eq!(bill.total_cents, 2700 + 180 + 189);
+    }
+
+    #[test]
+    fn an_empty_table_owes_nothing() {
+        assert_eq!(Bill::new(1, vec![]).total_cents, 0);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::domain::item::Item;
+use crate::domain::tax::TaxClass;
+use serde::Serialize;
+
+/*
+ * Bundles are sold at their own price and expand into their components when ordered,
+ * so the kitchen still sees a hamburger, fries and a cola rather than a "burger meal".
+ * Components are written as they would be ordered, e.g. `cola:large`, which the tests
+ * below check.
+ */
+#[derive(Serialize, Debug)]
+pub struct Bundle {
+    pub name: &'static str,
+    pub price_cents: i64,
+    // A bundle is charged as one line, at the rates of its main component
+    pub tax_class: TaxClass,
+    pub slots: &'static [BundleSlot],
+}
+
+// One component of a bundle, e.g. the drink, and what it may be swapped for
+#[derive(Serialize, Debug)]
+pub struct BundleSlot {
+    pub name: &'static str,
+    pub default_item: &'static str,
+    pub substitutions: &'static [Substitution],
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Substitution {
+    pub item: &'static str,
+    // Added to the bundle's price, e.g. for upsizing the drink
+    pub price_delta_cents: i64,
+}
+
+// The item chosen for a slot of an ordered bundle
+#[derive(Debug)]
+pub struct BundleComponent {
+    pub slot: &'static str,
+    pub item: Item,
+    pub price_delta_cents: i64,
+}
+
+pub static BUNDLES: [Bundle; 1] = [Bundle {
+    name: "burger meal",
+    price_cents: 1200,
+    tax_class: TaxClass::Food,
+    slots: &[
+        BundleSlot {
+            name: "main",
+            default_item: "hamburger",
+            substitutions: &[Substitution {
+                item: "hamburger:double",
+                price_delta_cents: 250,
+            }],
+        },
+        BundleSlot {
+            name: "side",
+            default_item: "fries",
+            substitutions: &[],
+        },
+        BundleSlot {
+            name: "drink",
+            default_item: "cola",
+            substitutions: &[
+                Substitution {
+                    item: "cola:large",
+                    price_delta_cents: 50,
+                },
+                Substitution {
+                    item: "water",
+                    price_delta_cents: 0,
+                },
+            ],
+        },
+    ],
+}];
+
+pub fn bundle(name: &str) -> Option<&'static Bundle> {
+    BUNDLES.iter().find(|bundle| bundle.name == name)
+}
+
+impl Bundle {
+    /*
+     * Picks the item for every slot, given substitutions as `(slot, item)` pairs.
+     * Slots without a substitution get their default item. Each slot may be substituted
+     * once, and only with one of its allowed substitutions.
+     */
+    pub fn components(
+        &self,
+        substitutions: &[(String, String)],
+    ) -> Result<Vec<BundleComponent>, String> {
+        for (slot, _) in substitutions {
+            if !self.slots.iter().any(|s| s.name == slot) {
+                return Err(format!("{} has no {} to substitute.", self.name, slot));
+            }
+            if substitutions.iter().filter(|(s, _)| s == slot).count() > 1 {
+                return Err(format!("{} was substituted more than once.", slot));
+            }
+        }
+
+        self.slots
+            .iter()
+            .map(|slot| {
+                let (item, price_delta_cents) =
+                    match substitutions.iter().find(|(s, _)| s == slot.name) {
+                        None => (slot.default_item, 0),
+                        Some((_, item)) if item == slot.default_item => (slot.default_item, 0),
+                        Some((_, item)) => slot
+                            .substitutions
+                            .iter()
+                            .find(|substitution| substitution.item == item)
+                            .map(|substitution| (substitution.item, substitution.price_delta_cents))
+                            .ok_or(format!(
+                                "{} cannot be the {} of a {}.",
+                                item, slot.name, self.name
+                            ))?,
+                    };
+                Ok(BundleComponent {
+                    slot: slot.name,
+                    item: Item::parse(item.to_string())?,
+                    price_delta_cents,
+                })
+            })
+            .collect()
+    }
+
+    // Price of a single bundle including any substitutions that cost extra
+    pub fn unit_price_cents(&self, components: &[BundleComponent]) -> i64 {
+        self.price_cents
+            + components
+                .iter()
+                .map(|component| component.price_delta_cents)
+                .sum::<i64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::bundle::{bundle, BUNDLES};
+    use crate::domain::{Item, MENU_ITEMS};
+    use claim::{assert_err, assert_ok};
+
+    fn substitutions(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(slot, item)| (slot.to_string(), item.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn every_component_of_every_bundle_can_be_ordered() {
+        for bundle in BUNDLES.iter() {
+            for slot in bundle.slots {
+                assert_ok!(Item::parse(slot.default_item.to_string()));
+                for substitution in slot.substitutions {
+                    assert_ok!(Item::parse(substitution.item.to_string()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bundles_are_not_menu_items() {
+        assert!(BUNDLES
+            .iter()
+            .all(|bundle| !MENU_ITEMS.contains(&bundle.name)));
+    }
+
+    #[test]
+    fn slots_without_substitutions_get_their_default_item() {
+        let meal = bundle("burger meal").unwrap();
+        let components = meal.components(&[]).unwrap();
+
+        let items: Vec<(&str, &str)> = components
+            .iter()
+            .map(|c| (c.item.as_ref(), c.item.variant()))
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                ("hamburger", "single"),
+                ("fries", "regular"),
+                ("cola", "medium")
+            ]
+        );
+        assert_eq!(meal.unit_price_cents(&components), 1200);
+    }
+
+    #[test]
+    fn substitutions_replace_the_default_and_add_to_the_price() {
+        let meal = bundle("burger meal").unwrap();
+        let components = meal
+            .components(&substitutions(&[
+                ("drink", "cola:large"),
+                ("main", "hamburger:double"),
+            ]))
+            .unwrap();
+
+        assert_eq!(components[0].item.variant(), "double");
+        assert_eq!(components[2].item.variant(), "large");
+        assert_eq!(meal.unit_price_cents(&components), 1200 + 250 + 50);
+    }
+
+    #[test]
+    fn substitutions_that_are_not_allowed_are_rejected() {
+        let meal = bundle("burger meal").unwrap();
+        for pairs in [
+            vec![("drink", "fries")],
+            vec![("side", "water")],
+            vec![("dessert", "cola")],
+            vec![("drink", "water"), ("drink", "cola:large")],
+        ] {
+            assert_err!(meal.components(&substitutions(&pairs)));
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::domain::item::FORBIDDEN_CHARACTERS;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Who a booking or a waitlist entry is for, e.g. "Ana García"
+#[derive(Debug)]
+pub struct CustomerName(String);
+
+const MAX_NAME_LENGTH: usize = 100;
+
+impl CustomerName {
+    // Returns an instance of `CustomerName` if the input satisfies validation
+    pub fn parse(s: String) -> Result<CustomerName, String> {
+        let is_empty_or_whitespace = s.trim().is_empty();
+        let is_too_long = s.graphemes(true).count() > MAX_NAME_LENGTH;
+        let contains_forbidden_characters = s.chars().any(|g| FORBIDDEN_CHARACTERS.contains(&g));
+
+        if is_empty_or_whitespace || contains_forbidden_characters {
+            Err("The name is empty or contains forbidden characters.".to_string())
+        } else if is_too_long {
+            Err(format!(
+                "Names can be at most {} characters long.",
+                MAX_NAME_LENGTH
+            ))
+        } else {
+            Ok(Self(s.trim().to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for CustomerName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// A number to call or text when the table is ready, e.g. "+34 600 123 456"
+#[derive(Debug)]
+pub struct Phone(String);
+
+const MIN_PHONE_DIGITS: usize = 6;
+const MAX_PHONE_DIGITS: usize = 15;
+
+impl Phone {
+    // Digits with optional spaces, dashes and a leading `+`
+    pub fn parse(s: String) -> Result<Phone, String> {
+        let number = s.trim();
+        let digits = number.chars().filter(char::is_ascii_digit).count();
+        let well_formed = number
+            .strip_prefix('+')
+            .unwrap_or(number)
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ' ' || c == '-');
+
+        if well_formed && (MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&digits) {
+            Ok(Self(number.to_string()))
+        } else {
+            // The number itself is kept out of the message, it would end up in logs
+            Err("The phone number is not valid.".to_string())
+        }
+    }
+}
+
+impl AsRef<str> for Phone {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{CustomerName, Phone};
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    fn names_are_trimmed() {
+        let name = CustomerName::parse("  Ana García ".to_string()).unwrap();
+        assert_eq!(name.as_ref(), "Ana García");
+    }
+
+    #[test]
+    fn empty_names_are_rejected() {
+        assert_err!(CustomerName::parse(" ".to_string()));
+        assert_err!(CustomerName::parse("<script>".to_string()));
+    }
+
+    #[test]
+    fn common_phone_formats_are_accepted() {
+        for number in ["+34 600 123 456", "020-7946-0958", "600123456"] {
+            assert_ok!(Phone::parse(number.to_string()));
+        }
+    }
+
+    #[test]
+    fn anything_but_a_number_is_rejected() {
+        for number in ["", "12345", "call me", "+34 600 123 456 789 012", "600+123"] {
+            assert_err!(Phone::parse(number.to_string()));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+/*
+ * Which part of the meal an order line belongs to. Lines of a later course can be held
+ * back and fired when the table is ready for them, so mains do not arrive with starters.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Course {
+    Starter,
+    Main,
+    Dessert,
+    Drinks,
+}
+
+impl Course {
+    pub const ALL: [Course; 4] = [
+        Course::Starter,
+        Course::Main,
+        Course::Dessert,
+        Course::Drinks,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Course::Starter => "starter",
+            Course::Main => "main",
+            Course::Dessert => "dessert",
+            Course::Drinks => "drinks",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Course, String> {
+        Course::ALL
+            .into_iter()
+            .find(|course| course.as_str() == s.trim())
+            .ok_or_else(|| {
+                format!(
+                    "{} is not a course, expected starter, main, dessert or drinks.",
+                    s
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Course;
+    use claim::assert_err;
+
+    #[test]
+    fn courses_are_parsed_from_their_names() {
+        for course in Course::ALL {
+            assert_eq!(Course::parse(course.as_str()), Ok(course));
+        }
+        assert_err!(Course::parse("appetiser"));
+    }
+}
@@ -9,8 +9,20 @@ pub struct DatabaseResponse {
     pub id: Uuid,
     pub table_no: i32,
     pub item: String,
+    pub variant: String,
     pub quantity: i32,
     pub preparation_time: i32,
+    pub modifiers: Vec<String>,
+    pub note: Option<String>,
+    pub unit_price_cents: i64,
+    // Set on the components of a bundle, pointing at the bundle's line
+    pub parent_id: Option<Uuid>,
+    // The session of the party that placed the order, if anyone was seated at the table
+    pub session_id: Option<Uuid>,
+    pub course: String,
+    // When the line was sent to the kitchen, `None` while it is held
+    pub fired_at: Option<chrono::DateTime<Utc>>,
+    pub takeaway: bool,
     #[serde(skip_serializing, skip_deserializing)]
     pub placed_at: chrono::DateTime<Utc>,
 }
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+// Where a discount on a bill came from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscountKind {
+    // A rule the bill qualified for on its own, e.g. 2-for-1 fries
+    Promotion,
+    Voucher,
+    // Given by staff, e.g. for a cold dish
+    Manual,
+}
+
+impl DiscountKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscountKind::Promotion => "promotion",
+            DiscountKind::Voucher => "voucher",
+            DiscountKind::Manual => "manual",
+        }
+    }
+}
+
+// How much a voucher or manual discount takes off
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Off {
+    Percent(i64),
+    Cents(i64),
+}
+
+impl Off {
+    // Exactly one of a percentage or an amount
+    pub fn parse(percent_off: Option<i64>, amount_off_cents: Option<i64>) -> Result<Off, String> {
+        match (percent_off, amount_off_cents) {
+            (Some(percent), None) if (1..=100).contains(&percent) => Ok(Off::Percent(percent)),
+            (Some(percent), None) => Err(format!(
+                "A discount is between 1 and 100 percent, not {}.",
+                percent
+            )),
+            (None, Some(cents)) if cents > 0 => Ok(Off::Cents(cents)),
+            (None, Some(_)) => Err("A discount has to take something off.".to_string()),
+            _ => Err("A discount is either a percentage or an amount.".to_string()),
+        }
+    }
+
+    // What comes off `cents`, percentages rounded to the nearest cent, never more than `cents`
+    pub fn of(&self, cents: i64) -> i64 {
+        match *self {
+            Off::Percent(percent) => (cents * percent + 50) / 100,
+            Off::Cents(off) => off.min(cents),
+        }
+    }
+
+    pub fn percent(&self) -> Option<i64> {
+        match *self {
+            Off::Percent(percent) => Some(percent),
+            Off::Cents(_) => None,
+        }
+    }
+
+    pub fn cents(&self) -> Option<i64> {
+        match *self {
+            Off::Percent(_) => None,
+            Off::Cents(cents) => Some(cents),
+        }
+    }
+}
+
+// A voucher or manual discount given to a party, worked out when their bill is prepared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adjustment {
+    pub kind: DiscountKind,
+    // The voucher's code or the reason staff gave
+    pub name: String,
+    pub off: Off,
+}
+
+// A discount shown on a bill as a line of its own
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DiscountLine {
+    pub kind: DiscountKind,
+    pub name: String,
+    pub amount_cents: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Off;
+    use claim::assert_err;
+
+    #[test]
+    fn a_discount_is_a_percentage_or_an_amount() {
+        assert_eq!(Off::parse(Some(10), None), Ok(Off::Percent(10)));
+        assert_eq!(Off::parse(None, Some(500)), Ok(Off::Cents(500)));
+        assert_err!(Off::parse(Some(10), Some(500)));
+        assert_err!(Off::parse(None, None));
+        assert_err!(Off::parse(Some(0), None));
+        assert_err!(Off::parse(Some(101), None));
+        assert_err!(Off::parse(None, Some(-5)));
+    }
+
+    #[test]
+    fn percentages_are_rounded_to_the_nearest_cent() {
+        assert_eq!(Off::Percent(10).of(1855), 186);
+        assert_eq!(Off::Percent(10).of(1854), 185);
+    }
+
+    #[test]
+    fn amounts_never_take_off_more_than_there_is() {
+        assert_eq!(Off::Cents(500).of(300), 300);
+    }
+}
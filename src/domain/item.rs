@@ -1,5 +1,11 @@
+use crate::domain::menu::menu_item;
+
+// A menu item together with the variant ordered, e.g. `cola:large`
 #[derive(Debug)]
-pub struct Item(String);
+pub struct Item {
+    name: String,
+    variant: String,
+}
 
 /*
  * We need to ensure what a valid item it in this module
@@ -10,20 +16,38 @@ pub struct Item(String);
  */
 
 // Valid menu items used only in this module
-const VALID_ITEMS: [&str; 4] = ["hamburger", "fries", "cola", "water"];
+const VALID_ITEMS: [&str; 5] = ["hamburger", "fries", "cola", "water", "pancakes"];
+
+// Separates an item from its variant, e.g. `hamburger:double`
+pub const VARIANT_SEPARATOR: char = ':';
+
+// Characters that have no place in anything a waiter types in
+pub(crate) const FORBIDDEN_CHARACTERS: [char; 9] = ['/', '(', ')', '"', '<', '>', '\\', '{', '}'];
+
+// Splits `cola:large` into the item and the variant, which is `None` when not given
+pub fn split_variant(s: &str) -> (&str, Option<&str>) {
+    match s.split_once(VARIANT_SEPARATOR) {
+        Some((name, variant)) => (name, Some(variant)),
+        None => (s, None),
+    }
+}
 
 impl Item {
-    // Returns an instance of `Item` if the input satisfies validation
+    /*
+     * Returns an instance of `Item` if the input satisfies validation.
+     * The variant is optional and defaults to the item's default variant, so `cola`
+     * is the same order as `cola:medium`.
+     */
     pub fn parse(s: String) -> Result<Item, String> {
         // Checking if empty
         let is_empty_or_whitespace = s.trim().is_empty();
 
         // Checking for forbidden characters
-        let forbidden_characters = ['/', '(', ')', '"', '<', '>', '\\', '{', '}'];
-        let contains_forbidden_characters = s.chars().any(|g| forbidden_characters.contains(&g));
+        let contains_forbidden_characters = s.chars().any(|g| FORBIDDEN_CHARACTERS.contains(&g));
 
         // Checking if the item is in the valid item list
-        let valid_item = VALID_ITEMS.iter().any(|&g| g == s);
+        let (name, variant) = split_variant(&s);
+        let valid_item = VALID_ITEMS.contains(&name);
 
         if is_empty_or_whitespace || contains_forbidden_characters {
             Err(format!(
@@ -31,16 +55,30 @@ impl Item {
                 s
             ))
         } else if !valid_item {
-            Err(format!("{} item is not in the valid item list.", s))
+            Err(format!("{} item is not in the valid item list.", name))
         } else {
-            Ok(Self(s))
+            // Every valid item is on the menu, which the menu tests check
+            let menu_item = menu_item(name).ok_or(format!("{} item is not on the menu.", name))?;
+            let variant = variant.unwrap_or(menu_item.default_variant);
+            match menu_item.variant(variant) {
+                Some(variant) => Ok(Self {
+                    name: name.to_string(),
+                    variant: variant.name.to_string(),
+                }),
+                None => Err(format!("{} is not a variant of {}.", variant, name)),
+            }
         }
     }
+
+    pub fn variant(&self) -> &str {
+        &self.variant
+    }
 }
 
+// The item without its variant, so every size of an item is counted and filtered together
 impl AsRef<str> for Item {
     fn as_ref(&self) -> &str {
-        &self.0
+        &self.name
     }
 }
 
@@ -83,6 +121,32 @@ mod tests {
         assert!(VALID_ITEMS.iter().all(|item| MENU_ITEMS.contains(item)));
     }
 
+    #[test]
+    fn an_item_without_a_variant_gets_the_default_variant() {
+        let item = Item::parse("cola".to_string()).unwrap();
+        assert_eq!(item.as_ref(), "cola");
+        assert_eq!(item.variant(), "medium");
+    }
+
+    #[test]
+    fn an_item_with_one_of_its_variants_is_parsed_successfully() {
+        let item = Item::parse("hamburger:double".to_string()).unwrap();
+        assert_eq!(item.as_ref(), "hamburger");
+        assert_eq!(item.variant(), "double");
+    }
+
+    #[test]
+    fn variants_of_other_items_are_rejected() {
+        for item in &[
+            "hamburger:large",
+            "water:double",
+            "cola:",
+            "cola:large:large",
+        ] {
+            assert_err!(Item::parse(item.to_string()));
+        }
+    }
+
     #[test]
     fn whitespace_only_items_are_rejected() {
         let item = " ".to_string();
@@ -0,0 +1,514 @@
+use crate::domain::course::Course;
+use crate::domain::schedule::{time, Schedule, EVERY_DAY};
+use crate::domain::tax::TaxClass;
+use crate::domain::ticket::Station;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/*
+ * The menu: what can be ordered, what it costs and how it can be changed.
+ * Prices are kept in cents so bills add up exactly.
+ * Every name here must also be in `MENU_ITEMS`, which the tests below check.
+ */
+#[derive(Serialize, Debug)]
+pub struct MenuItem {
+    pub name: &'static str,
+    // Ordered when no variant is asked for, must be one of `variants`
+    pub default_variant: &'static str,
+    // Every item has at least one variant, items sold one way have a single `regular`
+    pub variants: &'static [Variant],
+    pub modifier_groups: &'static [ModifierGroup],
+    // When the item can be ordered, at any time when `None`
+    pub schedule: Option<Schedule>,
+    // The course the item is served with unless the waiter says otherwise
+    pub course: Course,
+    // What kind of item it is, e.g. `drinks`, for promotions covering a whole category
+    pub category: &'static str,
+    // Which tax rates the item is charged
+    pub tax_class: TaxClass,
+    // Where in the kitchen it is made, and so which printer its tickets come out of
+    pub station: Station,
+}
+
+// A size or style of an item, e.g. a large cola, with its own price and preparation time
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Variant {
+    pub name: &'static str,
+    pub price_cents: i64,
+    pub preparation_minutes: Range<i32>,
+    // Prices that replace `price_cents` while their schedule applies, the first match wins
+    pub price_overrides: &'static [PriceOverride],
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct PriceOverride {
+    pub name: &'static str,
+    pub price_cents: i64,
+    pub schedule: Schedule,
+}
+
+const BREAKFAST: Schedule = Schedule {
+    days: Cow::Borrowed(&EVERY_DAY),
+    from: time(6, 0),
+    until: time(11, 0),
+};
+
+const HAPPY_HOUR: Schedule = Schedule {
+    days: Cow::Borrowed(&EVERY_DAY),
+    from: time(17, 0),
+    until: time(19, 0),
+};
+
+// A set of related choices, e.g. toppings, of which a customer picks between `min` and `max`
+#[derive(Serialize, Debug)]
+pub struct ModifierGroup {
+    pub name: &'static str,
+    // Optional groups may also be skipped entirely
+    pub required: bool,
+    pub min_choices: usize,
+    pub max_choices: usize,
+    pub modifiers: &'static [Modifier],
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Modifier {
+    pub name: &'static str,
+    // Added to the item's price, zero for requests like "no onions"
+    pub price_delta_cents: i64,
+}
+
+pub static MENU: [MenuItem; 5] = [
+    MenuItem {
+        name: "hamburger",
+        default_variant: "single",
+        schedule: None,
+        course: Course::Main,
+        category: "burgers",
+        tax_class: TaxClass::Food,
+        station: Station::Grill,
+        variants: &[
+            Variant {
+                name: "single",
+                price_cents: 850,
+                preparation_minutes: 5..15,
+                price_overrides: &[],
+            },
+            Variant {
+                name: "double",
+                price_cents: 1150,
+                preparation_minutes: 8..18,
+                price_overrides: &[],
+            },
+        ],
+        modifier_groups: &[
+            ModifierGroup {
+                name: "remove",
+                required: false,
+                min_choices: 1,
+                max_choices: 3,
+                modifiers: &[
+                    Modifier {
+                        name: "no onions",
+                        price_delta_cents: 0,
+                    },
+                    Modifier {
+                        name: "no pickles",
+                        price_delta_cents: 0,
+                    },
+                    Modifier {
+                        name: "no sauce",
+                        price_delta_cents: 0,
+                    },
+                ],
+            },
+            ModifierGroup {
+                name: "extras",
+                required: false,
+                min_choices: 1,
+                max_choices: 2,
+                modifiers: &[
+                    Modifier {
+                        name: "extra cheese",
+                        price_delta_cents: 100,
+                    },
+                    Modifier {
+                        name: "bacon",
+                        price_delta_cents: 150,
+                    },
+                ],
+            },
+        ],
+    },
+    MenuItem {
+        name: "fries",
+        default_variant: "regular",
+        schedule: None,
+        course: Course::Main,
+        category: "sides",
+        tax_class: TaxClass::Food,
+        station: Station::Fryer,
+        variants: &[Variant {
+            name: "regular",
+            price_cents: 350,
+            preparation_minutes: 5..15,
+            price_overrides: &[],
+        }],
+        modifier_groups: &[ModifierGroup {
+            name: "dip",
+            required: false,
+            min_choices: 1,
+            max_choices: 1,
+            modifiers: &[
+                Modifier {
+                    name: "ketchup",
+                    price_delta_cents: 0,
+                },
+                Modifier {
+                    name: "mayonnaise",
+                    price_delta_cents: 0,
+                },
+                Modifier {
+                    name: "cheese sauce",
+                    price_delta_cents: 80,
+                },
+            ],
+        }],
+    },
+    MenuItem {
+        name: "cola",
+        default_variant: "medium",
+        schedule: None,
+        course: Course::Drinks,
+        category: "drinks",
+        tax_class: TaxClass::Drinks,
+        station: Station::Bar,
+        variants: &[
+            Variant {
+                name: "small",
+                price_cents: 200,
+                preparation_minutes: 4..10,
+                price_overrides: &[PriceOverride {
+                    name: "happy hour",
+                    price_cents: 150,
+                    schedule: HAPPY_HOUR,
+                }],
+            },
+            Variant {
+                name: "medium",
+                price_cents: 250,
+                preparation_minutes: 5..15,
+                price_overrides: &[PriceOverride {
+                    name: "happy hour",
+                    price_cents: 180,
+                    schedule: HAPPY_HOUR,
+                }],
+            },
+            Variant {
+                name: "large",
+                price_cents: 300,
+                preparation_minutes: 6..16,
+                price_overrides: &[PriceOverride {
+                    name: "happy hour",
+                    price_cents: 220,
+                    schedule: HAPPY_HOUR,
+                }],
+            },
+        ],
+        modifier_groups: &[ModifierGroup {
+            name: "ice",
+            required: false,
+            min_choices: 1,
+            max_choices: 1,
+            modifiers: &[Modifier {
+                name: "no ice",
+                price_delta_cents: 0,
+            }],
+        }],
+    },
+    MenuItem {
+        name: "water",
+        default_variant: "regular",
+        schedule: None,
+        course: Course::Drinks,
+        category: "drinks",
+        tax_class: TaxClass::Drinks,
+        station: Station::Bar,
+        variants: &[Variant {
+            name: "regular",
+            price_cents: 150,
+            preparation_minutes: 5..15,
+            price_overrides: &[PriceOverride {
+                name: "happy hour",
+                price_cents: 100,
+                schedule: HAPPY_HOUR,
+            }],
+        }],
+        modifier_groups: &[],
+    },
+    MenuItem {
+        name: "pancakes",
+        default_variant: "regular",
+        schedule: Some(BREAKFAST),
+        course: Course::Main,
+        category: "breakfast",
+        tax_class: TaxClass::Food,
+        station: Station::Grill,
+        variants: &[Variant {
+            name: "regular",
+            price_cents: 650,
+            preparation_minutes: 5..15,
+            price_overrides: &[],
+        }],
+        modifier_groups: &[ModifierGroup {
+            name: "toppings",
+            required: false,
+            min_choices: 1,
+            max_choices: 2,
+            modifiers: &[
+                Modifier {
+                    name: "maple syrup",
+                    price_delta_cents: 0,
+                },
+                Modifier {
+                    name: "berries",
+                    price_delta_cents: 120,
+                },
+            ],
+        }],
+    },
+];
+
+pub fn menu_item(name: &str) -> Option<&'static MenuItem> {
+    MENU.iter().find(|item| item.name == name)
+}
+
+impl MenuItem {
+    // Whether the item can be ordered at this local time
+    pub fn is_served_at(&self, local: NaiveDateTime) -> bool {
+        self.schedule
+            .as_ref()
+            .is_none_or(|schedule| schedule.contains(local))
+    }
+
+    pub fn variant(&self, name: &str) -> Option<&'static Variant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    /*
+     * Checks the chosen modifiers against the item's groups and returns them in menu order.
+     * Every modifier must belong to this item and be chosen once, and every group must
+     * end up with an allowed number of choices.
+     */
+    pub fn select_modifiers(&self, names: &[String]) -> Result<Vec<&'static Modifier>, String> {
+        let mut selected = Vec::new();
+
+        for name in names {
+            let known = self
+                .modifier_groups
+                .iter()
+                .flat_map(|group| group.modifiers.iter())
+                .any(|modifier| modifier.name == name);
+            if !known {
+                return Err(format!("{} is not a modifier of {}.", name, self.name));
+            }
+            if names.iter().filter(|n| *n == name).count() > 1 {
+                return Err(format!("{} was chosen more than once.", name));
+            }
+        }
+
+        for group in self.modifier_groups {
+            let chosen: Vec<&'static Modifier> = group
+                .modifiers
+                .iter()
+                .filter(|modifier| names.iter().any(|n| n == modifier.name))
+                .collect();
+
+            let allowed = if chosen.is_empty() {
+                !group.required
+            } else {
+                (group.min_choices..=group.max_choices).contains(&chosen.len())
+            };
+            if !allowed {
+                return Err(format!(
+                    "{} needs between {} and {} choices of {}.",
+                    self.name,
+                    group.min_choices.max(1),
+                    group.max_choices,
+                    group.name
+                ));
+            }
+            selected.extend(chosen);
+        }
+
+        Ok(selected)
+    }
+
+    // Price of a single item of the given variant including its modifiers, at a local time
+    pub fn unit_price_cents(
+        &self,
+        variant: &Variant,
+        modifiers: &[&Modifier],
+        local: NaiveDateTime,
+    ) -> i64 {
+        variant.price_cents_at(local)
+            + modifiers
+                .iter()
+                .map(|modifier| modifier.price_delta_cents)
+                .sum::<i64>()
+    }
+}
+
+impl Variant {
+    pub fn price_cents_at(&self, local: NaiveDateTime) -> i64 {
+        self.price_overrides
+            .iter()
+            .find(|price_override| price_override.schedule.contains(local))
+            .map_or(self.price_cents, |price_override| {
+                price_override.price_cents
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::menu::{menu_item, MenuItem, Modifier, ModifierGroup, Variant, MENU};
+    use crate::domain::{Course, Station, TaxClass, MENU_ITEMS};
+    use chrono::{NaiveDate, NaiveDateTime};
+    use claim::{assert_err, assert_ok};
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    static DRINK: MenuItem = MenuItem {
+        name: "lemonade",
+        default_variant: "regular",
+        schedule: None,
+        course: Course::Drinks,
+        category: "drinks",
+        tax_class: TaxClass::Drinks,
+        station: Station::Bar,
+        variants: &[Variant {
+            name: "regular",
+            price_cents: 300,
+            preparation_minutes: 1..3,
+            price_overrides: &[],
+        }],
+        modifier_groups: &[ModifierGroup {
+            name: "size",
+            required: true,
+            min_choices: 1,
+            max_choices: 1,
+            modifiers: &[
+                Modifier {
+                    name: "small",
+                    price_delta_cents: 0,
+                },
+                Modifier {
+                    name: "large",
+                    price_delta_cents: 100,
+                },
+            ],
+        }],
+    };
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn every_menu_item_is_in_menu_items() {
+        assert_eq!(MENU.len(), MENU_ITEMS.len());
+        assert!(MENU.iter().all(|item| MENU_ITEMS.contains(&item.name)));
+    }
+
+    #[test]
+    fn every_default_variant_is_a_variant_of_its_item() {
+        for item in MENU.iter() {
+            assert!(item.variant(item.default_variant).is_some());
+        }
+    }
+
+    #[test]
+    fn variants_have_their_own_price() {
+        let cola = menu_item("cola").unwrap();
+        let small = cola.variant("small").unwrap();
+        let large = cola.variant("large").unwrap();
+        assert!(
+            cola.unit_price_cents(small, &[], at(12, 0))
+                < cola.unit_price_cents(large, &[], at(12, 0))
+        );
+        assert!(cola.variant("double").is_none());
+    }
+
+    #[test]
+    fn breakfast_items_are_only_served_in_the_morning() {
+        let pancakes = menu_item("pancakes").unwrap();
+        assert!(pancakes.is_served_at(at(10, 59)));
+        assert!(!pancakes.is_served_at(at(11, 0)));
+        assert!(menu_item("hamburger").unwrap().is_served_at(at(3, 0)));
+    }
+
+    #[test]
+    fn happy_hour_prices_replace_the_usual_price_while_it_lasts() {
+        let cola = menu_item("cola").unwrap();
+        let medium = cola.variant("medium").unwrap();
+        assert_eq!(cola.unit_price_cents(medium, &[], at(16, 59)), 250);
+        assert_eq!(cola.unit_price_cents(medium, &[], at(17, 0)), 180);
+        assert_eq!(cola.unit_price_cents(medium, &[], at(19, 0)), 250);
+    }
+
+    #[test]
+    fn modifiers_are_optional_unless_required() {
+        let hamburger = menu_item("hamburger").unwrap();
+        assert_ok!(hamburger.select_modifiers(&[]));
+        assert_err!(DRINK.select_modifiers(&[]));
+    }
+
+    #[test]
+    fn modifiers_from_several_groups_are_accepted() {
+        let hamburger = menu_item("hamburger").unwrap();
+        let selected = hamburger
+            .select_modifiers(&names(&["extra cheese", "no onions"]))
+            .unwrap();
+        let selected: Vec<&str> = selected.iter().map(|m| m.name).collect();
+        assert_eq!(selected, vec!["no onions", "extra cheese"]);
+    }
+
+    #[test]
+    fn modifiers_of_another_item_are_rejected() {
+        let hamburger = menu_item("hamburger").unwrap();
+        assert_err!(hamburger.select_modifiers(&names(&["no ice"])));
+    }
+
+    #[test]
+    fn a_modifier_cannot_be_chosen_twice() {
+        let hamburger = menu_item("hamburger").unwrap();
+        assert_err!(hamburger.select_modifiers(&names(&["bacon", "bacon"])));
+    }
+
+    #[test]
+    fn too_many_choices_in_a_group_are_rejected() {
+        let fries = menu_item("fries").unwrap();
+        assert_err!(fries.select_modifiers(&names(&["ketchup", "mayonnaise"])));
+        assert_err!(DRINK.select_modifiers(&names(&["small", "large"])));
+    }
+
+    #[test]
+    fn modifiers_add_to_the_price() {
+        let hamburger = menu_item("hamburger").unwrap();
+        let selected = hamburger
+            .select_modifiers(&names(&["extra cheese", "bacon", "no onions"]))
+            .unwrap();
+        let double = hamburger.variant("double").unwrap();
+        assert_eq!(
+            hamburger.unit_price_cents(double, &selected, at(12, 0)),
+            1150 + 100 + 150
+        );
+    }
+}
@@ -1,4 +1,4 @@
 // Secondary list of valid items to be used as a second source of truth
 
 // This is the publically available constant containing menu items
-pub const MENU_ITEMS: [&str; 4] = ["hamburger", "fries", "cola", "water"];
+pub const MENU_ITEMS: [&str; 5] = ["hamburger", "fries", "cola", "water", "pancakes"];
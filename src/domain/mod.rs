@@ -1,11 +1,50 @@
+mod bill;
+mod bundle;
+mod contact;
+mod course;
 mod database_response;
+mod discount;
 mod item;
+mod menu;
 mod menu_items;
 mod nat;
 mod new_order;
+mod note;
+mod payment;
+mod promotion;
+mod receipt;
+mod recipe;
+mod reservation;
+mod schedule;
+mod table;
+mod tax;
+mod ticket;
+mod waitlist;
 
+pub use bill::{Bill, BillComponent, BillLine};
+pub use bundle::{bundle, Bundle, BundleComponent, BundleSlot, Substitution, BUNDLES};
+pub use contact::{CustomerName, Phone};
+pub use course::Course;
 pub use database_response::DatabaseResponse;
-pub use item::Item;
+pub use discount::{Adjustment, DiscountKind, DiscountLine, Off};
+pub use item::{split_variant, Item};
+pub use menu::{menu_item, MenuItem, Modifier, ModifierGroup, PriceOverride, Variant, MENU};
 pub use menu_items::MENU_ITEMS;
-pub use nat::Nat;
-pub use new_order::NewOrder;
+pub use nat::{Nat, MAX_QUANTITY};
+pub use new_order::{NewBundleOrder, NewOrder, OrderValidationError};
+pub use note::Note;
+pub use payment::{NewPayment, Tender};
+pub use promotion::{Promotion, Reward, Target};
+pub use receipt::{
+    render_escpos, render_html, render_text, Receipt, ReceiptFormat, ReceiptPayment,
+};
+pub use recipe::{ingredients_for, recipe, IngredientUsage, Recipe, RECIPES};
+pub use reservation::NewReservation;
+pub use schedule::{time, Schedule, EVERY_DAY};
+pub use table::{table, tables_for, Table, TABLES};
+pub use tax::{
+    rate_for, tax_class, tax_lines, Dining, RoundingLevel, RoundingMode, TaxClass, TaxLine,
+    TaxRate, TaxRounding,
+};
+pub use ticket::{render_ticket, Station, Ticket, TicketLine};
+pub use waitlist::{quote_wait_minutes, TableTurn};
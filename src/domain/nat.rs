@@ -1,6 +1,9 @@
 #[derive(Debug)]
 pub struct Nat(i32);
 
+// The most of anything one order line can ask for, which keeps stock sums well within i32
+pub const MAX_QUANTITY: i32 = 1000;
+
 /*
  * For this project table_no, quantity and preparation_time should be positive integers (natural numbers)
  * PostgreSQL requires i32 rather than u32 so this is ensured by this module
@@ -18,6 +21,17 @@ impl Nat {
             Ok(Self(n))
         }
     }
+
+    // A quantity on an order line, between 0 and `MAX_QUANTITY`
+    pub fn parse_quantity(n: i32) -> Result<Nat, String> {
+        if n > MAX_QUANTITY {
+            return Err(format!(
+                "An order line is for at most {} units, not {}.",
+                MAX_QUANTITY, n
+            ));
+        }
+        Self::parse(n)
+    }
 }
 
 impl AsRef<i32> for Nat {
@@ -28,7 +42,7 @@ impl AsRef<i32> for Nat {
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::nat::Nat;
+    use crate::domain::nat::{Nat, MAX_QUANTITY};
     use claim::{assert_err, assert_ok};
 
     #[test]
@@ -45,4 +59,12 @@ mod tests {
     fn negative_number_is_rejected() {
         assert_err!(Nat::parse(-1));
     }
+
+    #[test]
+    fn quantities_are_bounded() {
+        assert_ok!(Nat::parse_quantity(MAX_QUANTITY));
+        assert_err!(Nat::parse_quantity(MAX_QUANTITY + 1));
+        assert_err!(Nat::parse_quantity(i32::MAX));
+        assert_err!(Nat::parse_quantity(-1));
+    }
 }
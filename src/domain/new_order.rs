@@ -1,9 +1,110 @@
+use crate::domain::bundle::{Bundle, BundleComponent};
+use crate::domain::course::Course;
 use crate::domain::item::Item;
+use crate::domain::menu::{MenuItem, Modifier, Variant};
 use crate::domain::nat::Nat;
+use crate::domain::note::Note;
+use chrono::NaiveDateTime;
 
 // We don't want negative numbers when creating a new order even though the database has signed integers
 pub struct NewOrder {
     pub table_no: Nat,
     pub item: Item,
     pub quantity: Nat,
+    // The menu entry for `item`, holding its price and modifier groups
+    pub menu_item: &'static MenuItem,
+    // The variant named by `item`, holding its price and preparation time
+    pub variant: &'static Variant,
+    // Validated against the item's modifier groups, in menu order
+    pub modifiers: Vec<&'static Modifier>,
+    pub note: Option<Note>,
+    // The item's own course unless the waiter chose another, e.g. fries as a starter
+    pub course: Course,
+    // Keep the line from the kitchen until its course is fired
+    pub hold: bool,
+    // Taken away rather than eaten in, which can change the tax charged
+    pub takeaway: bool,
+}
+
+impl NewOrder {
+    // Price of one item as ordered, at the local time the order is placed
+    pub fn unit_price_cents(&self, local: NaiveDateTime) -> i64 {
+        self.menu_item
+            .unit_price_cents(self.variant, &self.modifiers, local)
+    }
+}
+
+// A bundle ordered as one line, placed as a parent line with a child line per component
+pub struct NewBundleOrder {
+    pub table_no: Nat,
+    pub bundle: &'static Bundle,
+    pub quantity: Nat,
+    pub components: Vec<BundleComponent>,
+    pub note: Option<Note>,
+    // Keep the components from the kitchen until their courses are fired
+    pub hold: bool,
+    pub takeaway: bool,
+}
+
+impl NewBundleOrder {
+    pub fn unit_price_cents(&self) -> i64 {
+        self.bundle.unit_price_cents(&self.components)
+    }
+}
+
+// The field that stopped an order from being created, along with the parse error
+#[derive(Debug)]
+pub enum OrderValidationError {
+    InvalidTableNo(String),
+    InvalidItem(String),
+    InvalidQuantity(String),
+    InvalidModifiers(String),
+    InvalidNote(String),
+    InvalidBundle(String),
+    InvalidCourse(String),
+    // Only lines for a seated party can be held, firing happens per session
+    NotSeated(String),
+    // A valid item the kitchen has run out of for now
+    EightySixed(String),
+    // A valid item that is not served at this time of day, e.g. breakfast in the evening
+    NotServedNow(String),
+    // A valid item whose ingredients have run out
+    SoldOut(String),
+}
+
+impl OrderValidationError {
+    // Short label suitable for metrics and error codes
+    pub fn reason(&self) -> &'static str {
+        match self {
+            OrderValidationError::InvalidTableNo(_) => "invalid_table_no",
+            OrderValidationError::InvalidItem(_) => "invalid_item",
+            OrderValidationError::InvalidQuantity(_) => "invalid_quantity",
+            OrderValidationError::InvalidModifiers(_) => "invalid_modifiers",
+            OrderValidationError::InvalidNote(_) => "invalid_note",
+            OrderValidationError::InvalidBundle(_) => "invalid_bundle",
+            OrderValidationError::InvalidCourse(_) => "invalid_course",
+            OrderValidationError::NotSeated(_) => "not_seated",
+            OrderValidationError::EightySixed(_) => "item_86d",
+            OrderValidationError::NotServedNow(_) => "not_served_now",
+            OrderValidationError::SoldOut(_) => "sold_out",
+        }
+    }
+}
+
+impl std::fmt::Display for OrderValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderValidationError::InvalidTableNo(e)
+            | OrderValidationError::InvalidItem(e)
+            | OrderValidationError::InvalidQuantity(e)
+            | OrderValidationError::InvalidModifiers(e)
+            | OrderValidationError::InvalidNote(e)
+            | OrderValidationError::InvalidBundle(e)
+            | OrderValidationError::InvalidCourse(e)
+            | OrderValidationError::NotSeated(e)
+            | OrderValidationError::EightySixed(e)
+            | OrderValidationError::NotServedNow(e)
+            | OrderValidationError::SoldOut(e) => write!(f, "{}", e),
+        }
+    }
 }
@@ -0,0 +1,74 @@
+use crate::domain::item::FORBIDDEN_CHARACTERS;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Free text special instructions on an order line, e.g. "allergic to sesame"
+#[derive(Debug)]
+pub struct Note(String);
+
+// Long enough for any instruction, short enough for a kitchen ticket
+const MAX_NOTE_LENGTH: usize = 200;
+
+impl Note {
+    // Returns an instance of `Note` if the input satisfies validation
+    pub fn parse(s: String) -> Result<Note, String> {
+        let is_empty_or_whitespace = s.trim().is_empty();
+
+        // A grapheme is a user-perceived character, so `é` counts once however it is encoded
+        let is_too_long = s.graphemes(true).count() > MAX_NOTE_LENGTH;
+
+        let contains_forbidden_characters = s.chars().any(|g| FORBIDDEN_CHARACTERS.contains(&g));
+
+        if is_empty_or_whitespace || contains_forbidden_characters {
+            Err(format!(
+                "{} note is empty or contains forbidden characters.",
+                s
+            ))
+        } else if is_too_long {
+            Err(format!(
+                "Notes can be at most {} characters long.",
+                MAX_NOTE_LENGTH
+            ))
+        } else {
+            Ok(Self(s.trim().to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for Note {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Note;
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    fn a_note_is_parsed_successfully() {
+        assert_ok!(Note::parse("allergic to sesame".to_string()));
+    }
+
+    #[test]
+    fn a_200_grapheme_long_note_is_valid() {
+        assert_ok!(Note::parse("ё".repeat(200)));
+    }
+
+    #[test]
+    fn a_note_longer_than_200_graphemes_is_rejected() {
+        assert_err!(Note::parse("a".repeat(201)));
+    }
+
+    #[test]
+    fn whitespace_only_notes_are_rejected() {
+        assert_err!(Note::parse(" ".to_string()));
+    }
+
+    #[test]
+    fn notes_containing_an_invalid_character_are_rejected() {
+        for character in &['/', '(', ')', '"', '<', '>', '\\', '{', '}'] {
+            assert_err!(Note::parse(format!("no onions {}", character)));
+        }
+    }
+}
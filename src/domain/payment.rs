@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+// How a payment was made
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Tender {
+    Cash,
+    Card,
+    // A gift voucher, identified by its code
+    Voucher,
+}
+
+impl Tender {
+    pub const ALL: [Tender; 3] = [Tender::Cash, Tender::Card, Tender::Voucher];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tender::Cash => "cash",
+            Tender::Card => "card",
+            Tender::Voucher => "voucher",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Tender, String> {
+        Tender::ALL
+            .into_iter()
+            .find(|tender| tender.as_str() == s.trim())
+            .ok_or_else(|| format!("{} is not a tender, expected cash, card or voucher.", s))
+    }
+}
+
+// A payment that has passed validation but not yet been checked against the balance
+#[derive(Debug, PartialEq, Eq)]
+pub struct NewPayment {
+    pub tender: Tender,
+    // What goes towards the bill
+    pub amount_cents: i64,
+    pub tip_cents: i64,
+    // What was handed over, only ever more than amount and tip for cash
+    pub tendered_cents: i64,
+    pub change_cents: i64,
+    pub reference: Option<String>,
+}
+
+impl NewPayment {
+    /*
+     * Cash can be handed over in excess of the amount and tip, the rest is given back as change.
+     * Card and voucher payments are for exactly what they are charged, and vouchers need their code.
+     */
+    pub fn parse(
+        tender: &str,
+        amount_cents: i64,
+        tip_cents: Option<i64>,
+        tendered_cents: Option<i64>,
+        reference: Option<String>,
+    ) -> Result<NewPayment, String> {
+        let tender = Tender::parse(tender)?;
+        let tip_cents = tip_cents.unwrap_or(0);
+        if amount_cents <= 0 {
+            return Err("A payment has to be for more than nothing.".to_string());
+        }
+        if tip_cents < 0 {
+            return Err("A tip cannot be negative.".to_string());
+        }
+        let due = amount_cents
+            .checked_add(tip_cents)
+            .ok_or_else(|| "The amount and tip are too large.".to_string())?;
+        let tendered_cents = tendered_cents.unwrap_or(due);
+        if tendered_cents < due {
+            return Err(format!(
+                "{} cents tendered does not cover the {} cents due.",
+                tendered_cents, due
+            ));
+        }
+        if tender != Tender::Cash && tendered_cents != due {
+            return Err("Only cash is given change.".to_string());
+        }
+        let reference = reference
+            .map(|reference| reference.trim().to_string())
+            .filter(|reference| !reference.is_empty());
+        if tender == Tender::Voucher && reference.is_none() {
+            return Err("A voucher payment needs the voucher's code.".to_string());
+        }
+
+        Ok(NewPayment {
+            tender,
+            amount_cents,
+            tip_cents,
+            tendered_cents,
+            change_cents: tendered_cents - due,
+            reference,
+        })
+    }
+
+    // What is charged, the amount towards the bill and the tip
+    pub fn due_cents(&self) -> i64 {
+        self.amount_cents + self.tip_cents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{NewPayment, Tender};
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    fn cash_handed_over_beyond_the_amount_and_tip_is_given_back() {
+        let payment = NewPayment::parse("cash", 1850, Some(150), Some(2500), None).unwrap();
+
+        assert_eq!(payment.tender, Tender::Cash);
+        assert_eq!(payment.change_cents, 500);
+    }
+
+    #[test]
+    fn exact_payments_need_no_change() {
+        let payment = NewPayment::parse("card", 1850, None, None, None).unwrap();
+
+        assert_eq!(payment.tendered_cents, 1850);
+        assert_eq!(payment.change_cents, 0);
+    }
+
+    #[test]
+    fn too_little_cash_is_rejected() {
+        assert_err!(NewPayment::parse("cash", 1850, Some(150), Some(1900), None));
+    }
+
+    #[test]
+    fn only_cash_is_given_change() {
+        assert_err!(NewPayment::parse("card", 1850, None, Some(2000), None));
+    }
+
+    #[test]
+    fn empty_and_negative_amounts_are_rejected() {
+        assert_err!(NewPayment::parse("cash", 0, None, None, None));
+        assert_err!(NewPayment::parse("cash", 1850, Some(-100), None, None));
+    }
+
+    #[test]
+    fn amounts_and_tips_too_large_to_add_up_are_rejected() {
+        assert_err!(NewPayment::parse("card", i64::MAX, Some(1), None, None));
+    }
+
+    #[test]
+    fn vouchers_need_their_code() {
+        assert_err!(NewPayment::parse("voucher", 1000, None, None, None));
+        assert_err!(NewPayment::parse(
+            "voucher",
+            1000,
+            None,
+            None,
+            Some(" ".to_string())
+        ));
+        assert_ok!(NewPayment::parse(
+            "voucher",
+            1000,
+            None,
+            None,
+            Some("GIFT-42".to_string())
+        ));
+    }
+
+    #[test]
+    fn unknown_tenders_are_rejected() {
+        assert_err!(NewPayment::parse("cheque", 1000, None, None, None));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::domain::bill::BillLine;
+use crate::domain::menu::{menu_item, MENU};
+use crate::domain::schedule::Schedule;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+// What a promotion covers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    Item(String),
+    // Every item of a menu category, e.g. `drinks`
+    Category(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Reward {
+    // Of every `buy` units the cheapest `free` cost nothing, e.g. 2-for-1
+    BuyGetFree { buy: i32, free: i32 },
+    PercentOff(i64),
+}
+
+/*
+ * A discount a bill qualifies for on its own, worked out whenever the bill is prepared.
+ * Only lines ordered while the schedule applies count, so the discount does not depend on
+ * when the bill is asked for. Managers set them up, they are kept in the `promotions` table.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Promotion {
+    pub name: String,
+    pub target: Target,
+    // How many units of the target have to be on the bill before anything comes off
+    pub min_quantity: i32,
+    pub reward: Reward,
+    // When the lines have to be ordered, at any time when `None`
+    pub schedule: Option<Schedule>,
+}
+
+impl Promotion {
+    // Whether the promotion can ever apply, checked before it is saved
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.len() > 64 {
+            return Err("A promotion's name is between 1 and 64 characters.".to_string());
+        }
+        match &self.target {
+            Target::Item(item) if menu_item(item).is_none() => {
+                return Err(format!("There is no {} on the menu.", item));
+            }
+            Target::Category(category) if !MENU.iter().any(|item| item.category == category) => {
+                return Err(format!("There is no {} category on the menu.", category));
+            }
+            _ => {}
+        }
+        if self.min_quantity < 1 {
+            return Err("A promotion needs at least one unit of its target.".to_string());
+        }
+        match self.reward {
+            Reward::BuyGetFree { buy, free } if free < 1 || free >= buy => {
+                return Err(format!(
+                    "Of every {} units between 1 and {} can be free, not {}.",
+                    buy,
+                    buy - 1,
+                    free
+                ));
+            }
+            Reward::PercentOff(percent) if !(1..=100).contains(&percent) => {
+                return Err(format!(
+                    "A promotion takes between 1 and 100 percent off, not {}.",
+                    percent
+                ));
+            }
+            _ => {}
+        }
+        if self
+            .schedule
+            .as_ref()
+            .is_some_and(|schedule| schedule.days.is_empty())
+        {
+            return Err("A promotion's schedule needs at least one day.".to_string());
+        }
+        Ok(())
+    }
+
+    // Whether a line counts towards the promotion
+    pub fn covers(&self, line: &BillLine, timezone: Tz) -> bool {
+        let targeted = match &self.target {
+            Target::Item(item) => line.item == *item,
+            Target::Category(category) => {
+                menu_item(&line.item).is_some_and(|item| item.category == category)
+            }
+        };
+        targeted
+            && self.schedule.as_ref().is_none_or(|schedule| {
+                schedule.contains(line.placed_at.with_timezone(&timezone).naive_local())
+            })
+    }
+
+    // What comes off the bill, zero when it does not qualify
+    pub fn discount(&self, lines: &[BillLine], timezone: Tz) -> i64 {
+        let covered: Vec<&BillLine> = lines
+            .iter()
+            .filter(|line| self.covers(line, timezone))
+            .collect();
+        let quantity: i64 = covered.iter().map(|line| i64::from(line.quantity)).sum();
+        if quantity < i64::from(self.min_quantity) {
+            return 0;
+        }
+
+        match self.reward {
+            Reward::BuyGetFree { buy, free } => {
+                // The cheapest units are free, taken line by line from the cheapest line up
+                let mut covered = covered;
+                covered.sort_by_key(|line| line.unit_price_cents);
+                let mut free_units = quantity / i64::from(buy) * i64::from(free);
+                let mut discount = 0;
+                for line in covered {
+                    let units = free_units.min(i64::from(line.quantity));
+                    discount += units * line.unit_price_cents;
+                    free_units -= units;
+                }
+                discount
+            }
+            Reward::PercentOff(percent) => {
+                let total: i64 = covered.iter().map(|line| line.total_cents).sum();
+                (total * percent + 50) / 100
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::bill::BillLine;
+    use crate::domain::promotion::{Promotion, Reward, Target};
+    use crate::domain::schedule::{time, Schedule};
+    use chrono::{DateTime, TimeZone, Utc, Weekday};
+    use chrono_tz::Tz;
+    use claim::{assert_err, assert_ok};
+    use std::borrow::Cow;
+
+    // 2026-10-19 is a Monday
+    fn monday_noon() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap()
+    }
+
+    fn tuesday_noon() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 20, 12, 0, 0).unwrap()
+    }
+
+    // The promotions the `promotions` table starts out with
+    fn promotions() -> Vec<Promotion> {
+        vec![
+            Promotion {
+                name: "2-for-1 fries tuesdays".to_string(),
+                target: Target::Item("fries".to_string()),
+                min_quantity: 2,
+                reward: Reward::BuyGetFree { buy: 2, free: 1 },
+                // From opening until closing time
+                schedule: Some(Schedule {
+                    days: Cow::Borrowed(&[Weekday::Tue]),
+                    from: time(11, 0),
+                    until: time(2, 0),
+                }),
+            },
+            Promotion {
+                name: "10% off a round of 6 drinks".to_string(),
+                target: Target::Category("drinks".to_string()),
+                min_quantity: 6,
+                reward: Reward::PercentOff(10),
+                schedule: None,
+            },
+        ]
+    }
+
+    // What every promotion the lines qualify for takes off
+    fn discounts(lines: &[BillLine], timezone: Tz) -> Vec<i64> {
+        promotions()
+            .iter()
+            .map(|promotion| promotion.discount(lines, timezone))
+            .filter(|cents| *cents > 0)
+            .collect()
+    }
+
+    fn line(item: &str, quantity: i32, unit_price_cents: i64, at: DateTime<Utc>) -> BillLine {
+        BillLine {
+            item: item.to_string(),
+            variant: "regular".to_string(),
+            modifiers: vec![],
+            quantity,
+            unit_price_cents,
+            total_cents: unit_price_cents * quantity as i64,
+            components: vec![],
+            takeaway: false,
+            placed_at: at,
+            discount_cents: 0,
+        }
+    }
+
+    #[test]
+    fn every_second_portion_of_fries_is_free_on_tuesdays() {
+        let lines = vec![
+            line("fries", 2, 350, tuesday_noon()),
+            line("fries", 1, 450, tuesday_noon()),
+        ];
+
+        let discounts = discounts(&lines, Tz::UTC);
+
+        assert_eq!(discounts.len(), 1);
+        // One of three portions is free, the cheapest
+        assert_eq!(discounts[0], 350);
+    }
+
+    #[test]
+    fn fries_ordered_on_other_days_do_not_count() {
+        let lines = vec![
+            line("fries", 1, 350, monday_noon()),
+            line("fries", 1, 350, tuesday_noon()),
+        ];
+
+        assert!(discounts(&lines, Tz::UTC).is_empty());
+    }
+
+    #[test]
+    fn a_promotion_respects_the_restaurants_timezone() {
+        // 10:30 on Tuesday in UTC is before opening, but already 12:30 in Madrid
+        let early = Utc.with_ymd_and_hms(2026, 10, 20, 10, 30, 0).unwrap();
+        let lines = vec![line("fries", 2, 350, early)];
+
+        assert!(discounts(&lines, Tz::UTC).is_empty());
+        assert_eq!(discounts(&lines, Tz::Europe__Madrid)[0], 350);
+    }
+
+    #[test]
+    fn a_category_promotion_needs_enough_units_of_the_category() {
+        let five = vec![
+            line("cola", 3, 250, monday_noon()),
+            line("water", 2, 150, monday_noon()),
+        ];
+        let six = vec![
+            line("cola", 3, 250, monday_noon()),
+            line("water", 3, 150, monday_noon()),
+            line("hamburger", 1, 850, monday_noon()),
+        ];
+
+        assert!(discounts(&five, Tz::UTC).is_empty());
+        // 10% of the drinks only, 1200 cents
+        assert_eq!(discounts(&six, Tz::UTC)[0], 120);
+    }
+
+    #[test]
+    fn huge_quantities_are_counted_without_overflowing() {
+        let lines = vec![
+            line("fries", i32::MAX, 350, tuesday_noon()),
+            line("fries", 1, 300, tuesday_noon()),
+        ];
+
+        let discounts = discounts(&lines, Tz::UTC);
+
+        // Half of the 2^31 portions are free, the one at 300 among them
+        let free = (i64::from(i32::MAX) + 1) / 2;
+        assert_eq!(discounts[0], 300 + (free - 1) * 350);
+    }
+
+    #[test]
+    fn a_promotion_has_to_be_able_to_apply() {
+        let fries = &promotions()[0];
+        assert_ok!(fries.validate());
+
+        let changed = |change: fn(&mut Promotion)| {
+            let mut promotion = fries.clone();
+            change(&mut promotion);
+            promotion.validate()
+        };
+        assert_err!(changed(|p| p.name = " ".to_string()));
+        assert_err!(changed(|p| p.target = Target::Item("pizza".to_string())));
+        assert_err!(changed(
+            |p| p.target = Target::Category("pizzas".to_string())
+        ));
+        assert_err!(changed(|p| p.min_quantity = 0));
+        assert_err!(changed(
+            |p| p.reward = Reward::BuyGetFree { buy: 2, free: 2 }
+        ));
+        assert_err!(changed(|p| p.reward = Reward::PercentOff(0)));
+        assert_err!(changed(
+            |p| p.schedule.as_mut().unwrap().days = Cow::Owned(vec![])
+        ));
+    }
+}
@@ -0,0 +1,500 @@
+use crate::configuration::ReceiptSettings;
+use crate::domain::Bill;
+use chrono::NaiveDateTime;
+
+// How a receipt is handed out, picked with `?format=` and plain text when not given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptFormat {
+    Text,
+    Html,
+    // Bytes for a thermal printer
+    EscPos,
+}
+
+impl ReceiptFormat {
+    pub const ALL: [ReceiptFormat; 3] = [
+        ReceiptFormat::Text,
+        ReceiptFormat::Html,
+        ReceiptFormat::EscPos,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReceiptFormat::Text => "text",
+            ReceiptFormat::Html => "html",
+            ReceiptFormat::EscPos => "escpos",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<ReceiptFormat, String> {
+        ReceiptFormat::ALL
+            .into_iter()
+            .find(|format| format.as_str() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                format!(
+                    "{} is not a receipt format, expected text, html or escpos.",
+                    s
+                )
+            })
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReceiptFormat::Text => "text/plain; charset=utf-8",
+            ReceiptFormat::Html => "text/html; charset=utf-8",
+            ReceiptFormat::EscPos => "application/octet-stream",
+        }
+    }
+}
+
+// A payment or refund as printed, amounts are negative for refunds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptPayment {
+    pub tender: String,
+    pub amount_cents: i64,
+    pub tip_cents: i64,
+    pub change_cents: i64,
+    pub refund: bool,
+}
+
+// Everything printed on a closed session's receipt, times in the restaurant's timezone
+#[derive(Debug, PartialEq, Eq)]
+pub struct Receipt {
+    pub table_no: i32,
+    pub opened_at: NaiveDateTime,
+    pub closed_at: NaiveDateTime,
+    pub bill: Bill,
+    pub payments: Vec<ReceiptPayment>,
+}
+
+/*
+ * A receipt is laid out once as rows and each format draws the rows its own way,
+ * so the three formats always say the same thing.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Row {
+    Centered(String),
+    // Text on the left and an amount on the right
+    Columns(String, String),
+    // Indented under the line before, e.g. a modifier
+    Detail(String),
+    Total(String, String),
+    Rule,
+    Blank,
+}
+
+// `1250` is `12.50`, `-300` is `-3.00`
+fn money(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+fn tender_name(tender: &str) -> String {
+    let mut chars = tender.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn rows(receipt: &Receipt, settings: &ReceiptSettings) -> Vec<Row> {
+    let bill = &receipt.bill;
+    let mut rows: Vec<Row> = settings.header.iter().cloned().map(Row::Centered).collect();
+    rows.push(Row::Blank);
+    rows.push(Row::Columns(
+        format!("Table {}", receipt.table_no),
+        receipt.closed_at.format("%Y-%m-%d %H:%M").to_string(),
+    ));
+    rows.push(Row::Detail(format!(
+        "Seated {}",
+        receipt.opened_at.format("%H:%M")
+    )));
+    rows.push(Row::Rule);
+
+    for line in &bill.lines {
+        let name = match line.variant.as_str() {
+            "regular" => line.item.clone(),
+            variant => format!("{} ({})", line.item, variant),
+        };
+        rows.push(Row::Columns(
+            format!("{} x {}", line.quantity, name),
+            money(line.total_cents),
+        ));
+        rows.extend(
+            line.components
+                .iter()
+                .map(|component| Row::Detail(component.item.clone())),
+        );
+        rows.extend(
+            line.modifiers
+                .iter()
+                .map(|modifier| Row::Detail(format!("+ {}", modifier))),
+        );
+        if line.takeaway {
+            rows.push(Row::Detail("takeaway".to_string()));
+        }
+    }
+    rows.push(Row::Rule);
+
+    rows.push(Row::Columns(
+        "Subtotal".to_string(),
+        money(bill.subtotal_cents),
+    ));
+    rows.extend(
+        bill.discounts
+            .iter()
+            .map(|discount| Row::Columns(discount.name.clone(), money(-discount.amount_cents))),
+    );
+    // Tax is part of the total when prices include it, so it is listed after it
+    let tax: Vec<Row> = bill
+        .tax
+        .iter()
+        .map(|line| {
+            let label = format!(
+                "{} {}% on {}",
+                line.name,
+                line.percent,
+                money(line.taxable_cents)
+            );
+            match bill.prices_include_tax {
+                true => Row::Columns(format!("incl. {}", label), money(line.tax_cents)),
+                false => Row::Columns(label, money(line.tax_cents)),
+            }
+        })
+        .collect();
+    if !bill.prices_include_tax {
+        rows.extend(tax.iter().cloned());
+    }
+    rows.push(Row::Total("TOTAL".to_string(), money(bill.total_cents)));
+    if bill.prices_include_tax {
+        rows.extend(tax);
+    }
+
+    if !receipt.payments.is_empty() {
+        rows.push(Row::Rule);
+    }
+    for payment in &receipt.payments {
+        let label = match payment.refund {
+            true => format!("Refund {}", payment.tender),
+            false => tender_name(&payment.tender),
+        };
+        rows.push(Row::Columns(label, money(payment.amount_cents)));
+        if payment.tip_cents > 0 {
+            rows.push(Row::Columns("Tip".to_string(), money(payment.tip_cents)));
+        }
+        if payment.change_cents > 0 {
+            rows.push(Row::Columns(
+                "Change".to_string(),
+                money(payment.change_cents),
+            ));
+        }
+    }
+
+    rows.push(Row::Blank);
+    rows.extend(settings.footer.iter().cloned().map(Row::Centered));
+    rows
+}
+
+/*
+ * Breaks `text` into lines of at most `width` characters at spaces,
+ * words longer than a line are cut.
+ */
+pub(super) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        let word: String = word.into_iter().collect();
+        let length = line.chars().count();
+        if length > 0 && length + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+// The amount sits at the end of the first line, the text wraps in the space left of it
+pub(super) fn columns(left: &str, right: &str, width: usize) -> Vec<String> {
+    let right_width = right.chars().count();
+    let mut lines = wrap(left, width.saturating_sub(right_width + 1));
+    let padding = width.saturating_sub(lines[0].chars().count() + right_width);
+    lines[0] = format!("{}{}{}", lines[0], " ".repeat(padding.max(1)), right);
+    lines
+}
+
+fn centered(text: &str, width: usize) -> Vec<String> {
+    wrap(text, width)
+        .into_iter()
+        .map(|line| {
+            let padding = width.saturating_sub(line.chars().count()) / 2;
+            format!("{}{}", " ".repeat(padding), line)
+        })
+        .collect()
+}
+
+fn detail(text: &str, width: usize) -> Vec<String> {
+    wrap(text, width.saturating_sub(2))
+        .into_iter()
+        .map(|line| format!("  {}", line))
+        .collect()
+}
+
+fn text_lines(row: &Row, width: usize) -> Vec<String> {
+    match row {
+        Row::Centered(text) => centered(text, width),
+        Row::Columns(left, right) | Row::Total(left, right) => columns(left, right, width),
+        Row::Detail(text) => detail(text, width),
+        Row::Rule => vec!["-".repeat(width)],
+        Row::Blank => vec![String::new()],
+    }
+}
+
+// Fixed width text, `settings.width` characters to a line
+pub fn render_text(receipt: &Receipt, settings: &ReceiptSettings) -> String {
+    rows(receipt, settings)
+        .iter()
+        .flat_map(|row| text_lines(row, settings.width))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// A page of its own that prints at the width of the text receipt
+pub fn render_html(receipt: &Receipt, settings: &ReceiptSettings) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n\
+         <html lang=\"en\">\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>Receipt, table {}</title>\n\
+         <style>\n\
+         .receipt {{ font-family: monospace; max-width: {}ch; margin: 0 auto; }}\n\
+         .centered {{ text-align: center; }}\n\
+         .columns {{ display: flex; justify-content: space-between; gap: 1ch; }}\n\
+         .detail {{ padding-left: 2ch; }}\n\
+         .total {{ font-weight: bold; }}\n\
+         </style>\n\
+         </head>\n\
+         <body>\n\
+         <div class=\"receipt\">\n",
+        receipt.table_no, settings.width
+    );
+    for row in rows(receipt, settings) {
+        let element = match row {
+            Row::Centered(text) => format!("<p class=\"centered\">{}</p>", escape_html(&text)),
+            Row::Columns(left, right) => format!(
+                "<p class=\"columns\"><span>{}</span><span>{}</span></p>",
+                escape_html(&left),
+                escape_html(&right)
+            ),
+            Row::Detail(text) => format!("<p class=\"detail\">{}</p>", escape_html(&text)),
+            Row::Total(left, right) => format!(
+                "<p class=\"columns total\"><span>{}</span><span>{}</span></p>",
+                escape_html(&left),
+                escape_html(&right)
+            ),
+            Row::Rule => "<hr>".to_string(),
+            Row::Blank => "<br>".to_string(),
+        };
+        html.push_str(&element);
+        html.push('\n');
+    }
+    html.push_str("</div>\n</body>\n</html>\n");
+    html
+}
+
+pub(super) const ESC: u8 = 0x1b;
+pub(super) const GS: u8 = 0x1d;
+
+// Printers are left in their default code page, so anything beyond ASCII prints as `?`
+pub(super) fn ascii(line: &str) -> Vec<u8> {
+    line.chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .chain(std::iter::once(b'\n'))
+        .collect()
+}
+
+/*
+ * Commands for an ESC/POS thermal printer. The printer centres the header and footer
+ * itself, prints the total in bold, then feeds the paper past the cutter and cuts it.
+ */
+pub fn render_escpos(receipt: &Receipt, settings: &ReceiptSettings) -> Vec<u8> {
+    // Initialise the printer
+    let mut bytes = vec![ESC, b'@'];
+    for row in rows(receipt, settings) {
+        match &row {
+            Row::Centered(text) => {
+                bytes.extend([ESC, b'a', 1]);
+                for line in wrap(text, settings.width) {
+                    bytes.extend(ascii(&line));
+                }
+                bytes.extend([ESC, b'a', 0]);
+            }
+            Row::Total(..) => {
+                bytes.extend([ESC, b'E', 1]);
+                for line in text_lines(&row, settings.width) {
+                    bytes.extend(ascii(&line));
+                }
+                bytes.extend([ESC, b'E', 0]);
+            }
+            row => {
+                for line in text_lines(row, settings.width) {
+                    bytes.extend(ascii(&line));
+                }
+            }
+        }
+    }
+    // Feed four lines and make a partial cut
+    bytes.extend([GS, b'V', 66, 4]);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::ReceiptSettings;
+    use crate::domain::receipt::{
+        columns, escape_html, money, render_escpos, render_html, render_text, wrap, Receipt,
+        ReceiptFormat,
+    };
+    use crate::domain::{Bill, DatabaseResponse};
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use claim::{assert_err, assert_ok};
+    use uuid::Uuid;
+
+    fn receipt(item: &str) -> Receipt {
+        let placed_at = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let order = DatabaseResponse {
+            id: Uuid::nil(),
+            table_no: 1,
+            item: item.to_string(),
+            variant: "regular".to_string(),
+            quantity: 1,
+            preparation_time: 5,
+            modifiers: vec![],
+            note: None,
+            unit_price_cents: 350,
+            parent_id: None,
+            session_id: None,
+            course: "main".to_string(),
+            fired_at: None,
+            takeaway: false,
+            placed_at,
+        };
+        let time = NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap();
+        Receipt {
+            table_no: 1,
+            opened_at: time,
+            closed_at: time,
+            bill: Bill::new(1, vec![order]),
+            payments: vec![],
+        }
+    }
+
+    #[test]
+    fn formats_are_parsed_whatever_their_case() {
+        assert_eq!(
+            ReceiptFormat::parse("ESCPOS").unwrap(),
+            ReceiptFormat::EscPos
+        );
+        assert_ok!(ReceiptFormat::parse("html"));
+        assert_err!(ReceiptFormat::parse("pdf"));
+    }
+
+    #[test]
+    fn amounts_are_printed_with_two_decimals() {
+        assert_eq!(money(1250), "12.50");
+        assert_eq!(money(5), "0.05");
+        assert_eq!(money(-300), "-3.00");
+    }
+
+    #[test]
+    fn long_text_wraps_at_spaces_and_long_words_are_cut() {
+        assert_eq!(wrap("two for one fries", 10), vec!["two for", "one fries"]);
+        assert_eq!(wrap("abcdefghijkl", 5), vec!["abcde", "fghij", "kl"]);
+        assert_eq!(wrap("", 5), vec![""]);
+    }
+
+    #[test]
+    fn amounts_are_right_aligned_next_to_wrapped_text() {
+        let lines = columns("2 x a very long name for a dish", "12.50", 20);
+
+        assert_eq!(
+            lines,
+            vec!["2 x a very     12.50", "long name for", "a dish"]
+        );
+    }
+
+    #[test]
+    fn every_text_line_fits_the_paper() {
+        let settings = ReceiptSettings {
+            width: 24,
+            ..ReceiptSettings::default()
+        };
+
+        let text = render_text(&receipt("hamburger"), &settings);
+
+        assert!(text.lines().all(|line| line.chars().count() <= 24));
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        assert_eq!(
+            escape_html("<b>fish & chips</b>"),
+            "&lt;b&gt;fish &amp; chips&lt;/b&gt;"
+        );
+        let settings = ReceiptSettings {
+            header: vec!["Tom & Jerry's".to_string()],
+            ..ReceiptSettings::default()
+        };
+
+        let html = render_html(&receipt("fries"), &settings);
+
+        assert!(html.contains("Tom &amp; Jerry&#39;s"));
+    }
+
+    #[test]
+    fn escpos_starts_with_initialising_the_printer_and_ends_with_a_cut() {
+        let settings = ReceiptSettings {
+            header: vec!["Café".to_string()],
+            ..ReceiptSettings::default()
+        };
+
+        let bytes = render_escpos(&receipt("fries"), &settings);
+
+        assert_eq!(&bytes[..2], &[0x1b, b'@']);
+        assert_eq!(&bytes[bytes.len() - 4..], &[0x1d, b'V', 66, 4]);
+        assert!(bytes.windows(5).any(|window| window == b"Caf?\n"));
+        assert!(bytes.iter().all(|byte| byte.is_ascii()));
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+/*
+ * What goes into each variant of a menu item, used to take ingredients out of stock.
+ * Variants without a recipe use nothing that is tracked.
+ */
+pub struct Recipe {
+    pub item: &'static str,
+    pub variant: &'static str,
+    pub ingredients: &'static [IngredientUsage],
+}
+
+pub struct IngredientUsage {
+    pub ingredient: &'static str,
+    // Units used for a single item
+    pub quantity: i32,
+}
+
+pub static RECIPES: [Recipe; 6] = [
+    Recipe {
+        item: "hamburger",
+        variant: "single",
+        ingredients: &[
+            IngredientUsage {
+                ingredient: "burger bun",
+                quantity: 1,
+            },
+            IngredientUsage {
+                ingredient: "beef patty",
+                quantity: 1,
+            },
+        ],
+    },
+    Recipe {
+        item: "hamburger",
+        variant: "double",
+        ingredients: &[
+            IngredientUsage {
+                ingredient: "burger bun",
+                quantity: 1,
+            },
+            IngredientUsage {
+                ingredient: "beef patty",
+                quantity: 2,
+            },
+        ],
+    },
+    Recipe {
+        item: "fries",
+        variant: "regular",
+        ingredients: &[IngredientUsage {
+            ingredient: "potato portion",
+            quantity: 1,
+        }],
+    },
+    Recipe {
+        item: "cola",
+        variant: "small",
+        ingredients: &[IngredientUsage {
+            ingredient: "cola syrup",
+            quantity: 1,
+        }],
+    },
+    Recipe {
+        item: "cola",
+        variant: "medium",
+        ingredients: &[IngredientUsage {
+            ingredient: "cola syrup",
+            quantity: 2,
+        }],
+    },
+    Recipe {
+        item: "cola",
+        variant: "large",
+        ingredients: &[IngredientUsage {
+            ingredient: "cola syrup",
+            quantity: 3,
+        }],
+    },
+];
+
+pub fn recipe(item: &str, variant: &str) -> &'static [IngredientUsage] {
+    RECIPES
+        .iter()
+        .find(|recipe| recipe.item == item && recipe.variant == variant)
+        .map(|recipe| recipe.ingredients)
+        .unwrap_or(&[])
+}
+
+/*
+ * Totals the ingredients for order lines given as `(item, variant, quantity)`.
+ * Ingredients come out sorted by name, so stock rows are always locked in the same order.
+ */
+pub fn ingredients_for(lines: &[(&str, &str, i32)]) -> BTreeMap<&'static str, i32> {
+    let mut totals = BTreeMap::new();
+    for (item, variant, quantity) in lines {
+        for usage in recipe(item, variant) {
+            *totals.entry(usage.ingredient).or_insert(0) += usage.quantity * quantity;
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::menu_item;
+    use crate::domain::recipe::{ingredients_for, RECIPES};
+
+    #[test]
+    fn every_recipe_is_for_a_variant_on_the_menu() {
+        for recipe in RECIPES.iter() {
+            let item = menu_item(recipe.item).unwrap();
+            assert!(item.variant(recipe.variant).is_some());
+        }
+    }
+
+    #[test]
+    fn ingredients_are_totalled_across_lines_and_quantities() {
+        let totals = ingredients_for(&[
+            ("hamburger", "double", 2),
+            ("hamburger", "single", 1),
+            ("water", "regular", 4),
+        ]);
+
+        assert_eq!(totals.get("burger bun"), Some(&3));
+        assert_eq!(totals.get("beef patty"), Some(&5));
+        assert_eq!(totals.len(), 2);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::configuration::ReservationSettings;
+use crate::domain::contact::{CustomerName, Phone};
+use crate::domain::table::{table, TABLES};
+use chrono::{DateTime, Duration, Timelike, Utc};
+
+// A booking that has passed validation but not yet been given a table
+#[derive(Debug)]
+pub struct NewReservation {
+    pub party_size: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub customer_name: CustomerName,
+    pub phone: Phone,
+    // The table asked for, any table the party fits at when `None`
+    pub table_no: Option<i32>,
+}
+
+// Bookings are for a sitting, not an afternoon
+const MAX_DURATION_MINUTES: i32 = 240;
+
+impl NewReservation {
+    /*
+     * Checks a booking on its own, before looking at the other bookings.
+     * It has to start in the future on a slot boundary, e.g. 19:30 with 15 minute slots,
+     * and last a whole number of slots.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn parse(
+        party_size: i32,
+        starts_at: DateTime<Utc>,
+        duration_minutes: Option<i32>,
+        customer_name: String,
+        phone: String,
+        table_no: Option<i32>,
+        now: DateTime<Utc>,
+        settings: &ReservationSettings,
+    ) -> Result<NewReservation, String> {
+        let largest_table = TABLES.iter().map(|t| t.capacity).max().unwrap_or(0);
+        if party_size < 1 {
+            return Err("A party is at least one person.".to_string());
+        }
+        if party_size > largest_table {
+            return Err(format!(
+                "No table seats {}, the largest seats {}.",
+                party_size, largest_table
+            ));
+        }
+        if let Some(table_no) = table_no {
+            if table(table_no).is_none() {
+                return Err(format!("There is no table {}.", table_no));
+            }
+        }
+
+        let slot = settings.slot_minutes;
+        if starts_at <= now {
+            return Err("Reservations have to be in the future.".to_string());
+        }
+        if starts_at.minute() as i32 % slot != 0 || starts_at.second() != 0 {
+            return Err(format!(
+                "Reservations start on the {} minute slots, e.g. 19:00 or 19:{:02}.",
+                slot, slot
+            ));
+        }
+        let duration_minutes = duration_minutes.unwrap_or(settings.default_duration_minutes);
+        if duration_minutes < slot
+            || duration_minutes > MAX_DURATION_MINUTES
+            || duration_minutes % slot != 0
+        {
+            return Err(format!(
+                "A reservation lasts between {} and {} minutes, in steps of {}.",
+                slot, MAX_DURATION_MINUTES, slot
+            ));
+        }
+
+        Ok(Self {
+            party_size,
+            starts_at,
+            ends_at: starts_at + Duration::minutes(duration_minutes.into()),
+            customer_name: CustomerName::parse(customer_name)?,
+            phone: Phone::parse(phone)?,
+            table_no,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::ReservationSettings;
+    use crate::domain::NewReservation;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use claim::{assert_err, assert_ok};
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap()
+    }
+
+    fn reservation(
+        party_size: i32,
+        starts_at: DateTime<Utc>,
+        duration_minutes: Option<i32>,
+        table_no: Option<i32>,
+    ) -> Result<NewReservation, String> {
+        NewReservation::parse(
+            party_size,
+            starts_at,
+            duration_minutes,
+            "Ana García".to_string(),
+            "+34 600 123 456".to_string(),
+            table_no,
+            now(),
+            &ReservationSettings::default(),
+        )
+    }
+
+    #[test]
+    fn a_booking_lasts_the_default_duration_unless_given() {
+        let starts_at = now() + Duration::minutes(450);
+        let booking = reservation(2, starts_at, None, None).unwrap();
+        assert_eq!(booking.ends_at - booking.starts_at, Duration::minutes(90));
+
+        let booking = reservation(2, starts_at, Some(120), None).unwrap();
+        assert_eq!(booking.ends_at - booking.starts_at, Duration::minutes(120));
+    }
+
+    #[test]
+    fn bookings_start_on_a_slot_in_the_future() {
+        assert_ok!(reservation(2, now() + Duration::minutes(15), None, None));
+        assert_err!(reservation(2, now(), None, None));
+        assert_err!(reservation(2, now() - Duration::minutes(60), None, None));
+        assert_err!(reservation(2, now() + Duration::minutes(20), None, None));
+        assert_err!(reservation(
+            2,
+            now() + Duration::seconds(900 + 30),
+            None,
+            None
+        ));
+    }
+
+    #[test]
+    fn durations_are_whole_slots_within_limits() {
+        let starts_at = now() + Duration::minutes(60);
+        for minutes in [0, 10, 100, 300] {
+            assert_err!(reservation(2, starts_at, Some(minutes), None));
+        }
+    }
+
+    #[test]
+    fn parties_have_to_fit_at_some_table() {
+        let starts_at = now() + Duration::minutes(60);
+        assert_err!(reservation(0, starts_at, None, None));
+        assert_err!(reservation(9, starts_at, None, None));
+        assert_ok!(reservation(8, starts_at, None, None));
+    }
+
+    #[test]
+    fn only_tables_on_the_floor_plan_can_be_asked_for() {
+        let starts_at = now() + Duration::minutes(60);
+        assert_ok!(reservation(2, starts_at, None, Some(1)));
+        assert_err!(reservation(2, starts_at, None, Some(99)));
+    }
+}
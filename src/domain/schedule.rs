@@ -0,0 +1,97 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+pub const EVERY_DAY: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/*
+ * When something is on offer, in the restaurant's local time.
+ * A window from 22:00 until 02:00 runs past midnight, and belongs to the day it starts on.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    // Borrowed by the menu's schedules, owned by those read from the database
+    pub days: Cow<'static, [Weekday]>,
+    pub from: NaiveTime,
+    // The first moment no longer in the window
+    pub until: NaiveTime,
+}
+
+impl Schedule {
+    pub fn contains(&self, local: NaiveDateTime) -> bool {
+        let time = local.time();
+        if self.from <= self.until {
+            self.days.contains(&local.weekday()) && self.from <= time && time < self.until
+        } else if time >= self.from {
+            self.days.contains(&local.weekday())
+        } else {
+            time < self.until && self.days.contains(&local.weekday().pred())
+        }
+    }
+}
+
+// Builds a time of day for schedules, panicking at compile time on an invalid time
+pub const fn time(hour: u32, minute: u32) -> NaiveTime {
+    match NaiveTime::from_hms_opt(hour, minute, 0) {
+        Some(time) => time,
+        None => panic!("Invalid time of day in a schedule."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::schedule::{time, Schedule, EVERY_DAY};
+    use chrono::{NaiveDate, NaiveDateTime, Weekday};
+    use std::borrow::Cow;
+
+    // 2026-10-19 is a Monday
+    fn monday_at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn a_window_includes_its_start_but_not_its_end() {
+        let breakfast = Schedule {
+            days: Cow::Borrowed(&EVERY_DAY),
+            from: time(6, 0),
+            until: time(11, 0),
+        };
+        assert!(breakfast.contains(monday_at(6, 0)));
+        assert!(breakfast.contains(monday_at(10, 59)));
+        assert!(!breakfast.contains(monday_at(11, 0)));
+        assert!(!breakfast.contains(monday_at(5, 59)));
+    }
+
+    #[test]
+    fn only_the_listed_days_are_included() {
+        let weekends = Schedule {
+            days: Cow::Borrowed(&[Weekday::Sat, Weekday::Sun]),
+            from: time(0, 0),
+            until: time(23, 59),
+        };
+        assert!(!weekends.contains(monday_at(12, 0)));
+    }
+
+    #[test]
+    fn a_window_past_midnight_belongs_to_the_day_it_starts_on() {
+        let sunday_night = Schedule {
+            days: Cow::Borrowed(&[Weekday::Sun]),
+            from: time(22, 0),
+            until: time(2, 0),
+        };
+        assert!(sunday_night.contains(monday_at(1, 30)));
+        assert!(!sunday_night.contains(monday_at(2, 0)));
+        assert!(!sunday_night.contains(monday_at(23, 0)));
+    }
+}
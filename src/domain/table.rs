@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+// A table on the floor and how many people can sit at it
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Table {
+    pub table_no: i32,
+    pub capacity: i32,
+}
+
+// The floor plan, tables that can be booked or seated
+pub static TABLES: [Table; 8] = [
+    Table {
+        table_no: 1,
+        capacity: 2,
+    },
+    Table {
+        table_no: 2,
+        capacity: 2,
+    },
+    Table {
+        table_no: 3,
+        capacity: 4,
+    },
+    Table {
+        table_no: 4,
+        capacity: 4,
+    },
+    Table {
+        table_no: 5,
+        capacity: 4,
+    },
+    Table {
+        table_no: 6,
+        capacity: 6,
+    },
+    Table {
+        table_no: 7,
+        capacity: 6,
+    },
+    Table {
+        table_no: 8,
+        capacity: 8,
+    },
+];
+
+pub fn table(table_no: i32) -> Option<&'static Table> {
+    TABLES.iter().find(|table| table.table_no == table_no)
+}
+
+// Tables a party fits at, smallest first so large tables are kept for large parties
+pub fn tables_for(party_size: i32) -> Vec<&'static Table> {
+    let mut tables: Vec<&'static Table> = TABLES
+        .iter()
+        .filter(|table| table.capacity >= party_size)
+        .collect();
+    tables.sort_by_key(|table| (table.capacity, table.table_no));
+    tables
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::table::{tables_for, TABLES};
+
+    #[test]
+    fn table_numbers_are_unique() {
+        for (i, table) in TABLES.iter().enumerate() {
+            assert!(TABLES[..i].iter().all(|t| t.table_no != table.table_no));
+        }
+    }
+
+    #[test]
+    fn parties_get_the_smallest_table_they_fit_at_first() {
+        let tables: Vec<i32> = tables_for(3).iter().map(|t| t.table_no).collect();
+        assert_eq!(tables, vec![3, 4, 5, 6, 7, 8]);
+        assert!(tables_for(9).is_empty());
+    }
+}
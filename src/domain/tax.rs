@@ -0,0 +1,344 @@
+use crate::domain::bundle::bundle;
+use crate::domain::menu::menu_item;
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+// Decides which rates apply to an item, e.g. drinks may be taxed differently from food
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxClass {
+    Food,
+    Drinks,
+}
+
+impl TaxClass {
+    pub const ALL: [TaxClass; 2] = [TaxClass::Food, TaxClass::Drinks];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Dining {
+    EatIn,
+    Takeaway,
+}
+
+impl Dining {
+    pub const ALL: [Dining; 2] = [Dining::EatIn, Dining::Takeaway];
+
+    pub fn of(takeaway: bool) -> Self {
+        match takeaway {
+            true => Dining::Takeaway,
+            false => Dining::EatIn,
+        }
+    }
+}
+
+// The class of a menu item or bundle, `None` for anything no longer sold
+pub fn tax_class(item: &str) -> Option<TaxClass> {
+    menu_item(item)
+        .map(|item| item.tax_class)
+        .or_else(|| bundle(item).map(|bundle| bundle.tax_class))
+}
+
+/*
+ * A rate charged on a class of items from a date on, e.g. 10% on food eaten in.
+ * A rate replaces the one for the same class and dining that took effect before it.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TaxRate {
+    // As printed on the bill, e.g. `VAT reduced`
+    pub name: String,
+    pub class: TaxClass,
+    // Eating in and takeaway alike when `None`
+    #[serde(default)]
+    pub dining: Option<Dining>,
+    // e.g. `5.5` for 5.5%, written as a string to keep it exact
+    pub percent: Decimal,
+    // In the restaurant's timezone, lines ordered from this day on are charged this rate
+    pub effective_from: NaiveDate,
+}
+
+impl TaxRate {
+    fn applies(&self, class: TaxClass, dining: Dining, date: NaiveDate) -> bool {
+        self.class == class
+            && self.dining.is_none_or(|d| d == dining)
+            && self.effective_from <= date
+    }
+
+    // The tax on `cents` before rounding, whether the tax is already in `cents` or added on top
+    pub fn tax_on(&self, cents: i64, prices_include_tax: bool) -> Decimal {
+        let cents = Decimal::from(cents);
+        let hundred = Decimal::ONE_HUNDRED;
+        match prices_include_tax {
+            true => cents * self.percent / (hundred + self.percent),
+            false => cents * self.percent / hundred,
+        }
+    }
+}
+
+/*
+ * The rate a line is charged: of the rates for its class and dining that have taken effect,
+ * the latest, preferring one for its dining over one for both when they start the same day.
+ */
+pub fn rate_for(
+    rates: &[TaxRate],
+    class: TaxClass,
+    dining: Dining,
+    date: NaiveDate,
+) -> Option<&TaxRate> {
+    rates
+        .iter()
+        .filter(|rate| rate.applies(class, dining, date))
+        .max_by_key(|rate| (rate.effective_from, rate.dining.is_some()))
+}
+
+// How fractions of a cent are settled, which differs between jurisdictions
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    // Half a cent and up rounds up
+    #[default]
+    HalfUp,
+    // Half a cent rounds to the even cent, also known as banker's rounding
+    HalfEven,
+    // Fractions of a cent are dropped
+    Down,
+}
+
+// Whether tax is rounded on every line or once for everything charged at a rate
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingLevel {
+    Line,
+    #[default]
+    Rate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TaxRounding {
+    pub mode: RoundingMode,
+    pub level: RoundingLevel,
+}
+
+impl TaxRounding {
+    // To a whole number of cents
+    pub fn round(&self, cents: Decimal) -> Decimal {
+        let strategy = match self.mode {
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::Down => RoundingStrategy::ToZero,
+        };
+        cents.round_dp_with_strategy(0, strategy)
+    }
+}
+
+// What was charged at one rate, shown on the bill
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TaxLine {
+    pub name: String,
+    pub percent: Decimal,
+    // What the tax was charged on, tax not included
+    pub taxable_cents: i64,
+    pub tax_cents: i64,
+}
+
+/*
+ * Adds up tax per rate. `charges` are the cents charged for each taxed line with the rate it
+ * is taxed at, after discounts, and rates show in the order they first appear.
+ */
+pub fn tax_lines(
+    charges: &[(&TaxRate, i64)],
+    prices_include_tax: bool,
+    rounding: TaxRounding,
+) -> Vec<TaxLine> {
+    let mut totals: Vec<(&TaxRate, i64, Decimal)> = vec![];
+    for (rate, cents) in charges {
+        let tax = rate.tax_on(*cents, prices_include_tax);
+        let tax = match rounding.level {
+            RoundingLevel::Line => rounding.round(tax),
+            RoundingLevel::Rate => tax,
+        };
+        match totals
+            .iter_mut()
+            .find(|(r, _, _)| r.name == rate.name && r.percent == rate.percent)
+        {
+            Some((_, charged, total)) => {
+                *charged += cents;
+                *total += tax;
+            }
+            None => totals.push((rate, *cents, tax)),
+        }
+    }
+
+    totals
+        .into_iter()
+        .map(|(rate, charged, tax)| {
+            let tax_cents = rounding
+                .round(tax)
+                .to_i64()
+                .expect("Tax on a bill fits in an i64.");
+            TaxLine {
+                name: rate.name.clone(),
+                percent: rate.percent.normalize(),
+                taxable_cents: match prices_include_tax {
+                    true => charged - tax_cents,
+                    false => charged,
+                },
+                tax_cents,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::tax::{
+        rate_for, tax_class, tax_lines, Dining, RoundingLevel, RoundingMode, TaxClass, TaxRate,
+        TaxRounding,
+    };
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    fn rate(name: &str, class: TaxClass, dining: Option<Dining>, percent: &str) -> TaxRate {
+        TaxRate {
+            name: name.to_string(),
+            class,
+            dining,
+            percent: percent.parse().unwrap(),
+            effective_from: date(1, 1),
+        }
+    }
+
+    fn rounding(mode: RoundingMode, level: RoundingLevel) -> TaxRounding {
+        TaxRounding { mode, level }
+    }
+
+    #[test]
+    fn every_item_and_bundle_has_a_tax_class() {
+        assert_eq!(tax_class("hamburger"), Some(TaxClass::Food));
+        assert_eq!(tax_class("cola"), Some(TaxClass::Drinks));
+        assert_eq!(tax_class("burger meal"), Some(TaxClass::Food));
+        assert_eq!(tax_class("pizza"), None);
+    }
+
+    #[test]
+    fn the_latest_rate_in_effect_applies() {
+        let mut raised = rate("VAT", TaxClass::Food, None, "12");
+        raised.effective_from = date(7, 1);
+        let rates = vec![rate("VAT", TaxClass::Food, None, "10"), raised];
+
+        let before = rate_for(&rates, TaxClass::Food, Dining::EatIn, date(6, 30)).unwrap();
+        let after = rate_for(&rates, TaxClass::Food, Dining::EatIn, date(7, 1)).unwrap();
+
+        assert_eq!(before.percent, Decimal::from(10));
+        assert_eq!(after.percent, Decimal::from(12));
+        assert!(rate_for(&rates, TaxClass::Drinks, Dining::EatIn, date(7, 1)).is_none());
+    }
+
+    #[test]
+    fn a_rate_for_takeaway_beats_one_for_both() {
+        let rates = vec![
+            rate("VAT", TaxClass::Food, None, "20"),
+            rate("VAT zero", TaxClass::Food, Some(Dining::Takeaway), "0"),
+        ];
+
+        let eat_in = rate_for(&rates, TaxClass::Food, Dining::EatIn, date(3, 1)).unwrap();
+        let takeaway = rate_for(&rates, TaxClass::Food, Dining::Takeaway, date(3, 1)).unwrap();
+
+        assert_eq!(eat_in.name, "VAT");
+        assert_eq!(takeaway.name, "VAT zero");
+    }
+
+    #[test]
+    fn tax_is_added_on_top_of_exclusive_prices() {
+        let food = rate("VAT", TaxClass::Food, None, "10");
+        let drinks = rate("VAT standard", TaxClass::Drinks, None, "21");
+
+        let lines = tax_lines(
+            &[(&food, 1900), (&drinks, 250), (&food, 350)],
+            false,
+            TaxRounding::default(),
+        );
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].taxable_cents, 2250);
+        assert_eq!(lines[0].tax_cents, 225);
+        // 52.5 cents rounds up
+        assert_eq!(lines[1].tax_cents, 53);
+    }
+
+    #[test]
+    fn tax_is_taken_out_of_inclusive_prices() {
+        let food = rate("VAT", TaxClass::Food, None, "10");
+
+        let lines = tax_lines(&[(&food, 1100)], true, TaxRounding::default());
+
+        assert_eq!(lines[0].tax_cents, 100);
+        assert_eq!(lines[0].taxable_cents, 1000);
+    }
+
+    #[test]
+    fn rounding_follows_the_jurisdictions_rules() {
+        let drinks = rate("VAT", TaxClass::Drinks, None, "21");
+        // 52.5 cents of tax
+        let charges = [(&drinks, 250)];
+
+        let half_up = tax_lines(
+            &charges,
+            false,
+            rounding(RoundingMode::HalfUp, RoundingLevel::Rate),
+        );
+        let half_even = tax_lines(
+            &charges,
+            false,
+            rounding(RoundingMode::HalfEven, RoundingLevel::Rate),
+        );
+        let down = tax_lines(
+            &charges,
+            false,
+            rounding(RoundingMode::Down, RoundingLevel::Rate),
+        );
+
+        assert_eq!(half_up[0].tax_cents, 53);
+        assert_eq!(half_even[0].tax_cents, 52);
+        assert_eq!(down[0].tax_cents, 52);
+    }
+
+    #[test]
+    fn rounding_per_line_can_differ_from_rounding_per_rate() {
+        let drinks = rate("VAT", TaxClass::Drinks, None, "21");
+        // 52.5 cents of tax on each line
+        let charges = [(&drinks, 250), (&drinks, 250)];
+
+        let per_line = tax_lines(
+            &charges,
+            false,
+            rounding(RoundingMode::HalfUp, RoundingLevel::Line),
+        );
+        let per_rate = tax_lines(
+            &charges,
+            false,
+            rounding(RoundingMode::HalfUp, RoundingLevel::Rate),
+        );
+
+        assert_eq!(per_line[0].tax_cents, 106);
+        assert_eq!(per_rate[0].tax_cents, 105);
+    }
+
+    #[test]
+    fn fractional_rates_are_exact() {
+        let food = rate("TVA", TaxClass::Food, None, "5.5");
+
+        let lines = tax_lines(&[(&food, 1000)], false, TaxRounding::default());
+
+        assert_eq!(lines[0].tax_cents, 55);
+        assert_eq!(lines[0].percent.to_string(), "5.5");
+    }
+}
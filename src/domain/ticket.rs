@@ -0,0 +1,178 @@
+use crate::domain::course::Course;
+use crate::domain::menu::menu_item;
+use crate::domain::receipt::{ascii, columns, wrap, ESC, GS};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+// A part of the kitchen with its own printer
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Station {
+    Grill,
+    Fryer,
+    Bar,
+}
+
+impl Station {
+    pub const ALL: [Station; 3] = [Station::Grill, Station::Fryer, Station::Bar];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Station::Grill => "grill",
+            Station::Fryer => "fryer",
+            Station::Bar => "bar",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Station, String> {
+        Station::ALL
+            .into_iter()
+            .find(|station| station.as_str() == s.trim())
+            .ok_or_else(|| format!("{} is not a station, expected grill, fryer or bar.", s))
+    }
+
+    // Anything no longer on the menu is made on the grill, the kitchen's main station
+    pub fn of(item: &str) -> Station {
+        menu_item(item).map_or(Station::Grill, |item| item.station)
+    }
+}
+
+// One line of a ticket, a bundle's components each have their own
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TicketLine {
+    pub quantity: i32,
+    pub item: String,
+    pub variant: String,
+    pub modifiers: Vec<String>,
+    pub note: Option<String>,
+    pub takeaway: bool,
+}
+
+// What one station is asked to make for a table at once
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Ticket {
+    pub station: Station,
+    pub table_no: i32,
+    // Set when the ticket is for a course that was held back, `None` for new orders
+    pub fired_course: Option<Course>,
+    pub sent_at: DateTime<Utc>,
+    pub lines: Vec<TicketLine>,
+}
+
+/*
+ * Commands for the station's ESC/POS printer. The table number is printed large so it can
+ * be read from across the pass, then every line in bold with its modifiers and note under it.
+ */
+pub fn render_ticket(ticket: &Ticket, timezone: Tz, width: usize) -> Vec<u8> {
+    let mut bytes = vec![ESC, b'@'];
+    // Centred, double width and height
+    bytes.extend([ESC, b'a', 1, GS, b'!', 0x11]);
+    bytes.extend(ascii(&format!("TABLE {}", ticket.table_no)));
+    bytes.extend([GS, b'!', 0]);
+    let heading = match ticket.fired_course {
+        None => format!("{} - NEW ORDER", ticket.station.as_str()),
+        Some(course) => format!("{} - FIRE {}", ticket.station.as_str(), course.as_str()),
+    };
+    bytes.extend(ascii(&heading.to_uppercase()));
+    bytes.extend([ESC, b'a', 0]);
+    let sent_at = ticket.sent_at.with_timezone(&timezone);
+    for line in columns(
+        &sent_at.format("%Y-%m-%d").to_string(),
+        &sent_at.format("%H:%M").to_string(),
+        width,
+    ) {
+        bytes.extend(ascii(&line));
+    }
+    bytes.extend(ascii(&"-".repeat(width)));
+
+    for line in &ticket.lines {
+        let name = match line.variant.as_str() {
+            "regular" => line.item.clone(),
+            variant => format!("{} ({})", line.item, variant),
+        };
+        bytes.extend([ESC, b'E', 1]);
+        for text in wrap(&format!("{} x {}", line.quantity, name), width) {
+            bytes.extend(ascii(&text));
+        }
+        bytes.extend([ESC, b'E', 0]);
+        let details = line
+            .modifiers
+            .iter()
+            .map(|modifier| format!("+ {}", modifier))
+            .chain(line.note.iter().map(|note| format!("NOTE: {}", note)))
+            .chain(line.takeaway.then(|| "TAKEAWAY".to_string()));
+        for detail in details {
+            for text in wrap(&detail, width.saturating_sub(2)) {
+                bytes.extend(ascii(&format!("  {}", text)));
+            }
+        }
+    }
+
+    bytes.extend(ascii(&"-".repeat(width)));
+    // Feed four lines and make a partial cut
+    bytes.extend([GS, b'V', 66, 4]);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::ticket::{render_ticket, Station, Ticket, TicketLine};
+    use crate::domain::Course;
+    use chrono::{TimeZone, Utc};
+
+    fn ticket(fired_course: Option<Course>) -> Ticket {
+        Ticket {
+            station: Station::Grill,
+            table_no: 7,
+            fired_course,
+            sent_at: Utc.with_ymd_and_hms(2026, 10, 19, 12, 5, 0).unwrap(),
+            lines: vec![TicketLine {
+                quantity: 2,
+                item: "hamburger".to_string(),
+                variant: "double".to_string(),
+                modifiers: vec!["no onions".to_string()],
+                note: Some("well done".to_string()),
+                takeaway: true,
+            }],
+        }
+    }
+
+    fn contains(bytes: &[u8], text: &str) -> bool {
+        bytes
+            .windows(text.len())
+            .any(|window| window == text.as_bytes())
+    }
+
+    #[test]
+    fn every_item_is_made_at_a_station() {
+        assert_eq!(Station::of("hamburger"), Station::Grill);
+        assert_eq!(Station::of("fries"), Station::Fryer);
+        assert_eq!(Station::of("cola"), Station::Bar);
+        assert_eq!(Station::of("pizza"), Station::Grill);
+        assert_eq!(Station::parse("bar").unwrap(), Station::Bar);
+        assert!(Station::parse("oven").is_err());
+    }
+
+    #[test]
+    fn a_ticket_shows_the_table_lines_and_what_to_change() {
+        let bytes = render_ticket(&ticket(None), chrono_tz::Europe::Madrid, 32);
+
+        assert!(contains(&bytes, "TABLE 7\n"));
+        assert!(contains(&bytes, "GRILL - NEW ORDER\n"));
+        // Madrid is two hours ahead in October
+        assert!(contains(&bytes, "14:05\n"));
+        assert!(contains(&bytes, "2 x hamburger (double)\n"));
+        assert!(contains(&bytes, "  + no onions\n"));
+        assert!(contains(&bytes, "  NOTE: well done\n"));
+        assert!(contains(&bytes, "  TAKEAWAY\n"));
+        assert_eq!(&bytes[bytes.len() - 4..], &[0x1d, b'V', 66, 4]);
+    }
+
+    #[test]
+    fn a_fired_course_says_so() {
+        let bytes = render_ticket(&ticket(Some(Course::Main)), chrono_tz::UTC, 32);
+
+        assert!(contains(&bytes, "GRILL - FIRE MAIN\n"));
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Duration, Utc};
+
+// Quotes are rounded up to this many minutes, nobody believes "in 7 minutes"
+const QUOTE_ROUNDING_MINUTES: u64 = 5;
+
+// A table as far as the waitlist is concerned, how many it seats and when it frees up
+#[derive(Debug, Clone, Copy)]
+pub struct TableTurn {
+    pub capacity: i32,
+    pub free_at: DateTime<Utc>,
+}
+
+/*
+ * How long a walk-in party of `party_size` is likely to wait for a table.
+ * Every party already waiting, given by size in the order they arrived, is assumed to take
+ * the first table they fit at to free up, the smallest if several do, before this party gets
+ * a turn. A table is expected to be in use for `turn` once someone sits at it.
+ * There is no quote for a party no table seats.
+ */
+pub fn quote_wait_minutes(
+    now: DateTime<Utc>,
+    tables: &[TableTurn],
+    parties_ahead: &[i32],
+    party_size: i32,
+    turn: Duration,
+) -> Option<i64> {
+    let mut tables: Vec<TableTurn> = tables
+        .iter()
+        .map(|table| TableTurn {
+            free_at: table.free_at.max(now),
+            ..*table
+        })
+        .collect();
+    let first_free = |tables: &[TableTurn], size: i32| -> Option<usize> {
+        tables
+            .iter()
+            .enumerate()
+            .filter(|(_, table)| table.capacity >= size)
+            .min_by_key(|(_, table)| (table.free_at, table.capacity))
+            .map(|(i, _)| i)
+    };
+
+    for &size in parties_ahead {
+        if let Some(i) = first_free(&tables, size) {
+            tables[i].free_at += turn;
+        }
+    }
+    let next = tables[first_free(&tables, party_size)?].free_at;
+
+    // Never negative, every table is free at `now` at the earliest
+    let minutes = ((next - now).num_seconds() as u64).div_ceil(60);
+    Some((minutes.div_ceil(QUOTE_ROUNDING_MINUTES) * QUOTE_ROUNDING_MINUTES) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{quote_wait_minutes, TableTurn};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, 20, 0, 0).unwrap()
+    }
+
+    fn table(capacity: i32, free_in_minutes: i64) -> TableTurn {
+        TableTurn {
+            capacity,
+            free_at: now() + Duration::minutes(free_in_minutes),
+        }
+    }
+
+    fn turn() -> Duration {
+        Duration::minutes(90)
+    }
+
+    #[test]
+    fn a_free_table_means_no_wait() {
+        let quote = quote_wait_minutes(now(), &[table(2, -30)], &[], 2, turn());
+        assert_eq!(quote, Some(0));
+    }
+
+    #[test]
+    fn the_wait_is_until_the_first_table_frees_up_rounded_up() {
+        let tables = [table(4, 42), table(4, 21), table(2, 0)];
+        let quote = quote_wait_minutes(now(), &tables, &[], 3, turn());
+        assert_eq!(quote, Some(25));
+    }
+
+    #[test]
+    fn parties_ahead_take_the_first_tables_to_free_up() {
+        let tables = [table(4, 10), table(4, 30)];
+        assert_eq!(
+            quote_wait_minutes(now(), &tables, &[4], 4, turn()),
+            Some(30)
+        );
+        assert_eq!(
+            quote_wait_minutes(now(), &tables, &[4, 2], 4, turn()),
+            Some(100)
+        );
+    }
+
+    #[test]
+    fn parties_ahead_take_the_smallest_table_they_fit_at() {
+        let tables = [table(8, 0), table(2, 0)];
+        assert_eq!(quote_wait_minutes(now(), &tables, &[2], 8, turn()), Some(0));
+    }
+
+    #[test]
+    fn there_is_no_quote_without_a_table_to_wait_for() {
+        assert_eq!(
+            quote_wait_minutes(now(), &[table(4, 0)], &[], 6, turn()),
+            None
+        );
+    }
+}
@@ -0,0 +1,23 @@
+// Postgres error code for a row breaking a unique index
+pub(crate) const UNIQUE_VIOLATION: &str = "23505";
+
+// What bookings, sessions, payments, discounts and the kitchen can fail with
+#[derive(Debug)]
+pub enum DomainError {
+    // Something that could never be done, whatever else is going on
+    Invalid(String),
+    NotFound(String),
+    // Fine on its own, but not with things as they are, e.g. `table_booked`
+    Conflict { code: &'static str, message: String },
+    // The card provider turned the payment down
+    Declined(String),
+    // Worth more than staff can give without a manager's code
+    ApprovalRequired(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for DomainError {
+    fn from(e: sqlx::Error) -> Self {
+        DomainError::Database(e)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::configuration::RuntimeSettings;
+use crate::kitchen::Fired;
+use crate::sessions::{Merge, Transfer};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+// Events buffered per subscriber before a slow subscriber starts missing them
+const EVENT_BUFFER: usize = 64;
+
+// Something that happened which other parts of the system, or clients, may react to
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    RuntimeSettingsChanged {
+        source: ChangeSource,
+        settings: RuntimeSettings,
+    },
+    // An ingredient dropped to its low stock threshold, time to restock
+    LowStock {
+        ingredient: String,
+        stock: i32,
+        low_stock_threshold: i32,
+    },
+    // An item was 86'd or put back on
+    AvailabilityChanged {
+        item: String,
+        available: bool,
+        restore_at: Option<DateTime<Utc>>,
+    },
+    // A party and their open orders moved to another table
+    TableTransferred(Transfer),
+    // Two parties were combined into one session
+    SessionsMerged(Merge),
+    // Held lines of a course were sent to the kitchen
+    CourseFired(Fired),
+}
+
+impl Event {
+    // Used as the event name in server-sent event streams
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::RuntimeSettingsChanged { .. } => "runtime_settings_changed",
+            Event::LowStock { .. } => "low_stock",
+            Event::AvailabilityChanged { .. } => "availability_changed",
+            Event::TableTransferred(_) => "table_transferred",
+            Event::SessionsMerged(_) => "sessions_merged",
+            Event::CourseFired(_) => "course_fired",
+        }
+    }
+}
+
+// What caused runtime settings to change
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeSource {
+    File,
+    Admin,
+}
+
+impl std::fmt::Display for ChangeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeSource::File => write!(f, "file"),
+            ChangeSource::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/*
+ * Fans events out to every subscriber across actix workers.
+ * Publishing never blocks, events with no subscriber are simply dropped.
+ */
+#[derive(Clone)]
+pub struct EventBus(broadcast::Sender<Event>);
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self(sender)
+    }
+
+    pub fn publish(&self, event: Event) {
+        // An error only means nobody is listening
+        let _ = self.0.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.0.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+
+/*
+ * Where the ids of new rows come from, so tests can know an order's id before placing it.
+ */
+pub trait IdGenerator: Send + Sync {
+    fn new_id(&self) -> Uuid;
+}
+
+// Random ids, used everywhere outside of tests
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn new_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+// Hands out 00000000-0000-0000-0000-000000000001, then ...0002 and so on
+#[derive(Default)]
+pub struct SequentialIds(AtomicU64);
+
+impl SequentialIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn new_id(&self) -> Uuid {
+        Uuid::from_u128(u128::from(self.0.fetch_add(1, Ordering::SeqCst) + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::id_generator::{IdGenerator, SequentialIds};
+    use uuid::Uuid;
+
+    #[test]
+    fn sequential_ids_count_up_from_one() {
+        let ids = SequentialIds::new();
+        assert_eq!(ids.new_id(), Uuid::from_u128(1));
+        assert_eq!(ids.new_id(), Uuid::from_u128(2));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::events::Event;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::BTreeMap;
+
+// An ingredient whose stock is tracked
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Ingredient {
+    pub name: String,
+    pub stock: i32,
+    pub low_stock_threshold: i32,
+}
+
+// An ingredient that has just dropped to its low stock threshold
+#[derive(Debug, PartialEq, Eq)]
+pub struct LowStock {
+    pub ingredient: String,
+    pub stock: i32,
+    pub low_stock_threshold: i32,
+}
+
+impl From<LowStock> for Event {
+    fn from(low: LowStock) -> Self {
+        Event::LowStock {
+            ingredient: low.ingredient,
+            stock: low.stock,
+            low_stock_threshold: low.low_stock_threshold,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StockError {
+    // Taking the ingredient would leave less than none of it
+    SoldOut(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for StockError {
+    fn from(e: sqlx::Error) -> Self {
+        StockError::Database(e)
+    }
+}
+
+/*
+ * Takes ingredients out of stock as part of placing an order, so the order and the stock
+ * change together or not at all. Ingredients that are not tracked are skipped.
+ * Returns the ingredients that dropped to their low stock threshold with this order.
+ */
+#[tracing::instrument(name = "Taking ingredients out of stock", skip(transaction))]
+pub async fn deduct_stock(
+    transaction: &mut Transaction<'_, Postgres>,
+    ingredients: &BTreeMap<&'static str, i32>,
+) -> Result<Vec<LowStock>, StockError> {
+    let mut low_stock = Vec::new();
+
+    // Rows are locked in name order, so concurrent orders cannot deadlock
+    for (&name, &quantity) in ingredients {
+        let updated = sqlx::query!(
+            r#"
+    UPDATE ingredients
+    SET stock = stock - $2, updated_at = $3
+    WHERE name = $1 AND stock >= $2
+    RETURNING stock, low_stock_threshold
+    "#,
+            name,
+            quantity,
+            Utc::now()
+        )
+        .fetch_optional(&mut **transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+
+        match updated {
+            Some(row) => {
+                let crossed_threshold = row.stock <= row.low_stock_threshold
+                    && row.stock + quantity > row.low_stock_threshold;
+                if crossed_threshold {
+                    low_stock.push(LowStock {
+                        ingredient: name.to_string(),
+                        stock: row.stock,
+                        low_stock_threshold: row.low_stock_threshold,
+                    });
+                }
+            }
+            None => {
+                if is_tracked(transaction, name).await? {
+                    return Err(StockError::SoldOut(name.to_string()));
+                }
+            }
+        }
+    }
+
+    Ok(low_stock)
+}
+
+async fn is_tracked(
+    transaction: &mut Transaction<'_, Postgres>,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let tracked = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM ingredients WHERE name = $1) AS "tracked!""#,
+        name
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+    Ok(tracked.tracked)
+}
+
+// Puts the ingredients of voided orders back into stock
+#[tracing::instrument(name = "Returning ingredients to stock", skip(transaction))]
+pub async fn restore_stock(
+    transaction: &mut Transaction<'_, Postgres>,
+    ingredients: &BTreeMap<&'static str, i32>,
+) -> Result<(), sqlx::Error> {
+    for (&name, &quantity) in ingredients {
+        sqlx::query!(
+            r#"
+    UPDATE ingredients
+    SET stock = stock + $2, updated_at = $3
+    WHERE name = $1
+    "#,
+            name,
+            quantity,
+            Utc::now()
+        )
+        .execute(&mut **transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    }
+    Ok(())
+}
+
+#[tracing::instrument(name = "Retrieving stock levels from the database", skip(pool))]
+pub async fn list_ingredients(pool: &PgPool) -> Result<Vec<Ingredient>, sqlx::Error> {
+    sqlx::query_as!(
+        Ingredient,
+        r#"
+    SELECT name, stock, low_stock_threshold FROM ingredients
+    ORDER BY name
+    "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Sets the stock of an ingredient, starting to track it if it was not already
+#[tracing::instrument(name = "Setting stock level in the database", skip(pool))]
+pub async fn set_stock(pool: &PgPool, ingredient: &Ingredient) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+    INSERT INTO ingredients (name, stock, low_stock_threshold, updated_at)
+    VALUES ($1, $2, $3, $4)
+    ON CONFLICT (name) DO UPDATE SET
+        stock = EXCLUDED.stock,
+        low_stock_threshold = EXCLUDED.low_stock_threshold,
+        updated_at = EXCLUDED.updated_at
+    "#,
+        ingredient.name,
+        ingredient.stock,
+        ingredient.low_stock_threshold,
+        Utc::now()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::domain::Course;
+use crate::error::DomainError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// What firing a course sent to the kitchen, for the audit trail and subscribers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Fired {
+    pub session_id: Uuid,
+    pub table_no: i32,
+    pub course: Course,
+    pub order_ids: Vec<Uuid>,
+    pub fired_at: DateTime<Utc>,
+}
+
+/*
+ * Sends the held lines of one course of a party to the kitchen, e.g. the mains once the
+ * starters are cleared. Lines of the course that were never held are already cooking and
+ * are left alone. The caller commits, like `transfer_table`.
+ */
+#[tracing::instrument(name = "Firing a course", skip(transaction))]
+pub async fn fire_course(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+    course: Course,
+    now: DateTime<Utc>,
+) -> Result<Fired, DomainError> {
+    let session = sqlx::query!(
+        "SELECT table_no, closed_at FROM table_sessions WHERE id = $1 FOR UPDATE",
+        session_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .ok_or_else(|| DomainError::NotFound(format!("There is no session {}.", session_id)))?;
+    if session.closed_at.is_some() {
+        return Err(DomainError::Conflict {
+            code: "session_closed",
+            message: format!("Session {} is already closed.", session_id),
+        });
+    }
+
+    let fired = sqlx::query!(
+        r#"
+    UPDATE orders SET fired_at = $3
+    WHERE session_id = $1 AND course = $2 AND fired_at IS NULL
+    RETURNING id
+    "#,
+        session_id,
+        course.as_str(),
+        now
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    if fired.is_empty() {
+        return Err(DomainError::Conflict {
+            code: "nothing_to_fire",
+            message: format!(
+                "Session {} has no {} held back.",
+                session_id,
+                course.as_str()
+            ),
+        });
+    }
+
+    Ok(Fired {
+        session_id,
+        table_no: session.table_no,
+        course,
+        order_ids: fired.into_iter().map(|row| row.id).collect(),
+        fired_at: now,
+    })
+}
+
+// A line the kitchen is working on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KitchenTicket {
+    pub id: Uuid,
+    pub table_no: i32,
+    pub item: String,
+    pub variant: String,
+    pub quantity: i32,
+    pub modifiers: Vec<String>,
+    pub note: Option<String>,
+    pub course: String,
+    pub fired_at: DateTime<Utc>,
+    // When the line should be ready, its preparation time after being fired
+    pub due_at: DateTime<Utc>,
+}
+
+/*
+ * Fired lines that are not ready yet, soonest due first. Held lines have not reached the
+ * kitchen and a bundle's own line is only a price, its components are what gets cooked.
+ */
+#[tracing::instrument(name = "Retrieving the kitchen queue from the database", skip(pool))]
+pub async fn kitchen_queue(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+) -> Result<Vec<KitchenTicket>, sqlx::Error> {
+    sqlx::query_as!(
+        KitchenTicket,
+        r#"
+    SELECT id, table_no, item, variant, quantity, modifiers, note, course,
+        fired_at AS "fired_at!",
+        fired_at + make_interval(mins => preparation_time) AS "due_at!"
+    FROM orders o
+    WHERE fired_at IS NOT NULL
+        AND fired_at + make_interval(mins => preparation_time) > $1
+        AND NOT EXISTS (SELECT 1 FROM orders c WHERE c.parent_id = o.id)
+    ORDER BY fired_at + make_interval(mins => preparation_time), id
+    "#,
+        now
+    )
+    .fetch_all(pool)
+    .await
+}
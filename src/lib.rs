@@ -1,6 +1,33 @@
 #![allow(clippy::toplevel_ref_arg)]
+pub mod audit;
+pub mod auth;
+pub mod availability;
+pub mod bills;
+pub mod cli;
+pub mod clock;
 pub mod configuration;
 pub mod domain;
+pub mod error;
+pub mod events;
+pub mod id_generator;
+pub mod inventory;
+pub mod kitchen;
+pub mod metrics;
+pub mod migration;
+pub mod payment_provider;
+pub mod payments;
+pub mod printer;
+pub mod printing;
+pub mod promotions;
+pub mod rate_limit;
+pub mod receipts;
+pub mod request_id;
+pub mod reservations;
+pub mod rng;
 pub mod routes;
+pub mod runtime_settings;
+pub mod sessions;
+pub mod shutdown;
 pub mod startup;
 pub mod telemetry;
+pub mod waitlist;
@@ -1,14 +1,88 @@
-use restaurant::configuration::get_configuration;
-use restaurant::startup::Application;
-use restaurant::telemetry::{get_user, init_user};
+use restaurant::cli::{Command, Task};
+use restaurant::configuration::{
+    configuration_directory, current_environment, load_configuration, Settings,
+};
+use restaurant::migration::{pending_migrations, run_migrations};
+use restaurant::startup::{get_connection_pool, Application};
+use restaurant::telemetry::{get_tracer_provider, get_user, init_user, Redact};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let user = get_user("restaurant".into(), "info".into(), std::io::stdout);
+    let command = Command::parse(std::env::args().skip(1))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let configuration_directory =
+        configuration_directory().map_err(|e| std::io::Error::other(e.to_string()))?;
+    let environment = current_environment().map_err(|e| std::io::Error::other(e.to_string()))?;
+    let configuration = load_configuration(&configuration_directory, &environment)
+        .map_err(|e| std::io::Error::other(format!("Failed to read configuration: {}", e)))?;
+
+    let task = match command {
+        // Nothing is started, so there is no need for telemetry
+        Command::ConfigCheck => {
+            println!("Environment: {}", environment.as_str());
+            println!(
+                "Configuration directory: {}",
+                configuration_directory.display()
+            );
+            // Secrets are `Secret` values, which never print their contents
+            println!("{:#?}", configuration);
+            return Ok(());
+        }
+        Command::Run(task) => task,
+    };
+
+    let tracer_provider = configuration
+        .telemetry
+        .otlp
+        .as_ref()
+        .map(|otlp| get_tracer_provider(otlp, &configuration.telemetry.redacted_fields))
+        .transpose()
+        .map_err(std::io::Error::other)?;
+    let user = get_user(
+        "restaurant".into(),
+        configuration.runtime.log_level.clone(),
+        Redact::new(std::io::stdout, &configuration.telemetry.redacted_fields),
+        tracer_provider.as_ref(),
+    );
     init_user(user);
 
-    let configuration = get_configuration().expect("Failed to read configuration.");
-    let application = Application::build(configuration).await?;
-    application.run_until_stopped().await?;
+    let result = run_task(task, configuration).await;
+
+    // Spans are exported in batches, send whatever is left before exiting
+    if let Some(tracer_provider) = tracer_provider {
+        for flushed in tracer_provider.force_flush() {
+            if let Err(e) = flushed {
+                eprintln!("Failed to export spans: {}", e);
+            }
+        }
+    }
+    result
+}
+
+async fn run_task(task: Task, configuration: Settings) -> std::io::Result<()> {
+    match task {
+        Task::Serve => {
+            let application = Application::build(configuration).await?;
+            application.run_until_stopped().await?;
+        }
+        Task::Migrate { dry_run } => {
+            let connection_pool = get_connection_pool(&configuration.database);
+            if dry_run {
+                let pending = pending_migrations(&connection_pool)
+                    .await
+                    .map_err(std::io::Error::other)?;
+                if pending.is_empty() {
+                    println!("The database schema is up to date.");
+                }
+                for migration in pending {
+                    println!("Pending: {}/{}", migration.version, migration.description);
+                }
+            } else {
+                run_migrations(&connection_pool)
+                    .await
+                    .map_err(std::io::Error::other)?;
+            }
+        }
+    }
     Ok(())
 }
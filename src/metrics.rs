@@ -0,0 +1,148 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use std::time::Instant;
+
+/*
+ * Metrics are kept in a registry owned by the application rather than the prometheus
+ * global registry. This keeps every application instance, and therefore every test, isolated.
+ */
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub db_pool_connections: IntGauge,
+    pub db_pool_idle_connections: IntGauge,
+    pub orders_placed: IntCounterVec,
+    pub order_voids: IntCounter,
+    pub order_validation_failures: IntCounterVec,
+    pub kitchen_backlog: IntGaugeVec,
+    pub rate_limited_requests: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests handled"),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to handle HTTP requests",
+            ),
+            &["method", "route", "status"],
+        )?;
+        let db_pool_connections = IntGauge::new(
+            "db_pool_connections",
+            "Number of connections currently held by the pool",
+        )?;
+        let db_pool_idle_connections = IntGauge::new(
+            "db_pool_idle_connections",
+            "Number of idle connections in the pool",
+        )?;
+        let orders_placed = IntCounterVec::new(
+            Opts::new("orders_placed_total", "Number of orders placed"),
+            &["item"],
+        )?;
+        let order_voids = IntCounter::new("order_voids_total", "Number of orders voided")?;
+        let order_validation_failures = IntCounterVec::new(
+            Opts::new(
+                "order_validation_failures_total",
+                "Number of orders rejected by validation",
+            ),
+            &["reason"],
+        )?;
+        let kitchen_backlog = IntGaugeVec::new(
+            Opts::new(
+                "kitchen_backlog_orders",
+                "Number of orders still being prepared",
+            ),
+            &["item"],
+        )?;
+
+        let rate_limited_requests = IntCounterVec::new(
+            Opts::new(
+                "rate_limited_requests_total",
+                "Number of requests rejected by rate limiting",
+            ),
+            &["group"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(db_pool_idle_connections.clone()))?;
+        registry.register(Box::new(orders_placed.clone()))?;
+        registry.register(Box::new(order_voids.clone()))?;
+        registry.register(Box::new(order_validation_failures.clone()))?;
+        registry.register(Box::new(kitchen_backlog.clone()))?;
+        registry.register(Box::new(rate_limited_requests.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_pool_connections,
+            db_pool_idle_connections,
+            orders_placed,
+            order_voids,
+            order_validation_failures,
+            kitchen_backlog,
+            rate_limited_requests,
+        })
+    }
+
+    // Gauges describing the pool are sampled when metrics are scraped, sqlx does not say
+    // how many tasks are waiting for a connection so there is no gauge for that
+    pub fn observe_pool(&self, pool: &PgPool) {
+        self.db_pool_connections.set(pool.size() as i64);
+        self.db_pool_idle_connections.set(pool.num_idle() as i64);
+    }
+
+    // Render every registered metric in the prometheus text format
+    pub fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+// Middleware recording a counter and latency histogram for every request
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    // Use the route pattern rather than the path so ids don't create a series each
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+
+    let res = next.call(req).await;
+
+    if let Some(metrics) = metrics {
+        let status = match &res {
+            Ok(res) => res.status(),
+            Err(e) => e.as_response_error().status_code(),
+        };
+        let labels = [method.as_str(), route.as_str(), status.as_str()];
+        metrics.http_requests.with_label_values(&labels).inc();
+        metrics
+            .http_request_duration
+            .with_label_values(&labels)
+            .observe(start.elapsed().as_secs_f64());
+    }
+
+    res
+}
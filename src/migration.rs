@@ -0,0 +1,61 @@
+use sqlx::migrate::{Migrate, MigrateError, Migration, Migrator};
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+// The migrations in `./migrations` are embedded into the binary at compile time
+// so every build carries the exact schema it expects to run against
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+// A migration known to this binary that has not been applied to the database yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+impl From<&Migration> for PendingMigration {
+    fn from(migration: &Migration) -> Self {
+        Self {
+            version: migration.version,
+            description: migration.description.to_string(),
+        }
+    }
+}
+
+#[tracing::instrument(name = "Applying database migrations", skip(pool))]
+pub async fn run_migrations(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+#[tracing::instrument(name = "Listing pending database migrations", skip(pool))]
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<PendingMigration>, MigrateError> {
+    let mut connection = pool.acquire().await?;
+
+    // A database that has never been migrated has no bookkeeping table.
+    // We check for it rather than creating it so that a dry run never writes anything.
+    let has_migrations_table: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&mut *connection)
+            .await?;
+
+    let applied: HashSet<i64> = if has_migrations_table {
+        // A migration that failed half way needs a human, not another attempt
+        if let Some(version) = connection.dirty_version().await? {
+            return Err(MigrateError::Dirty(version));
+        }
+        connection
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|m| m.version)
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+        .map(PendingMigration::from)
+        .collect())
+}
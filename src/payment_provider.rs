@@ -0,0 +1,190 @@
+use crate::domain::Tender;
+use futures::future::BoxFuture;
+use std::sync::Mutex;
+
+// The provider turned a charge or refund down, e.g. a card was declined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declined(pub String);
+
+/*
+ * Takes card and voucher payments, cash is counted by staff and never goes through a provider.
+ * Both calls return the provider's own reference for the transaction, if it has one.
+ */
+pub trait PaymentProvider: Send + Sync {
+    // Stored with every payment, so it is known later who to refund through
+    fn name(&self) -> &'static str;
+
+    fn charge<'a>(
+        &'a self,
+        tender: Tender,
+        amount_cents: i64,
+        reference: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<String>, Declined>>;
+
+    fn refund<'a>(
+        &'a self,
+        tender: Tender,
+        amount_cents: i64,
+        reference: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<String>, Declined>>;
+}
+
+/*
+ * For a standalone card terminal and paper vouchers: staff take the payment by hand and
+ * key in the terminal's reference or the voucher's code, which is recorded as given.
+ */
+pub struct OfflineProvider;
+
+impl PaymentProvider for OfflineProvider {
+    fn name(&self) -> &'static str {
+        "offline"
+    }
+
+    fn charge<'a>(
+        &'a self,
+        _tender: Tender,
+        _amount_cents: i64,
+        reference: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<String>, Declined>> {
+        Box::pin(async move { Ok(reference.map(str::to_string)) })
+    }
+
+    fn refund<'a>(
+        &'a self,
+        _tender: Tender,
+        _amount_cents: i64,
+        _reference: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<String>, Declined>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+// A charge or refund the mock provider was asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockTransaction {
+    pub tender: Tender,
+    pub amount_cents: i64,
+    pub reference: Option<String>,
+}
+
+#[derive(Default)]
+struct MockState {
+    decline: Option<String>,
+    charges: Vec<MockTransaction>,
+    refunds: Vec<MockTransaction>,
+}
+
+// Approves everything with references `mock-1`, `mock-2`... until told to decline
+#[derive(Default)]
+pub struct MockProvider(Mutex<MockState>);
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Every charge and refund from now on is declined with `reason`
+    pub fn decline(&self, reason: &str) {
+        self.state().decline = Some(reason.to_string());
+    }
+
+    pub fn approve(&self) {
+        self.state().decline = None;
+    }
+
+    pub fn charges(&self) -> Vec<MockTransaction> {
+        self.state().charges.clone()
+    }
+
+    pub fn refunds(&self) -> Vec<MockTransaction> {
+        self.state().refunds.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record(
+        &self,
+        refund: bool,
+        tender: Tender,
+        amount_cents: i64,
+        reference: Option<&str>,
+    ) -> Result<Option<String>, Declined> {
+        let mut state = self.state();
+        if let Some(reason) = &state.decline {
+            return Err(Declined(reason.clone()));
+        }
+        let transaction = MockTransaction {
+            tender,
+            amount_cents,
+            reference: reference.map(str::to_string),
+        };
+        if refund {
+            state.refunds.push(transaction);
+        } else {
+            state.charges.push(transaction);
+        }
+        Ok(Some(format!(
+            "mock-{}",
+            state.charges.len() + state.refunds.len()
+        )))
+    }
+}
+
+impl PaymentProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn charge<'a>(
+        &'a self,
+        tender: Tender,
+        amount_cents: i64,
+        reference: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<String>, Declined>> {
+        Box::pin(async move { self.record(false, tender, amount_cents, reference) })
+    }
+
+    fn refund<'a>(
+        &'a self,
+        tender: Tender,
+        amount_cents: i64,
+        reference: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<String>, Declined>> {
+        Box::pin(async move { self.record(true, tender, amount_cents, reference) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Tender;
+    use crate::payment_provider::{Declined, MockProvider, OfflineProvider, PaymentProvider};
+
+    #[actix_rt::test]
+    async fn the_offline_provider_records_the_reference_it_is_given() {
+        let reference = OfflineProvider
+            .charge(Tender::Card, 1000, Some("slip 0042"))
+            .await;
+
+        assert_eq!(reference, Ok(Some("slip 0042".to_string())));
+    }
+
+    #[actix_rt::test]
+    async fn the_mock_provider_declines_once_told_to() {
+        let provider = MockProvider::new();
+        assert_eq!(
+            provider.charge(Tender::Card, 1000, None).await,
+            Ok(Some("mock-1".to_string()))
+        );
+
+        provider.decline("insufficient funds");
+
+        assert_eq!(
+            provider.refund(Tender::Card, 1000, Some("mock-1")).await,
+            Err(Declined("insufficient funds".to_string()))
+        );
+        assert_eq!(provider.charges().len(), 1);
+        assert!(provider.refunds().is_empty());
+    }
+}
@@ -0,0 +1,533 @@
+use crate::audit::record_audit;
+use crate::bills::session_bill;
+use crate::configuration::TaxSettings;
+use crate::domain::{NewPayment, Tender};
+use crate::error::DomainError;
+use crate::payment_provider::{Declined, PaymentProvider};
+use crate::request_id::RequestId;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// Waiting on the provider, a pending payment already counts towards the balance
+const PENDING: &str = "pending";
+const COMPLETED: &str = "completed";
+// Declined, or given back because it could not be recorded, a failed payment counts for nothing
+const FAILED: &str = "failed";
+
+// Money taken towards a session's bill, or given back when `refund_of` is set
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Payment {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub tender: String,
+    // Negative for refunds
+    pub amount_cents: i64,
+    pub tip_cents: i64,
+    pub tendered_cents: i64,
+    pub change_cents: i64,
+    pub provider: Option<String>,
+    pub reference: Option<String>,
+    pub refund_of: Option<Uuid>,
+    // `pending`, `completed` or `failed`
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// Where a session stands, what it was charged against what has been paid
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub session_id: Uuid,
+    pub table_no: i32,
+    pub total_cents: i64,
+    // Net of refunds, tips not included, pending payments and refunds included
+    pub paid_cents: i64,
+    pub tips_cents: i64,
+    // Still to pay, negative when more was paid than is now owed, e.g. after a void
+    pub balance_cents: i64,
+    pub closed_at: Option<DateTime<Utc>>,
+    // Failed payments are left out
+    pub payments: Vec<Payment>,
+}
+
+impl From<Declined> for DomainError {
+    fn from(e: Declined) -> Self {
+        DomainError::Declined(e.0)
+    }
+}
+
+struct LockedSession {
+    table_no: i32,
+    closed_at: Option<DateTime<Utc>>,
+}
+
+// Payments, refunds and closing a session take turns on the session's row
+async fn lock_session(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+) -> Result<LockedSession, DomainError> {
+    sqlx::query_as!(
+        LockedSession,
+        "SELECT table_no, closed_at FROM table_sessions WHERE id = $1 FOR UPDATE",
+        session_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .ok_or_else(|| DomainError::NotFound(format!("There is no session {}.", session_id)))
+}
+
+fn session_closed(session_id: Uuid) -> DomainError {
+    DomainError::Conflict {
+        code: "session_closed",
+        message: format!("Session {} is already closed.", session_id),
+    }
+}
+
+fn payment_not_completed(payment_id: Uuid, status: &str) -> DomainError {
+    DomainError::Conflict {
+        code: "payment_not_completed",
+        message: format!("Payment {} is {}.", payment_id, status),
+    }
+}
+
+pub(crate) async fn session_payments(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+) -> Result<Vec<Payment>, sqlx::Error> {
+    sqlx::query_as!(
+        Payment,
+        r#"
+    SELECT id, session_id, tender, amount_cents, tip_cents, tendered_cents, change_cents,
+        provider, reference, refund_of, status, created_at
+    FROM payments
+    WHERE session_id = $1 AND status <> 'failed'
+    ORDER BY created_at, id
+    "#,
+        session_id
+    )
+    .fetch_all(&mut **transaction)
+    .await
+}
+
+#[tracing::instrument(name = "Retrieving a session's account", skip(transaction, tax))]
+pub async fn account(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+    timezone: Tz,
+    tax: &TaxSettings,
+) -> Result<Account, DomainError> {
+    let session = lock_session(transaction, session_id).await?;
+    let total_cents = session_bill(transaction, session_id, session.table_no, timezone, tax)
+        .await?
+        .total_cents;
+    let payments = session_payments(transaction, session_id).await?;
+    let paid_cents = payments.iter().map(|payment| payment.amount_cents).sum();
+
+    Ok(Account {
+        session_id,
+        table_no: session.table_no,
+        total_cents,
+        paid_cents,
+        tips_cents: payments.iter().map(|payment| payment.tip_cents).sum(),
+        balance_cents: total_cents - paid_cents,
+        closed_at: session.closed_at,
+        payments,
+    })
+}
+
+async fn insert_payment(
+    transaction: &mut Transaction<'_, Postgres>,
+    payment: &Payment,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+    INSERT INTO payments (
+        id, session_id, tender, amount_cents, tip_cents, tendered_cents, change_cents,
+        provider, reference, refund_of, status, created_at
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+    "#,
+        payment.id,
+        payment.session_id,
+        payment.tender,
+        payment.amount_cents,
+        payment.tip_cents,
+        payment.tendered_cents,
+        payment.change_cents,
+        payment.provider,
+        payment.reference,
+        payment.refund_of,
+        payment.status,
+        payment.created_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    Ok(())
+}
+
+/*
+ * Voids change what a party owes, so they wait for the session's payments like a payment would.
+ * Once it is closed or paid in full its lines stay as they are, money is given back by refunding.
+ */
+pub async fn lock_session_for_void(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+    timezone: Tz,
+    tax: &TaxSettings,
+) -> Result<(), DomainError> {
+    let account = account(transaction, session_id, timezone, tax).await?;
+    if account.closed_at.is_some() {
+        return Err(session_closed(session_id));
+    }
+    if account.paid_cents > 0 && account.balance_cents <= 0 {
+        return Err(DomainError::Conflict {
+            code: "session_paid",
+            message: format!(
+                "Session {} has been paid for, refund a payment instead.",
+                session_id
+            ),
+        });
+    }
+    Ok(())
+}
+
+async fn set_status(pool: &PgPool, id: Uuid, status: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!("UPDATE payments SET status = $2 WHERE id = $1", id, status)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+    Ok(())
+}
+
+// Records what the provider answered for a pending payment, along with its audit record
+async fn complete_payment(
+    pool: &PgPool,
+    payment: &Payment,
+    action: &str,
+    request_id: &RequestId,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    sqlx::query!(
+        "UPDATE payments SET status = $2, reference = $3 WHERE id = $1",
+        payment.id,
+        payment.status,
+        payment.reference
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    record_audit(
+        &mut transaction,
+        request_id,
+        action,
+        serde_json::json!(payment),
+    )
+    .await?;
+    transaction.commit().await
+}
+
+/*
+ * Takes a payment towards an open session. A payment can cover part of the balance,
+ * but not more than is left, anything beyond that is a tip or, for cash, change.
+ * Card and voucher payments are recorded as pending and committed before the provider is asked
+ * to charge them, so no lock is held while it answers and a charge is never lost to a
+ * rollback. Should the charge then fail to be recorded, it is refunded.
+ */
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(
+    name = "Recording a payment",
+    skip(pool, provider, payment, tax, request_id)
+)]
+pub async fn record_payment(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    id: Uuid,
+    session_id: Uuid,
+    payment: &NewPayment,
+    now: DateTime<Utc>,
+    timezone: Tz,
+    tax: &TaxSettings,
+    request_id: &RequestId,
+) -> Result<Payment, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let account = account(&mut transaction, session_id, timezone, tax).await?;
+    if account.closed_at.is_some() {
+        return Err(session_closed(session_id));
+    }
+    if payment.amount_cents > account.balance_cents {
+        return Err(DomainError::Conflict {
+            code: "overpayment",
+            message: format!(
+                "Only {} cents are left to pay, anything more is a tip or change.",
+                account.balance_cents.max(0)
+            ),
+        });
+    }
+
+    let cash = payment.tender == Tender::Cash;
+    let mut recorded = Payment {
+        id,
+        session_id,
+        tender: payment.tender.as_str().to_string(),
+        amount_cents: payment.amount_cents,
+        tip_cents: payment.tip_cents,
+        tendered_cents: payment.tendered_cents,
+        change_cents: payment.change_cents,
+        provider: (!cash).then(|| provider.name().to_string()),
+        reference: payment.reference.clone(),
+        refund_of: None,
+        status: if cash { COMPLETED } else { PENDING }.to_string(),
+        created_at: now,
+    };
+    insert_payment(&mut transaction, &recorded).await?;
+    if cash {
+        record_audit(
+            &mut transaction,
+            request_id,
+            "payment_recorded",
+            serde_json::json!(recorded),
+        )
+        .await?;
+        transaction.commit().await?;
+        return Ok(recorded);
+    }
+    // The pending payment holds its share of the balance while the provider answers
+    transaction.commit().await?;
+
+    let charged = provider
+        .charge(
+            payment.tender,
+            payment.due_cents(),
+            payment.reference.as_deref(),
+        )
+        .await;
+    recorded.reference = match charged {
+        Ok(reference) => reference,
+        Err(declined) => {
+            // The decline is what the caller needs to hear about, whether or not it is recorded
+            if let Err(e) = set_status(pool, id, FAILED).await {
+                tracing::error!("Declined payment {} is left pending: {:?}", id, e);
+            }
+            return Err(declined.into());
+        }
+    };
+    recorded.status = COMPLETED.to_string();
+    if let Err(e) = complete_payment(pool, &recorded, "payment_recorded", request_id).await {
+        // The money was taken but could not be recorded, so it is given back
+        tracing::error!("Failed to record charged payment {}: {:?}", id, e);
+        if let Err(declined) = provider
+            .refund(
+                payment.tender,
+                payment.due_cents(),
+                recorded.reference.as_deref(),
+            )
+            .await
+        {
+            tracing::error!("Failed to refund unrecorded payment {}: {}", id, declined.0);
+        }
+        if let Err(e) = set_status(pool, id, FAILED).await {
+            tracing::error!("Payment {} is left pending: {:?}", id, e);
+        }
+        return Err(e.into());
+    }
+    Ok(recorded)
+}
+
+/*
+ * Gives back some or, when `amount_cents` is `None`, all of what is left of a payment,
+ * through the provider that took it. Tips are not refunded. Like payments, refunds through a
+ * provider are pending until it answers. A refund given but not recorded stays pending, so
+ * it keeps counting against the balance and the session cannot be closed until it is looked into.
+ */
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "Refunding a payment", skip(pool, provider, tax, request_id))]
+pub async fn refund_payment(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    id: Uuid,
+    payment_id: Uuid,
+    amount_cents: Option<i64>,
+    now: DateTime<Utc>,
+    timezone: Tz,
+    tax: &TaxSettings,
+    request_id: &RequestId,
+) -> Result<Payment, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let original = sqlx::query_as!(
+        Payment,
+        r#"
+    SELECT id, session_id, tender, amount_cents, tip_cents, tendered_cents, change_cents,
+        provider, reference, refund_of, status, created_at
+    FROM payments
+    WHERE id = $1
+    "#,
+        payment_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| DomainError::NotFound(format!("There is no payment {}.", payment_id)))?;
+    if original.refund_of.is_some() {
+        return Err(DomainError::Invalid(
+            "A refund cannot be refunded.".to_string(),
+        ));
+    }
+    // Refunds of the same payment wait for each other here
+    let account = account(&mut transaction, original.session_id, timezone, tax).await?;
+    if account.closed_at.is_some() {
+        return Err(session_closed(original.session_id));
+    }
+    if original.status != COMPLETED {
+        return Err(payment_not_completed(payment_id, &original.status));
+    }
+
+    let refunded: i64 = account
+        .payments
+        .iter()
+        .filter(|payment| payment.refund_of == Some(payment_id))
+        .map(|payment| -payment.amount_cents)
+        .sum();
+    let refundable = original.amount_cents - refunded;
+    let amount_cents = amount_cents.unwrap_or(refundable);
+    if amount_cents <= 0 {
+        return Err(DomainError::Invalid(
+            "A refund has to be for more than nothing.".to_string(),
+        ));
+    }
+    if amount_cents > refundable {
+        return Err(DomainError::Conflict {
+            code: "refund_exceeds_payment",
+            message: format!(
+                "Only {} cents of payment {} are left to refund.",
+                refundable, payment_id
+            ),
+        });
+    }
+
+    let tender = Tender::parse(&original.tender).map_err(DomainError::Invalid)?;
+    if let Some(name) = original
+        .provider
+        .as_ref()
+        .filter(|name| *name != provider.name())
+    {
+        return Err(DomainError::Conflict {
+            code: "provider_mismatch",
+            message: format!(
+                "Payment {} was taken through {}, refund it there.",
+                payment_id, name
+            ),
+        });
+    }
+    let through_provider = original.provider.is_some();
+    let mut refund = Payment {
+        id,
+        session_id: original.session_id,
+        tender: original.tender,
+        amount_cents: -amount_cents,
+        tip_cents: 0,
+        tendered_cents: 0,
+        change_cents: 0,
+        provider: original.provider,
+        reference: None,
+        refund_of: Some(payment_id),
+        status: if through_provider { PENDING } else { COMPLETED }.to_string(),
+        created_at: now,
+    };
+    insert_payment(&mut transaction, &refund).await?;
+    if !through_provider {
+        record_audit(
+            &mut transaction,
+            request_id,
+            "payment_refunded",
+            serde_json::json!(refund),
+        )
+        .await?;
+        transaction.commit().await?;
+        return Ok(refund);
+    }
+    transaction.commit().await?;
+
+    refund.reference = match provider
+        .refund(tender, amount_cents, original.reference.as_deref())
+        .await
+    {
+        Ok(reference) => reference,
+        Err(declined) => {
+            if let Err(e) = set_status(pool, id, FAILED).await {
+                tracing::error!("Declined refund {} is left pending: {:?}", id, e);
+            }
+            return Err(declined.into());
+        }
+    };
+    refund.status = COMPLETED.to_string();
+    if let Err(e) = complete_payment(pool, &refund, "payment_refunded", request_id).await {
+        tracing::error!(
+            "Refund {} was given but could not be recorded, it is left pending: {:?}",
+            id,
+            e
+        );
+        return Err(e.into());
+    }
+    Ok(refund)
+}
+
+/*
+ * Closes a session once it is settled, freeing the table for the next party.
+ * Anything still owed has to be paid, and anything overpaid refunded, first. The caller commits.
+ */
+#[tracing::instrument(name = "Closing a table session", skip(transaction, tax))]
+pub async fn close_session(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+    now: DateTime<Utc>,
+    timezone: Tz,
+    tax: &TaxSettings,
+) -> Result<Account, DomainError> {
+    let mut account = account(transaction, session_id, timezone, tax).await?;
+    if account.closed_at.is_some() {
+        return Err(session_closed(session_id));
+    }
+    if let Some(payment) = account
+        .payments
+        .iter()
+        .find(|payment| payment.status == PENDING)
+    {
+        return Err(payment_not_completed(payment.id, &payment.status));
+    }
+    if account.balance_cents > 0 {
+        return Err(DomainError::Conflict {
+            code: "balance_outstanding",
+            message: format!("{} cents are still to be paid.", account.balance_cents),
+        });
+    }
+    if account.balance_cents < 0 {
+        return Err(DomainError::Conflict {
+            code: "balance_outstanding",
+            message: format!(
+                "{} cents were overpaid and have to be refunded.",
+                -account.balance_cents
+            ),
+        });
+    }
+
+    sqlx::query!(
+        "UPDATE table_sessions SET closed_at = $2 WHERE id = $1",
+        session_id,
+        now
+    )
+    .execute(&mut **transaction)
+    .await?;
+    account.closed_at = Some(now);
+    Ok(account)
+}
@@ -0,0 +1,166 @@
+use crate::configuration::{PrinterKind, PrintingSettings};
+use crate::domain::Station;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+// The printer could not take a ticket, e.g. it is switched off or out of paper
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrinterUnavailable(pub String);
+
+/*
+ * Where kitchen tickets come out. Tickets arrive as ESC/POS bytes for the station's printer,
+ * a ticket that fails is retried later with the same `job_id`.
+ */
+pub trait PrinterSink: Send + Sync {
+    fn print<'a>(
+        &'a self,
+        station: Station,
+        job_id: Uuid,
+        bytes: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), PrinterUnavailable>>;
+}
+
+// The sink `printing.sink` asks for, `None` when tickets are only queued
+pub fn configured_printer(settings: &PrintingSettings) -> Option<Arc<dyn PrinterSink>> {
+    match settings.sink {
+        PrinterKind::None => None,
+        PrinterKind::Tcp => Some(Arc::new(TcpPrinters {
+            addresses: settings.printers.clone(),
+            timeout: Duration::from_millis(settings.timeout_milliseconds),
+        })),
+        PrinterKind::File => Some(Arc::new(FilePrinter {
+            directory: settings.directory.clone(),
+        })),
+    }
+}
+
+// Network printers taking raw ESC/POS on a TCP port, usually 9100
+pub struct TcpPrinters {
+    // `host:port` of every station's printer
+    pub addresses: HashMap<Station, String>,
+    // For connecting and sending together
+    pub timeout: Duration,
+}
+
+impl PrinterSink for TcpPrinters {
+    fn print<'a>(
+        &'a self,
+        station: Station,
+        _job_id: Uuid,
+        bytes: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), PrinterUnavailable>> {
+        Box::pin(async move {
+            let address = self.addresses.get(&station).ok_or_else(|| {
+                PrinterUnavailable(format!("There is no printer for the {}.", station.as_str()))
+            })?;
+            let send = async {
+                let mut stream = TcpStream::connect(address).await?;
+                stream.write_all(bytes).await?;
+                stream.shutdown().await
+            };
+            match tokio::time::timeout(self.timeout, send).await {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => Err(PrinterUnavailable(format!("{}: {}", address, e))),
+                Err(_) => Err(PrinterUnavailable(format!("{} timed out.", address))),
+            }
+        })
+    }
+}
+
+/*
+ * Writes every ticket to `{directory}/{station}/{job_id}.escpos`, for printers shared as
+ * devices or spool directories, and for trying the kitchen out without any printers.
+ */
+pub struct FilePrinter {
+    pub directory: PathBuf,
+}
+
+impl PrinterSink for FilePrinter {
+    fn print<'a>(
+        &'a self,
+        station: Station,
+        job_id: Uuid,
+        bytes: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), PrinterUnavailable>> {
+        Box::pin(async move {
+            let directory = self.directory.join(station.as_str());
+            let write = async {
+                tokio::fs::create_dir_all(&directory).await?;
+                tokio::fs::write(directory.join(format!("{}.escpos", job_id)), bytes).await
+            };
+            write
+                .await
+                .map_err(|e| PrinterUnavailable(format!("{}: {}", directory.display(), e)))
+        })
+    }
+}
+
+// A ticket the fake printer was sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintedTicket {
+    pub station: Station,
+    pub job_id: Uuid,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    jammed: Option<String>,
+    printed: Vec<PrintedTicket>,
+}
+
+// Keeps every ticket in memory until told it is jammed
+#[derive(Default)]
+pub struct FakePrinter(Mutex<FakeState>);
+
+impl FakePrinter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Every ticket from now on fails with `reason`
+    pub fn jam(&self, reason: &str) {
+        self.state().jammed = Some(reason.to_string());
+    }
+
+    pub fn fix(&self) {
+        self.state().jammed = None;
+    }
+
+    pub fn printed(&self) -> Vec<PrintedTicket> {
+        self.state().printed.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl PrinterSink for FakePrinter {
+    fn print<'a>(
+        &'a self,
+        station: Station,
+        job_id: Uuid,
+        bytes: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), PrinterUnavailable>> {
+        let mut state = self.state();
+        let result = match &state.jammed {
+            Some(reason) => Err(PrinterUnavailable(reason.clone())),
+            None => {
+                state.printed.push(PrintedTicket {
+                    station,
+                    job_id,
+                    bytes: bytes.to_vec(),
+                });
+                Ok(())
+            }
+        };
+        Box::pin(async { result })
+    }
+}
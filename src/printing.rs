@@ -0,0 +1,320 @@
+use crate::clock::Clock;
+use crate::configuration::PrintingSettings;
+use crate::domain::{render_ticket, Course, Station, Ticket, TicketLine};
+use crate::error::DomainError;
+use crate::id_generator::IdGenerator;
+use crate::printer::PrinterSink;
+use crate::shutdown::Shutdown;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+// How many tickets are sent to the printers in one round
+const BATCH_SIZE: usize = 50;
+
+// A kitchen ticket in the print queue
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrintJob {
+    pub id: Uuid,
+    pub station: String,
+    pub table_no: i32,
+    pub ticket: Json<Ticket>,
+    // `pending`, `printing` while it is sent, `printed` or `failed` once it has been given up on
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub printed_at: Option<DateTime<Utc>>,
+    // Until when an instance has claimed a `printing` ticket
+    pub leased_until: Option<DateTime<Utc>>,
+}
+
+pub const PRINT_JOB_STATUSES: [&str; 4] = ["pending", "printing", "printed", "failed"];
+
+// A ticket claimed for printing until `leased_until`
+struct ClaimedJob {
+    id: Uuid,
+    ticket: Json<Ticket>,
+    attempts: i32,
+    leased_until: DateTime<Utc>,
+}
+
+/*
+ * Queues a ticket per station and table for the lines of `order_ids` that have reached the
+ * kitchen. Held lines wait for their course to be fired, and a bundle's own line is only
+ * a price, its components are what gets made. The caller commits.
+ */
+#[tracing::instrument(name = "Queueing kitchen tickets", skip(transaction, ids))]
+pub async fn queue_tickets(
+    transaction: &mut Transaction<'_, Postgres>,
+    ids: &dyn IdGenerator,
+    order_ids: &[Uuid],
+    fired_course: Option<Course>,
+    now: DateTime<Utc>,
+) -> Result<Vec<PrintJob>, sqlx::Error> {
+    let lines = sqlx::query!(
+        r#"
+    SELECT o.table_no, o.item, o.variant, o.quantity, o.modifiers, o.takeaway,
+        COALESCE(o.note, b.note) AS note
+    FROM orders o
+    LEFT JOIN orders b ON b.id = o.parent_id
+    WHERE o.id = ANY($1)
+        AND o.fired_at IS NOT NULL
+        AND NOT EXISTS (SELECT 1 FROM orders c WHERE c.parent_id = o.id)
+    ORDER BY o.placed_at, o.id
+    "#,
+        order_ids
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let mut tickets: BTreeMap<(i32, Station), Vec<TicketLine>> = BTreeMap::new();
+    for line in lines {
+        tickets
+            .entry((line.table_no, Station::of(&line.item)))
+            .or_default()
+            .push(TicketLine {
+                quantity: line.quantity,
+                item: line.item,
+                variant: line.variant,
+                modifiers: line.modifiers,
+                note: line.note,
+                takeaway: line.takeaway,
+            });
+    }
+
+    let mut jobs = vec![];
+    for ((table_no, station), lines) in tickets {
+        let job = PrintJob {
+            id: ids.new_id(),
+            station: station.as_str().to_string(),
+            table_no,
+            ticket: Json(Ticket {
+                station,
+                table_no,
+                fired_course,
+                sent_at: now,
+                lines,
+            }),
+            status: "pending".to_string(),
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now,
+            created_at: now,
+            printed_at: None,
+            leased_until: None,
+        };
+        sqlx::query!(
+            r#"
+    INSERT INTO print_jobs (id, station, table_no, ticket, next_attempt_at, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6)
+    "#,
+            job.id,
+            job.station,
+            job.table_no,
+            &job.ticket as &Json<Ticket>,
+            job.next_attempt_at,
+            job.created_at
+        )
+        .execute(&mut **transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to execute query: {:?}", e);
+            e
+        })?;
+        jobs.push(job);
+    }
+    Ok(jobs)
+}
+
+#[tracing::instrument(name = "Retrieving print jobs from the database", skip(pool))]
+pub async fn print_jobs(pool: &PgPool, status: Option<&str>) -> Result<Vec<PrintJob>, sqlx::Error> {
+    sqlx::query_as!(
+        PrintJob,
+        r#"
+    SELECT id, station, table_no, ticket AS "ticket: Json<Ticket>", status, attempts,
+        last_error, next_attempt_at, created_at, printed_at, leased_until
+    FROM print_jobs
+    WHERE $1::text IS NULL OR status = $1
+    ORDER BY created_at, id
+    "#,
+        status
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/*
+ * Claims the ticket due next as `printing` until `now + lease`. The claim commits straight
+ * away, so nothing stays locked while the printer is busy and instances sharing the database
+ * skip the tickets another one is sending. A ticket whose claim ran out is due again.
+ */
+async fn claim_next_job(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+    lease: chrono::Duration,
+) -> Result<Option<ClaimedJob>, sqlx::Error> {
+    sqlx::query_as!(
+        ClaimedJob,
+        r#"
+    UPDATE print_jobs SET status = 'printing', leased_until = $2
+    WHERE id = (
+        SELECT id
+        FROM print_jobs
+        WHERE (status = 'pending' AND next_attempt_at <= $1)
+            OR (status = 'printing' AND leased_until <= $1)
+        ORDER BY created_at, id
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+    )
+    RETURNING id, ticket AS "ticket: Json<Ticket>", attempts, leased_until AS "leased_until!"
+    "#,
+        now,
+        now + lease
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/*
+ * Sends the tickets that are due to their printers, one claim at a time. A ticket that fails
+ * waits longer after every attempt and is marked `failed` after `max_attempts`. A result is
+ * only recorded while the claim still holds, so a ticket another instance took over after
+ * the claim ran out is left to it. Returns how many were printed.
+ */
+#[tracing::instrument(name = "Printing due tickets", skip(pool, printer, settings))]
+pub async fn print_due_tickets(
+    pool: &PgPool,
+    printer: &dyn PrinterSink,
+    timezone: Tz,
+    settings: &PrintingSettings,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let mut printed = 0;
+    for _ in 0..BATCH_SIZE {
+        let job = match claim_next_job(pool, now, settings.lease()).await? {
+            Some(job) => job,
+            None => break,
+        };
+        let bytes = render_ticket(&job.ticket, timezone, settings.width);
+        let attempts = job.attempts + 1;
+        match printer.print(job.ticket.station, job.id, &bytes).await {
+            Ok(()) => {
+                sqlx::query!(
+                    r#"
+    UPDATE print_jobs
+    SET status = 'printed', attempts = $2, last_error = NULL, printed_at = $3,
+        leased_until = NULL
+    WHERE id = $1 AND status = 'printing' AND leased_until = $4
+    "#,
+                    job.id,
+                    attempts,
+                    now,
+                    job.leased_until
+                )
+                .execute(pool)
+                .await?;
+                printed += 1;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    job_id = %job.id,
+                    station = job.ticket.station.as_str(),
+                    attempts,
+                    "Failed to print a ticket: {}",
+                    e.0
+                );
+                let status = match attempts >= settings.max_attempts {
+                    true => "failed",
+                    false => "pending",
+                };
+                sqlx::query!(
+                    r#"
+    UPDATE print_jobs
+    SET status = $2, attempts = $3, last_error = $4, next_attempt_at = $5, leased_until = NULL
+    WHERE id = $1 AND status = 'printing' AND leased_until = $6
+    "#,
+                    job.id,
+                    status,
+                    attempts,
+                    e.0,
+                    now + settings.retry_delay(attempts),
+                    job.leased_until
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+    Ok(printed)
+}
+
+// Puts a ticket that was given up on back in the queue, e.g. once the printer has paper again
+#[tracing::instrument(name = "Retrying a print job", skip(pool))]
+pub async fn retry_print_job(
+    pool: &PgPool,
+    id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<PrintJob, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let status = sqlx::query_scalar!("SELECT status FROM print_jobs WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or_else(|| DomainError::NotFound(format!("There is no print job {}.", id)))?;
+    if status != "failed" {
+        return Err(DomainError::Conflict {
+            code: "print_job_not_failed",
+            message: format!(
+                "Print job {} is {}, only failed jobs can be retried.",
+                id, status
+            ),
+        });
+    }
+    let job = sqlx::query_as!(
+        PrintJob,
+        r#"
+    UPDATE print_jobs SET status = 'pending', attempts = 0, last_error = NULL, next_attempt_at = $2
+    WHERE id = $1
+    RETURNING id, station, table_no, ticket AS "ticket: Json<Ticket>", status, attempts,
+        last_error, next_attempt_at, created_at, printed_at, leased_until
+    "#,
+        id,
+        now
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(job)
+}
+
+// Sends due tickets every `poll_interval_milliseconds` until the application shuts down
+pub async fn run_print_queue(
+    pool: PgPool,
+    printer: Arc<dyn PrinterSink>,
+    clock: Arc<dyn Clock>,
+    timezone: Tz,
+    settings: PrintingSettings,
+    shutdown: Shutdown,
+) {
+    let interval = Duration::from_millis(settings.poll_interval_milliseconds);
+    loop {
+        tokio::select! {
+            _ = shutdown.triggered() => return,
+            _ = actix_web::rt::time::sleep(interval) => {},
+        }
+
+        // The queue keeps the tickets, so they are sent on the next round instead
+        if let Err(e) =
+            print_due_tickets(&pool, printer.as_ref(), timezone, &settings, clock.now()).await
+        {
+            tracing::error!("Failed to print the queued tickets: {:?}", e);
+        }
+    }
+}
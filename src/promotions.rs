@@ -0,0 +1,430 @@
+use crate::configuration::PromotionSettings;
+use crate::domain::{Adjustment, DiscountKind, Off, Promotion, Reward, Schedule, Target};
+use crate::error::{DomainError, UNIQUE_VIOLATION};
+use chrono::{DateTime, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use std::borrow::Cow;
+use uuid::Uuid;
+
+// A promotional code, e.g. `TENOFF`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Voucher {
+    pub code: String,
+    pub percent_off: Option<i64>,
+    pub amount_off_cents: Option<i64>,
+    // How many parties can use the code, any number when `None`
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+// A row of the `promotions` table
+struct PromotionRow {
+    name: String,
+    target_item: Option<String>,
+    target_category: Option<String>,
+    min_quantity: i32,
+    buy: Option<i32>,
+    free: Option<i32>,
+    percent_off: Option<i64>,
+    days: Option<Vec<String>>,
+    from_time: Option<NaiveTime>,
+    until_time: Option<NaiveTime>,
+}
+
+impl TryFrom<PromotionRow> for Promotion {
+    type Error = sqlx::Error;
+
+    fn try_from(row: PromotionRow) -> Result<Self, Self::Error> {
+        let decode = |e: String| sqlx::Error::Decode(e.into());
+        let target = match (row.target_item, row.target_category) {
+            (Some(item), _) => Target::Item(item),
+            (None, Some(category)) => Target::Category(category),
+            (None, None) => return Err(decode(format!("Promotion {} has no target.", row.name))),
+        };
+        let reward = match (row.buy, row.free, row.percent_off) {
+            (Some(buy), Some(free), _) => Reward::BuyGetFree { buy, free },
+            (_, _, Some(percent)) => Reward::PercentOff(percent),
+            _ => return Err(decode(format!("Promotion {} has no reward.", row.name))),
+        };
+        let schedule = match (row.days, row.from_time, row.until_time) {
+            (Some(days), Some(from), Some(until)) => Some(Schedule {
+                days: Cow::Owned(
+                    days.iter()
+                        .map(|day| day.parse::<Weekday>().map_err(|e| decode(e.to_string())))
+                        .collect::<Result<_, _>>()?,
+                ),
+                from,
+                until,
+            }),
+            _ => None,
+        };
+        Ok(Promotion {
+            name: row.name,
+            target,
+            min_quantity: row.min_quantity,
+            reward,
+            schedule,
+        })
+    }
+}
+
+// Every promotion, in the order they were set up, which is the order bills apply them in
+pub async fn promotions<'e>(executor: impl PgExecutor<'e>) -> Result<Vec<Promotion>, sqlx::Error> {
+    sqlx::query_as!(
+        PromotionRow,
+        r#"
+    SELECT name, target_item, target_category, min_quantity, buy, free, percent_off,
+        days, from_time, until_time
+    FROM promotions
+    ORDER BY created_at, name
+    "#
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(Promotion::try_from)
+    .collect()
+}
+
+// Creates a promotion or changes one, which keeps its place among the others
+#[tracing::instrument(name = "Saving a promotion", skip(pool))]
+pub async fn save_promotion(
+    pool: &PgPool,
+    promotion: &Promotion,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let (target_item, target_category) = match &promotion.target {
+        Target::Item(item) => (Some(item.as_str()), None),
+        Target::Category(category) => (None, Some(category.as_str())),
+    };
+    let (buy, free, percent_off) = match promotion.reward {
+        Reward::BuyGetFree { buy, free } => (Some(buy), Some(free), None),
+        Reward::PercentOff(percent) => (None, None, Some(percent)),
+    };
+    let schedule = promotion.schedule.as_ref();
+    let days: Option<Vec<String>> =
+        schedule.map(|schedule| schedule.days.iter().map(|day| day.to_string()).collect());
+    sqlx::query!(
+        r#"
+    INSERT INTO promotions (
+        name, target_item, target_category, min_quantity, buy, free, percent_off,
+        days, from_time, until_time, created_at
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+    ON CONFLICT (name) DO UPDATE SET
+        target_item = EXCLUDED.target_item,
+        target_category = EXCLUDED.target_category,
+        min_quantity = EXCLUDED.min_quantity,
+        buy = EXCLUDED.buy,
+        free = EXCLUDED.free,
+        percent_off = EXCLUDED.percent_off,
+        days = EXCLUDED.days,
+        from_time = EXCLUDED.from_time,
+        until_time = EXCLUDED.until_time
+    "#,
+        promotion.name,
+        target_item,
+        target_category,
+        promotion.min_quantity,
+        buy,
+        free,
+        percent_off,
+        days.as_deref(),
+        schedule.map(|schedule| schedule.from),
+        schedule.map(|schedule| schedule.until),
+        now
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// Whether there was such a promotion to remove
+#[tracing::instrument(name = "Removing a promotion", skip(pool))]
+pub async fn remove_promotion(pool: &PgPool, name: &str) -> Result<bool, sqlx::Error> {
+    let removed = sqlx::query!("DELETE FROM promotions WHERE name = $1", name)
+        .execute(pool)
+        .await?;
+    Ok(removed.rows_affected() > 0)
+}
+
+// A voucher or manual discount given to a party
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Discount {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub kind: String,
+    pub name: String,
+    pub voucher_code: Option<String>,
+    pub percent_off: Option<i64>,
+    pub amount_off_cents: Option<i64>,
+    pub manager_approved: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Discount {
+    pub fn adjustment(&self) -> Adjustment {
+        Adjustment {
+            kind: match self.voucher_code {
+                Some(_) => DiscountKind::Voucher,
+                None => DiscountKind::Manual,
+            },
+            name: self.name.clone(),
+            off: match self.percent_off {
+                Some(percent) => Off::Percent(percent),
+                None => Off::Cents(self.amount_off_cents.unwrap_or(0)),
+            },
+        }
+    }
+}
+
+// Codes are matched whatever case they are typed in
+pub fn normalise_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+// Creates a voucher or changes one, keeping count of how often it was used
+#[tracing::instrument(name = "Saving a voucher", skip(pool))]
+pub async fn save_voucher(
+    pool: &PgPool,
+    code: &str,
+    off: Off,
+    max_uses: Option<i32>,
+    valid_until: Option<DateTime<Utc>>,
+) -> Result<Voucher, sqlx::Error> {
+    sqlx::query_as!(
+        Voucher,
+        r#"
+    INSERT INTO vouchers (code, percent_off, amount_off_cents, max_uses, valid_until)
+    VALUES ($1, $2, $3, $4, $5)
+    ON CONFLICT (code) DO UPDATE SET
+        percent_off = EXCLUDED.percent_off,
+        amount_off_cents = EXCLUDED.amount_off_cents,
+        max_uses = EXCLUDED.max_uses,
+        valid_until = EXCLUDED.valid_until
+    RETURNING code, percent_off, amount_off_cents, max_uses, uses, valid_until
+    "#,
+        code,
+        off.percent(),
+        off.cents(),
+        max_uses,
+        valid_until
+    )
+    .fetch_one(pool)
+    .await
+}
+
+#[tracing::instrument(name = "Retrieving vouchers from the database", skip(pool))]
+pub async fn vouchers(pool: &PgPool) -> Result<Vec<Voucher>, sqlx::Error> {
+    sqlx::query_as!(
+        Voucher,
+        r#"
+    SELECT code, percent_off, amount_off_cents, max_uses, uses, valid_until
+    FROM vouchers
+    ORDER BY code
+    "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Every discount given to the parties of `session_ids`, in the order they were given
+pub async fn session_discounts<'e>(
+    executor: impl PgExecutor<'e>,
+    session_ids: &[Uuid],
+) -> Result<Vec<Discount>, sqlx::Error> {
+    sqlx::query_as!(
+        Discount,
+        r#"
+    SELECT id, session_id, kind, name, voucher_code, percent_off, amount_off_cents,
+        manager_approved, created_at
+    FROM discounts
+    WHERE session_id = ANY($1)
+    ORDER BY created_at, id
+    "#,
+        session_ids
+    )
+    .fetch_all(executor)
+    .await
+}
+
+// Discounts can only be given while the party is seated
+pub(crate) async fn lock_open_session(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+) -> Result<i32, DomainError> {
+    let session = sqlx::query!(
+        "SELECT table_no, closed_at FROM table_sessions WHERE id = $1 FOR UPDATE",
+        session_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .ok_or_else(|| DomainError::NotFound(format!("There is no session {}.", session_id)))?;
+    match session.closed_at {
+        None => Ok(session.table_no),
+        Some(_) => Err(DomainError::Conflict {
+            code: "session_closed",
+            message: format!("Session {} is already closed.", session_id),
+        }),
+    }
+}
+
+async fn insert_discount(
+    transaction: &mut Transaction<'_, Postgres>,
+    discount: &Discount,
+) -> Result<(), DomainError> {
+    sqlx::query!(
+        r#"
+    INSERT INTO discounts (
+        id, session_id, kind, name, voucher_code, percent_off, amount_off_cents,
+        manager_approved, created_at
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+    "#,
+        discount.id,
+        discount.session_id,
+        discount.kind,
+        discount.name,
+        discount.voucher_code,
+        discount.percent_off,
+        discount.amount_off_cents,
+        discount.manager_approved,
+        discount.created_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some(UNIQUE_VIOLATION) => {
+            DomainError::Conflict {
+                code: "voucher_already_applied",
+                message: format!("{} has already been applied for this party.", discount.name),
+            }
+        }
+        _ => {
+            tracing::error!("Failed to execute query: {:?}", e);
+            DomainError::Database(e)
+        }
+    })?;
+    Ok(())
+}
+
+/*
+ * Applies a voucher code to a seated party's bill and counts the use. Vouchers being
+ * redeemed at two tables at once take turns, so a code cannot go over its limit.
+ * The caller commits.
+ */
+#[tracing::instrument(name = "Redeeming a voucher", skip(transaction))]
+pub async fn redeem_voucher(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    session_id: Uuid,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<Discount, DomainError> {
+    lock_open_session(transaction, session_id).await?;
+    let code = normalise_code(code);
+    let voucher = sqlx::query_as!(
+        Voucher,
+        r#"
+    SELECT code, percent_off, amount_off_cents, max_uses, uses, valid_until
+    FROM vouchers
+    WHERE code = $1
+    FOR UPDATE
+    "#,
+        code
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .ok_or_else(|| DomainError::NotFound(format!("There is no voucher {}.", code)))?;
+    if voucher
+        .valid_until
+        .is_some_and(|valid_until| valid_until <= now)
+    {
+        return Err(DomainError::Conflict {
+            code: "voucher_expired",
+            message: format!("Voucher {} has expired.", code),
+        });
+    }
+    if voucher
+        .max_uses
+        .is_some_and(|max_uses| voucher.uses >= max_uses)
+    {
+        return Err(DomainError::Conflict {
+            code: "voucher_used_up",
+            message: format!("Voucher {} has been used as often as it can be.", code),
+        });
+    }
+
+    let discount = Discount {
+        id,
+        session_id,
+        kind: DiscountKind::Voucher.as_str().to_string(),
+        name: voucher.code.clone(),
+        voucher_code: Some(voucher.code.clone()),
+        percent_off: voucher.percent_off,
+        amount_off_cents: voucher.amount_off_cents,
+        manager_approved: false,
+        created_at: now,
+    };
+    insert_discount(transaction, &discount).await?;
+    sqlx::query!(
+        "UPDATE vouchers SET uses = uses + 1 WHERE code = $1",
+        voucher.code
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(discount)
+}
+
+/*
+ * Records a discount staff gave a party. `worth_cents` is what it takes off the bill as it
+ * stands. Anything worth more than the configured amount, or any percentage above the
+ * configured one, needs `manager_approved`. The caller commits.
+ */
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "Giving a manual discount", skip(transaction))]
+pub async fn give_discount(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    session_id: Uuid,
+    reason: &str,
+    off: Off,
+    worth_cents: i64,
+    settings: &PromotionSettings,
+    manager_approved: bool,
+    now: DateTime<Utc>,
+) -> Result<Discount, DomainError> {
+    if !manager_approved {
+        if worth_cents > settings.manual_discount_approval_cents {
+            return Err(DomainError::ApprovalRequired(format!(
+                "Discounts worth more than {} cents need a manager's code, this one is worth {}.",
+                settings.manual_discount_approval_cents, worth_cents
+            )));
+        }
+        if let Some(percent) = off
+            .percent()
+            .filter(|percent| *percent > settings.manual_discount_approval_percent)
+        {
+            return Err(DomainError::ApprovalRequired(format!(
+                "Discounts of more than {}% need a manager's code, this one is {}%.",
+                settings.manual_discount_approval_percent, percent
+            )));
+        }
+    }
+
+    let discount = Discount {
+        id,
+        session_id,
+        kind: DiscountKind::Manual.as_str().to_string(),
+        name: reason.to_string(),
+        voucher_code: None,
+        percent_off: off.percent(),
+        amount_off_cents: off.cents(),
+        manager_approved,
+        created_at: now,
+    };
+    insert_discount(transaction, &discount).await?;
+    Ok(discount)
+}
@@ -0,0 +1,364 @@
+use crate::configuration::{RateLimit, RateLimitBackend, RateLimitingSettings};
+use crate::metrics::Metrics;
+use crate::runtime_settings::RuntimeSettingsStore;
+use crate::shutdown::Shutdown;
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+// Past this many buckets, those left untouched for a while are dropped
+const MAX_BUCKETS_BEFORE_CLEANUP: usize = 10_000;
+// Long enough for any sensible bucket to have refilled, so dropping it changes nothing
+const IDLE_BUCKET_LIFETIME: Duration = Duration::from_secs(600);
+
+// Whether a request may go ahead, and if not how long until it could
+#[derive(Debug, PartialEq)]
+pub enum Decision {
+    Allowed,
+    Limited { retry_after: Duration },
+}
+
+/*
+ * Keeps a token bucket per route group and client.
+ * In memory buckets only limit a single instance, Postgres buckets are shared by every
+ * instance using the same database at the cost of a query per limited request.
+ */
+pub struct RateLimiter {
+    buckets: Buckets,
+    // Hashes of the configured API keys, so the keys themselves are not kept around
+    api_keys: HashSet<String>,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+pub enum Buckets {
+    Memory(Mutex<HashMap<(String, String), Bucket>>),
+    Postgres(PgPool),
+}
+
+pub struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        self.updated_at = now;
+    }
+}
+
+impl RateLimiter {
+    pub fn new(settings: &RateLimitingSettings, pool: PgPool) -> Self {
+        let buckets = match settings.backend {
+            RateLimitBackend::Memory => Buckets::Memory(Mutex::new(HashMap::new())),
+            RateLimitBackend::Postgres => Buckets::Postgres(pool),
+        };
+        Self {
+            buckets,
+            api_keys: settings
+                .api_keys
+                .iter()
+                .map(|key| hash(key.expose_secret()))
+                .collect(),
+            trusted_proxies: settings.trusted_proxies.clone(),
+        }
+    }
+
+    // Take a token from the client's bucket for this group
+    pub async fn acquire(&self, limit: &RateLimit, client: &str) -> Result<Decision, sqlx::Error> {
+        match &self.buckets {
+            Buckets::Memory(buckets) => Ok(acquire_in_memory(buckets, limit, client)),
+            Buckets::Postgres(pool) => acquire_in_postgres(pool, limit, client).await,
+        }
+    }
+
+    /*
+     * Identifies the client a bucket belongs to: a configured API key when one is sent,
+     * otherwise the IP address. Keys are hashed so they are never stored or logged.
+     * Made up keys fall back to the address, so they cannot be used to get fresh buckets.
+     */
+    fn client_key(&self, req: &ServiceRequest) -> String {
+        let api_key = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(hash)
+            .filter(|key| self.api_keys.contains(key));
+        match api_key {
+            Some(api_key) => format!("api_key:{}", api_key),
+            None => match self.client_address(req) {
+                Some(address) => format!("ip:{}", address),
+                None => "ip:unknown".to_string(),
+            },
+        }
+    }
+
+    /*
+     * The address of whoever connected, unless that is a trusted proxy.
+     * Proxies append the address they were connected from to X-Forwarded-For, so the
+     * client is the last address in it not added by one of our own proxies.
+     * Anything further left was sent by the client and could be made up.
+     */
+    fn client_address(&self, req: &ServiceRequest) -> Option<IpAddr> {
+        let peer = req.peer_addr()?.ip();
+        if !self.trusted_proxies.contains(&peer) {
+            return Some(peer);
+        }
+        let forwarded: Vec<IpAddr> = req
+            .headers()
+            .get_all(FORWARDED_FOR_HEADER)
+            .filter_map(|h| h.to_str().ok())
+            .flat_map(|h| h.split(','))
+            .map(|address| address.trim().parse().ok())
+            .collect::<Option<_>>()
+            // A malformed header cannot be told apart from a spoofed one
+            .unwrap_or_default();
+        Some(
+            forwarded
+                .into_iter()
+                .rev()
+                .find(|address| !self.trusted_proxies.contains(address))
+                .unwrap_or(peer),
+        )
+    }
+}
+
+fn hash(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.as_bytes()))
+}
+
+fn acquire_in_memory(
+    buckets: &Mutex<HashMap<(String, String), Bucket>>,
+    limit: &RateLimit,
+    client: &str,
+) -> Decision {
+    let now = Instant::now();
+    let mut buckets = buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+    if buckets.len() >= MAX_BUCKETS_BEFORE_CLEANUP {
+        buckets.retain(|_, bucket| now.duration_since(bucket.updated_at) < IDLE_BUCKET_LIFETIME);
+    }
+
+    let bucket = buckets
+        .entry((limit.group.clone(), client.to_string()))
+        .or_insert(Bucket {
+            tokens: limit.burst as f64,
+            updated_at: now,
+        });
+    bucket.refill(limit, now);
+    take_token(&mut bucket.tokens, limit)
+}
+
+fn take_token(tokens: &mut f64, limit: &RateLimit) -> Decision {
+    if *tokens >= 1.0 {
+        *tokens -= 1.0;
+        Decision::Allowed
+    } else {
+        Decision::Limited {
+            retry_after: Duration::from_secs_f64((1.0 - *tokens) / limit.per_second),
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Taking a rate limit token from the database",
+    skip(pool, client)
+)]
+async fn acquire_in_postgres(
+    pool: &PgPool,
+    limit: &RateLimit,
+    client: &str,
+) -> Result<Decision, sqlx::Error> {
+    // Refill and take a token in a single statement so concurrent instances cannot race
+    let bucket = sqlx::query!(
+        r#"
+    INSERT INTO rate_limit_buckets (group_name, client_key, tokens, allowed, updated_at)
+    VALUES ($1, $2, $3 - 1, true, now())
+    ON CONFLICT (group_name, client_key) DO UPDATE SET
+        allowed = LEAST(
+            $3,
+            rate_limit_buckets.tokens
+                + EXTRACT(EPOCH FROM now() - rate_limit_buckets.updated_at)::DOUBLE PRECISION * $4
+        ) >= 1,
+        tokens = LEAST(
+            $3,
+            rate_limit_buckets.tokens
+                + EXTRACT(EPOCH FROM now() - rate_limit_buckets.updated_at)::DOUBLE PRECISION * $4
+        ) - CASE WHEN LEAST(
+            $3,
+            rate_limit_buckets.tokens
+                + EXTRACT(EPOCH FROM now() - rate_limit_buckets.updated_at)::DOUBLE PRECISION * $4
+        ) >= 1 THEN 1 ELSE 0 END,
+        updated_at = now()
+    RETURNING tokens, allowed
+    "#,
+        limit.group,
+        client,
+        limit.burst as f64,
+        limit.per_second,
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    if bucket.allowed {
+        Ok(Decision::Allowed)
+    } else {
+        Ok(Decision::Limited {
+            retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_second),
+        })
+    }
+}
+
+/*
+ * Drops Postgres buckets left untouched for a while, the in memory ones are cleaned up as
+ * they are used. Returns how many were dropped.
+ */
+pub async fn expire_idle_buckets(pool: &PgPool, idle: Duration) -> Result<u64, sqlx::Error> {
+    let expired = sqlx::query!(
+        r#"
+    DELETE FROM rate_limit_buckets
+    WHERE updated_at < now() - make_interval(secs => $1)
+    "#,
+        idle.as_secs_f64()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    Ok(expired.rows_affected())
+}
+
+// Expire idle Postgres buckets every so often, until shutdown
+pub async fn run_bucket_expiry(pool: PgPool, shutdown: Shutdown) {
+    loop {
+        tokio::select! {
+            _ = shutdown.triggered() => return,
+            _ = actix_web::rt::time::sleep(IDLE_BUCKET_LIFETIME) => {},
+        }
+        if let Ok(expired) = expire_idle_buckets(&pool, IDLE_BUCKET_LIFETIME).await {
+            tracing::debug!("Expired {} idle rate limit buckets", expired);
+        }
+    }
+}
+
+// Middleware answering 429 once a client has used up the tokens of a route group
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let limit = req.match_pattern().and_then(|route| {
+        req.app_data::<web::Data<RuntimeSettingsStore>>()
+            .and_then(|store| store.get().rate_limit_for(&route).cloned())
+    });
+    let limiter = req.app_data::<web::Data<RateLimiter>>().cloned();
+
+    let (limit, limiter) = match (limit, limiter) {
+        (Some(limit), Some(limiter)) => (limit, limiter),
+        _ => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+
+    match limiter.acquire(&limit, &limiter.client_key(&req)).await {
+        Ok(Decision::Allowed) => Ok(next.call(req).await?.map_into_boxed_body()),
+        Ok(Decision::Limited { retry_after }) => {
+            if let Some(metrics) = req.app_data::<web::Data<Metrics>>() {
+                metrics
+                    .rate_limited_requests
+                    .with_label_values(&[&limit.group])
+                    .inc();
+            }
+            // Retry-After only takes whole seconds, never tell a client to retry immediately
+            let retry_after_seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, retry_after_seconds))
+                .json(serde_json::json!({
+                    "error": format!("Too many requests to {}, please slow down.", limit.group),
+                    "retry_after_seconds": retry_after_seconds,
+                }));
+            Ok(req.into_response(response))
+        }
+        // A broken limiter should not take the whole service down with it
+        Err(_) => Ok(next.call(req).await?.map_into_boxed_body()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::RateLimit;
+    use crate::rate_limit::{acquire_in_memory, Bucket, Decision};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    fn limit(burst: u32, per_second: f64) -> RateLimit {
+        RateLimit {
+            group: "orders".into(),
+            routes: vec!["/order".into()],
+            burst,
+            per_second,
+        }
+    }
+
+    #[test]
+    fn a_full_bucket_allows_a_burst_then_limits() {
+        let buckets = Mutex::new(HashMap::new());
+        let limit = limit(3, 1.0);
+
+        for _ in 0..3 {
+            assert_eq!(
+                acquire_in_memory(&buckets, &limit, "ip:1"),
+                Decision::Allowed
+            );
+        }
+        match acquire_in_memory(&buckets, &limit, "ip:1") {
+            Decision::Limited { retry_after } => assert!(retry_after <= Duration::from_secs(1)),
+            Decision::Allowed => panic!("The fourth request was allowed."),
+        }
+    }
+
+    #[test]
+    fn clients_have_their_own_buckets() {
+        let buckets = Mutex::new(HashMap::new());
+        let limit = limit(1, 1.0);
+
+        assert_eq!(
+            acquire_in_memory(&buckets, &limit, "ip:1"),
+            Decision::Allowed
+        );
+        assert_eq!(
+            acquire_in_memory(&buckets, &limit, "ip:2"),
+            Decision::Allowed
+        );
+    }
+
+    #[test]
+    fn tokens_come_back_over_time_up_to_the_burst() {
+        let limit = limit(2, 10.0);
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated_at: Instant::now() - Duration::from_secs(60),
+        };
+
+        bucket.refill(&limit, Instant::now());
+
+        assert_eq!(bucket.tokens, 2.0);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::bills::session_bill;
+use crate::configuration::TaxSettings;
+use crate::domain::{Receipt, ReceiptPayment};
+use crate::error::DomainError;
+use crate::payments::session_payments;
+use crate::sessions::TableSession;
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/*
+ * The receipt of a party that has paid and left the table. Without `session_id` it is the
+ * receipt of the last party to leave. Parties that joined another table's party have none,
+ * their orders are on the other party's receipt.
+ */
+#[tracing::instrument(name = "Preparing a receipt", skip(pool, tax))]
+pub async fn closed_session_receipt(
+    pool: &PgPool,
+    table_no: i32,
+    session_id: Option<Uuid>,
+    timezone: Tz,
+    tax: &TaxSettings,
+) -> Result<Receipt, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let session = sqlx::query_as!(
+        TableSession,
+        r#"
+    SELECT id, table_no, party_size, reservation_id, opened_at, closed_at, merged_into
+    FROM table_sessions
+    WHERE table_no = $1
+        AND ($2::uuid IS NULL OR id = $2)
+        AND ($2::uuid IS NOT NULL OR (closed_at IS NOT NULL AND merged_into IS NULL))
+    ORDER BY closed_at DESC
+    LIMIT 1
+    "#,
+        table_no,
+        session_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| {
+        DomainError::NotFound(match session_id {
+            Some(session_id) => {
+                format!("There is no session {} at table {}.", session_id, table_no)
+            }
+            None => format!("No party has left table {} yet.", table_no),
+        })
+    })?;
+    let closed_at = match (session.closed_at, session.merged_into) {
+        (_, Some(merged_into)) => {
+            return Err(DomainError::Conflict {
+                code: "session_merged",
+                message: format!(
+                    "Session {} joined session {}, its orders are on that receipt.",
+                    session.id, merged_into
+                ),
+            })
+        }
+        (None, None) => {
+            return Err(DomainError::Conflict {
+                code: "session_open",
+                message: format!("Session {} has not been closed yet.", session.id),
+            })
+        }
+        (Some(closed_at), None) => closed_at,
+    };
+
+    let bill = session_bill(&mut transaction, session.id, table_no, timezone, tax).await?;
+    let payments = session_payments(&mut transaction, session.id)
+        .await?
+        .into_iter()
+        .map(|payment| ReceiptPayment {
+            refund: payment.refund_of.is_some(),
+            tender: payment.tender,
+            amount_cents: payment.amount_cents,
+            tip_cents: payment.tip_cents,
+            change_cents: payment.change_cents,
+        })
+        .collect();
+    transaction.commit().await?;
+
+    Ok(Receipt {
+        table_no,
+        opened_at: session.opened_at.with_timezone(&timezone).naive_local(),
+        closed_at: closed_at.with_timezone(&timezone).naive_local(),
+        bill,
+        payments,
+    })
+}
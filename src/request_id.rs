@@ -0,0 +1,259 @@
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use actix_web::middleware::Next;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TraceContextExt;
+use std::future::{ready, Ready};
+use tracing::Span;
+use tracing_actix_web::{DefaultRootSpanBuilder, RootSpanBuilder};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Ids supplied by clients end up in logs, so anything longer is replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/*
+ * Identifies a single request across logs, error responses and audit records.
+ * Clients may supply their own id through `X-Request-Id` so that a complaint can be
+ * tied back to our logs, otherwise one is generated.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    // Returns an instance of `RequestId` if the input is safe to echo back and log
+    pub fn parse(s: &str) -> Result<RequestId, String> {
+        let is_empty = s.is_empty();
+        let is_too_long = s.len() > MAX_REQUEST_ID_LENGTH;
+        let contains_forbidden_characters = !s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ['-', '_', '.', ':'].contains(&c));
+
+        if is_empty || is_too_long || contains_forbidden_characters {
+            Err(format!("{} is not a valid request id.", s))
+        } else {
+            Ok(Self(s.to_string()))
+        }
+    }
+
+    // Use the id supplied by the client when it is valid, otherwise generate one
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| RequestId::parse(s).ok())
+            .unwrap_or_else(RequestId::generate)
+    }
+}
+
+impl AsRef<str> for RequestId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Handlers can take a `RequestId` argument to pass the id on, e.g. into audit records
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(|| RequestId::from_headers(req.headers()));
+        ready(Ok(request_id))
+    }
+}
+
+/*
+ * `TracingLogger` always generates its own request id for the root span.
+ * We build the root span ourselves so that `request_id` holds our id from the very first log line.
+ */
+pub struct RequestIdRootSpanBuilder;
+
+impl RootSpanBuilder for RequestIdRootSpanBuilder {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let request_id = RequestId::from_headers(request.headers());
+        request.extensions_mut().insert(request_id.clone());
+
+        let http_route = request
+            .match_pattern()
+            .unwrap_or_else(|| "default".to_string());
+        let user_agent = request
+            .headers()
+            .get("User-Agent")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("");
+        let connection_info = request.connection_info();
+
+        let span = tracing::info_span!(
+            "HTTP request",
+            http.method = %request.method(),
+            http.route = %http_route,
+            http.host = %connection_info.host(),
+            http.client_ip = %connection_info.realip_remote_addr().unwrap_or(""),
+            http.user_agent = %user_agent,
+            http.target = %request.uri().path_and_query().map(|p| p.as_str()).unwrap_or(""),
+            http.status_code = tracing::field::Empty,
+            otel.name = %format!("HTTP {} {}", request.method(), http_route),
+            otel.kind = "server",
+            otel.status_code = tracing::field::Empty,
+            trace_id = tracing::field::Empty,
+            request_id = %request_id,
+            exception.message = tracing::field::Empty,
+            exception.details = tracing::field::Empty,
+        );
+
+        // Join the caller's trace when the request carries a `traceparent` header
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent);
+        let trace_id = span.context().span().span_context().trace_id();
+        span.record("trace_id", tracing::field::display(trace_id));
+
+        span
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/*
+ * Middleware echoing the request id in the response headers.
+ * Error responses are also given a JSON body carrying the request id, so that
+ * a client reporting a failure can quote it.
+ */
+pub async fn echo_request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .cloned()
+        .unwrap_or_else(|| RequestId::from_headers(req.headers()));
+
+    let res = next.call(req).await?;
+    let status = res.status();
+
+    let mut res = if status.is_client_error() || status.is_server_error() {
+        let (req, res) = res.into_parts();
+        let (res, body) = res.into_parts();
+        // An unreadable body is replaced rather than failing the whole response
+        let body = to_bytes(body).await.unwrap_or_default();
+        let error_body = error_body(&body, status.canonical_reason(), &request_id);
+        let mut res = res.set_body(error_body.to_string());
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        ServiceResponse::new(req, res).map_into_boxed_body()
+    } else {
+        res.map_into_boxed_body()
+    };
+
+    if let Ok(value) = HeaderValue::from_str(request_id.as_ref()) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(res)
+}
+
+// Adds the request id to a JSON error body, or wraps any other body into one
+fn error_body(
+    body: &[u8],
+    canonical_reason: Option<&str>,
+    request_id: &RequestId,
+) -> serde_json::Value {
+    let mut error = match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(object)) => object,
+        _ => {
+            let message = if body.is_empty() {
+                canonical_reason.unwrap_or("Error").to_string()
+            } else {
+                String::from_utf8_lossy(body).into_owned()
+            };
+            let mut object = serde_json::Map::new();
+            object.insert("error".into(), message.into());
+            object
+        }
+    };
+    error.insert("request_id".into(), request_id.as_ref().into());
+    serde_json::Value::Object(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request_id::{error_body, RequestId};
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    fn a_uuid_is_a_valid_request_id() {
+        assert_ok!(RequestId::parse("39ccee83-841e-43e8-ba13-0b6ae794c454"));
+    }
+
+    #[test]
+    fn an_empty_request_id_is_rejected() {
+        assert_err!(RequestId::parse(""));
+    }
+
+    #[test]
+    fn a_request_id_that_is_too_long_is_rejected() {
+        assert_err!(RequestId::parse(&"a".repeat(129)));
+    }
+
+    #[test]
+    fn request_ids_containing_an_invalid_character_are_rejected() {
+        for request_id in &[" ", "\n", "\"", "{", "<", "/"] {
+            assert_err!(RequestId::parse(&format!("abc{}", request_id)));
+        }
+    }
+
+    #[test]
+    fn an_empty_error_body_is_replaced_with_the_reason() {
+        let request_id = RequestId::parse("abc").unwrap();
+        let body = error_body(b"", Some("Bad Request"), &request_id);
+        assert_eq!(
+            body,
+            serde_json::json!({"error": "Bad Request", "request_id": "abc"})
+        );
+    }
+
+    #[test]
+    fn a_json_error_body_keeps_its_fields() {
+        let request_id = RequestId::parse("abc").unwrap();
+        let body = error_body(br#"{"code": "sold_out"}"#, Some("Conflict"), &request_id);
+        assert_eq!(
+            body,
+            serde_json::json!({"code": "sold_out", "request_id": "abc"})
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use crate::domain::{table, tables_for, NewReservation};
+use crate::error::DomainError;
+use crate::sessions::{open_session, TableSession};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// Reservation statuses as stored in the database
+pub const BOOKED: &str = "booked";
+pub const SEATED: &str = "seated";
+pub const CANCELLED: &str = "cancelled";
+
+// Any fixed key works, as long as nothing else takes an advisory lock with it
+const BOOKINGS_LOCK: i64 = 0x7461_626c_6573;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub id: Uuid,
+    pub table_no: i32,
+    pub party_size: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub customer_name: String,
+    pub phone: String,
+    pub status: String,
+}
+
+/*
+ * Bookings and seatings check the other bookings before writing, so they take turns.
+ * The lock is held until the transaction ends.
+ */
+pub(crate) async fn lock_bookings(
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(BOOKINGS_LOCK)
+        .execute(&mut **transaction)
+        .await?;
+    Ok(())
+}
+
+// Tables with a booking overlapping `from` until `until`, bookings that end at `from` do not
+pub(crate) async fn booked_tables(
+    transaction: &mut Transaction<'_, Postgres>,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<i32>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+    SELECT DISTINCT table_no FROM reservations
+    WHERE status = $1 AND starts_at < $3 AND ends_at > $2
+    "#,
+        BOOKED,
+        from,
+        until
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.table_no).collect())
+}
+
+/*
+ * Books the table asked for, or the smallest free table the party fits at.
+ * Seated bookings no longer hold their table, the open session does.
+ */
+#[tracing::instrument(name = "Booking a table", skip(pool, reservation))]
+pub async fn create_reservation(
+    pool: &PgPool,
+    id: Uuid,
+    reservation: &NewReservation,
+    now: DateTime<Utc>,
+) -> Result<Reservation, DomainError> {
+    let mut transaction = pool.begin().await?;
+    lock_bookings(&mut transaction).await?;
+    let booked =
+        booked_tables(&mut transaction, reservation.starts_at, reservation.ends_at).await?;
+
+    let table_no = match reservation.table_no {
+        Some(table_no) => {
+            let table = table(table_no)
+                .ok_or_else(|| DomainError::Invalid(format!("There is no table {}.", table_no)))?;
+            if table.capacity < reservation.party_size {
+                return Err(DomainError::Conflict {
+                    code: "table_too_small",
+                    message: format!(
+                        "Table {} seats {}, not {}.",
+                        table_no, table.capacity, reservation.party_size
+                    ),
+                });
+            }
+            if booked.contains(&table_no) {
+                return Err(DomainError::Conflict {
+                    code: "table_booked",
+                    message: format!("Table {} is already booked at that time.", table_no),
+                });
+            }
+            table_no
+        }
+        None => tables_for(reservation.party_size)
+            .into_iter()
+            .map(|table| table.table_no)
+            .find(|table_no| !booked.contains(table_no))
+            .ok_or_else(|| DomainError::Conflict {
+                code: "no_table_available",
+                message: format!(
+                    "There is no table for {} free at that time.",
+                    reservation.party_size
+                ),
+            })?,
+    };
+
+    let booking = Reservation {
+        id,
+        table_no,
+        party_size: reservation.party_size,
+        starts_at: reservation.starts_at,
+        ends_at: reservation.ends_at,
+        customer_name: reservation.customer_name.as_ref().to_string(),
+        phone: reservation.phone.as_ref().to_string(),
+        status: BOOKED.to_string(),
+    };
+    sqlx::query!(
+        r#"
+    INSERT INTO reservations (
+        id, table_no, party_size, starts_at, ends_at, customer_name, phone, status, created_at
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+    "#,
+        booking.id,
+        booking.table_no,
+        booking.party_size,
+        booking.starts_at,
+        booking.ends_at,
+        booking.customer_name,
+        booking.phone,
+        booking.status,
+        now
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+
+    transaction.commit().await?;
+    Ok(booking)
+}
+
+// Bookings starting from `from` until `until` that have not been cancelled
+#[tracing::instrument(name = "Retrieving reservations from the database", skip(pool))]
+pub async fn reservations_between(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<Reservation>, sqlx::Error> {
+    sqlx::query_as!(
+        Reservation,
+        r#"
+    SELECT id, table_no, party_size, starts_at, ends_at, customer_name, phone, status
+    FROM reservations
+    WHERE status <> $1 AND starts_at >= $2 AND starts_at < $3
+    ORDER BY starts_at, table_no
+    "#,
+        CANCELLED,
+        from,
+        until
+    )
+    .fetch_all(pool)
+    .await
+}
+
+async fn reservation_for_update(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+) -> Result<Reservation, DomainError> {
+    sqlx::query_as!(
+        Reservation,
+        r#"
+    SELECT id, table_no, party_size, starts_at, ends_at, customer_name, phone, status
+    FROM reservations
+    WHERE id = $1
+    FOR UPDATE
+    "#,
+        id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .ok_or_else(|| DomainError::NotFound(format!("There is no reservation {}.", id)))
+}
+
+fn not_booked(reservation: &Reservation) -> DomainError {
+    DomainError::Conflict {
+        code: "reservation_not_booked",
+        message: format!("The reservation is already {}.", reservation.status),
+    }
+}
+
+#[tracing::instrument(name = "Cancelling a reservation", skip(pool))]
+pub async fn cancel_reservation(pool: &PgPool, id: Uuid) -> Result<Reservation, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let mut reservation = reservation_for_update(&mut transaction, id).await?;
+    if reservation.status != BOOKED {
+        return Err(not_booked(&reservation));
+    }
+
+    sqlx::query!(
+        "UPDATE reservations SET status = $2 WHERE id = $1",
+        id,
+        CANCELLED
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    reservation.status = CANCELLED.to_string();
+    Ok(reservation)
+}
+
+/*
+ * Seats the party of a booking at its table, opening a session for it.
+ * Parties may turn up early or late, the table just has to be free.
+ */
+#[tracing::instrument(name = "Seating a reservation", skip(pool))]
+pub async fn seat_reservation(
+    pool: &PgPool,
+    id: Uuid,
+    session_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<TableSession, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let reservation = reservation_for_update(&mut transaction, id).await?;
+    if reservation.status != BOOKED {
+        return Err(not_booked(&reservation));
+    }
+
+    let session = open_session(
+        &mut transaction,
+        session_id,
+        reservation.table_no,
+        reservation.party_size,
+        Some(reservation.id),
+        now,
+    )
+    .await?;
+    sqlx::query!(
+        "UPDATE reservations SET status = $2 WHERE id = $1",
+        id,
+        SEATED
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    Ok(session)
+}
@@ -0,0 +1,56 @@
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng};
+use std::ops::Range;
+use std::sync::Mutex;
+
+/*
+ * Where random choices come from, e.g. how long an order takes to prepare,
+ * so tests can make the same choices every run.
+ */
+pub trait Rng: Send + Sync {
+    // A number from `range`, which must not be empty
+    fn gen_range(&self, range: Range<i32>) -> i32;
+}
+
+// The thread's own generator, used everywhere outside of tests
+pub struct SystemRng;
+
+impl Rng for SystemRng {
+    fn gen_range(&self, range: Range<i32>) -> i32 {
+        rand::thread_rng().gen_range(range)
+    }
+}
+
+// Makes the same choices, in the same order, for the same seed
+pub struct SeededRng(Mutex<StdRng>);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self(Mutex::new(StdRng::seed_from_u64(seed)))
+    }
+}
+
+impl Rng for SeededRng {
+    fn gen_range(&self, range: Range<i32>) -> i32 {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .gen_range(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::{Rng, SeededRng};
+
+    #[test]
+    fn the_same_seed_makes_the_same_choices() {
+        let first = SeededRng::new(42);
+        let second = SeededRng::new(42);
+        for _ in 0..10 {
+            let choice = first.gen_range(5..15);
+            assert_eq!(choice, second.gen_range(5..15));
+            assert!((5..15).contains(&choice));
+        }
+    }
+}
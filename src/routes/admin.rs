@@ -0,0 +1,94 @@
+use crate::configuration::RuntimeSettings;
+use crate::events::{ChangeSource, Event, EventBus};
+use crate::routes::error::bad_request;
+use crate::runtime_settings::RuntimeSettingsStore;
+use crate::shutdown::Shutdown;
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse};
+use tokio::sync::broadcast::error::RecvError;
+
+// How long clients of an event stream should wait before reconnecting after shutdown
+const RECONNECT_MILLISECONDS: u64 = 5000;
+
+pub async fn get_runtime_settings(store: web::Data<RuntimeSettingsStore>) -> HttpResponse {
+    HttpResponse::Ok().json(&*store.get())
+}
+
+/*
+ * Change some of the runtime settings, fields missing from the body keep their value.
+ * e.g. `{"preparation_time_multiplier": 1.5}` only changes how long orders take.
+ * The changes are merged into the settings in effect under the store's lock.
+ */
+#[tracing::instrument(name = "Changing runtime settings", skip(store, changes))]
+pub async fn update_runtime_settings(
+    store: web::Data<RuntimeSettingsStore>,
+    changes: web::Json<serde_json::Value>,
+) -> HttpResponse {
+    let changes = match changes.into_inner() {
+        serde_json::Value::Object(changes) => changes,
+        _ => return bad_request("Expected a JSON object of settings to change.".into()),
+    };
+
+    let merge = |current: &RuntimeSettings| {
+        let mut settings = match serde_json::to_value(current) {
+            Ok(serde_json::Value::Object(settings)) => settings,
+            _ => return Err("The runtime settings could not be read.".to_string()),
+        };
+        settings.extend(changes);
+        serde_json::from_value::<RuntimeSettings>(settings.into()).map_err(|e| e.to_string())
+    };
+
+    match store.modify(merge, ChangeSource::Admin) {
+        Ok(_) => HttpResponse::Ok().json(&*store.get()),
+        Err(e) => bad_request(e),
+    }
+}
+
+/*
+ * Streams every event as a server-sent event.
+ * When the server shuts down the stream ends with a `shutdown` event and a retry hint,
+ * so clients reconnect to another instance instead of treating it as an error.
+ */
+pub async fn stream_events(
+    events: web::Data<EventBus>,
+    shutdown: web::Data<Shutdown>,
+) -> HttpResponse {
+    let receiver = events.subscribe();
+    let shutdown = shutdown.get_ref().clone();
+
+    let stream = futures::stream::unfold(Some(receiver), move |receiver| {
+        let shutdown = shutdown.clone();
+        async move {
+            let mut receiver = receiver?;
+            loop {
+                tokio::select! {
+                    _ = shutdown.triggered() => {
+                        let message = format!(
+                            "retry: {}\nevent: shutdown\ndata: {{}}\n\n",
+                            RECONNECT_MILLISECONDS
+                        );
+                        return Some((Ok::<_, actix_web::Error>(Bytes::from(message)), None));
+                    }
+                    received = receiver.recv() => match received {
+                        Ok(event) => return Some((Ok(server_sent_event(&event)), Some(receiver))),
+                        // Missed events are skipped, a client needing all of them should refetch state
+                        Err(RecvError::Lagged(missed)) => {
+                            tracing::warn!("An event stream missed {} events", missed);
+                        }
+                        Err(RecvError::Closed) => return None,
+                    },
+                }
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream)
+}
+
+fn server_sent_event(event: &Event) -> Bytes {
+    let data = serde_json::to_string(event).unwrap_or_else(|_| "{}".to_string());
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event.name(), data))
+}
@@ -0,0 +1,65 @@
+use crate::auth::Kitchen;
+use crate::availability::{eighty_six, restore};
+use crate::clock::Clock;
+use crate::domain::MENU_ITEMS;
+use crate::events::{Event, EventBus};
+use crate::routes::error::{bad_request, database_error_response};
+use actix_web::{web, HttpResponse};
+use chrono::Duration;
+use sqlx::PgPool;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct AvailabilityChange {
+    available: bool,
+    // Put the item back by itself after this long, only when taking it off
+    restore_after_minutes: Option<u32>,
+}
+
+/*
+ * 86 an item or put it back on, e.g. `{"available": false, "restore_after_minutes": 30}`.
+ * Meant for the kitchen and managers, so it is served with the other operational endpoints.
+ */
+#[tracing::instrument(name = "Changing item availability", skip(pool, events, clock))]
+pub async fn update_availability(
+    _staff: Kitchen,
+    item: web::Path<String>,
+    change: web::Json<AvailabilityChange>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+    clock: web::Data<dyn Clock>,
+) -> HttpResponse {
+    let item = item.into_inner();
+    if !MENU_ITEMS.contains(&item.as_str()) {
+        return bad_request(format!("{} is not on the menu.", item));
+    }
+
+    let now = clock.now();
+    let restore_at = match (change.available, change.restore_after_minutes) {
+        (_, Some(0)) => return bad_request("restore_after_minutes must be above 0.".into()),
+        (true, Some(_)) => {
+            return bad_request(
+                "restore_after_minutes only applies when taking an item off.".into(),
+            )
+        }
+        (_, minutes) => minutes.map(|minutes| now + Duration::minutes(minutes.into())),
+    };
+
+    let result = if change.available {
+        restore(&pool, &item).await
+    } else {
+        eighty_six(&pool, &item, now, restore_at).await
+    };
+    if let Err(e) = result {
+        return database_error_response(&e);
+    }
+
+    tracing::info!(item = %item, available = change.available, "Item availability changed");
+    let event = Event::AvailabilityChanged {
+        item,
+        available: change.available,
+        restore_at,
+    };
+    let response = HttpResponse::Ok().json(&event);
+    events.publish(event);
+    response
+}
@@ -0,0 +1,19 @@
+use crate::bills::table_bill;
+use crate::configuration::TaxSettings;
+use crate::routes::error::database_error_response;
+use actix_web::{web, HttpResponse};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+
+#[tracing::instrument(name = "Preparing the bill", skip(table_no, pool, timezone, tax))]
+pub async fn bill(
+    table_no: web::Path<i32>,
+    pool: web::Data<PgPool>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+) -> HttpResponse {
+    match table_bill(&pool, table_no.into_inner(), **timezone, &tax).await {
+        Ok(bill) => HttpResponse::Ok().json(bill),
+        Err(e) => database_error_response(&e),
+    }
+}
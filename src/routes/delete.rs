@@ -1,33 +1,78 @@
+use crate::audit::record_audit;
+use crate::configuration::TaxSettings;
+use crate::domain::{ingredients_for, split_variant};
+use crate::error::DomainError;
+use crate::inventory::restore_stock;
+use crate::metrics::Metrics;
+use crate::payments::lock_session_for_void;
+use crate::request_id::RequestId;
+use crate::routes::error::domain_error_response;
+use crate::sessions::open_session_id;
 use actix_web::Result;
 use actix_web::{web, HttpResponse};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 // Handle result allowing for one place to change response handling
-fn handle_delete_result(res: Result<(), sqlx::Error>) -> HttpResponse {
+fn handle_delete_result(res: Result<u64, DomainError>, metrics: &Metrics) -> HttpResponse {
     match res {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+        Ok(voided) => {
+            metrics.order_voids.inc_by(voided);
+            HttpResponse::Ok().finish()
+        }
+        Err(e) => domain_error_response(e),
     }
 }
 
-#[tracing::instrument(name = "Delete with unique id", skip(id, pool))]
-pub async fn delete_with_id(id: web::Path<Uuid>, pool: web::Data<PgPool>) -> HttpResponse {
-    let result = delete_id_request(&pool, &id).await;
-    handle_delete_result(result)
+#[tracing::instrument(
+    name = "Delete with unique id",
+    skip(id, pool, metrics, timezone, tax, request_id)
+)]
+pub async fn delete_with_id(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    metrics: web::Data<Metrics>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let result = delete_id_request(&pool, &id, **timezone, &tax, &request_id).await;
+    handle_delete_result(result, &metrics)
 }
 
-#[tracing::instrument(name = "Delete item matching unique id from database", skip(id, pool))]
-pub async fn delete_id_request(pool: &PgPool, id: &Uuid) -> Result<(), sqlx::Error> {
-    sqlx::query_as!(
-        DatabaseResponse,
+#[tracing::instrument(
+    name = "Delete item matching unique id from database",
+    skip(id, pool, tax, request_id)
+)]
+pub async fn delete_id_request(
+    pool: &PgPool,
+    id: &Uuid,
+    timezone: Tz,
+    tax: &TaxSettings,
+    request_id: &RequestId,
+) -> Result<u64, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let session_id = sqlx::query!(
+        "SELECT session_id FROM orders WHERE id = $1 AND parent_id IS NULL",
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .and_then(|line| line.session_id);
+    if let Some(session_id) = session_id {
+        lock_session_for_void(&mut transaction, session_id, timezone, tax).await?;
+    }
+    // Components of a bundle are only voided along with their bundle
+    let voided = sqlx::query!(
         r#"
     DELETE FROM orders
-    WHERE id = $1
+    WHERE (id = $1 AND parent_id IS NULL) OR parent_id = $1
+    RETURNING item, variant, quantity, parent_id
     "#,
         id
     )
-    .execute(pool)
+    .fetch_all(&mut *transaction)
     .await
     .map_err(|e| {
         tracing::error!("Failed to execute query: {:?}", e);
@@ -35,40 +80,100 @@ pub async fn delete_id_request(pool: &PgPool, id: &Uuid) -> Result<(), sqlx::Err
         // Using the `?` operator to return early
         // if the function failed, returning a sqlx::Error
     })?;
-    Ok(())
+
+    let ingredients: Vec<(&str, &str, i32)> = voided
+        .iter()
+        .map(|line| (line.item.as_str(), line.variant.as_str(), line.quantity))
+        .collect();
+    restore_stock(&mut transaction, &ingredients_for(&ingredients)).await?;
+    let voided = voided
+        .iter()
+        .filter(|line| line.parent_id.is_none())
+        .count() as u64;
+
+    record_audit(
+        &mut transaction,
+        request_id,
+        "order_voided",
+        serde_json::json!({ "order_id": id, "voided": voided }),
+    )
+    .await?;
+
+    transaction.commit().await?;
+    Ok(voided)
 }
 
 #[tracing::instrument(
     name = "Delete all matching items from table in the database",
-    skip(args, pool)
+    skip(args, pool, metrics, timezone, tax, request_id)
 )]
 pub async fn delete_with_item_name(
     args: web::Path<(i32, String)>,
     pool: web::Data<PgPool>,
+    metrics: web::Data<Metrics>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+    request_id: RequestId,
 ) -> HttpResponse {
-    let result = delete_item_request(&pool, &args.0, args.1.to_string()).await;
-    handle_delete_result(result)
+    let (item, variant) = split_variant(&args.1);
+    let result = delete_item_request(
+        &pool,
+        &args.0,
+        item.to_string(),
+        variant,
+        **timezone,
+        &tax,
+        &request_id,
+    )
+    .await;
+    handle_delete_result(result, &metrics)
 }
 
 #[tracing::instrument(
     name = "Delete all matching items from table in the database sql request",
-    skip(table_no, item, pool)
+    skip(table_no, item, variant, pool, tax, request_id)
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn delete_item_request(
     pool: &PgPool,
     table_no: &i32,
     item: String,
-) -> Result<(), sqlx::Error> {
-    sqlx::query_as!(
-        DatabaseResponse,
+    // Every variant of the item is voided when `None`
+    variant: Option<&str>,
+    timezone: Tz,
+    tax: &TaxSettings,
+    request_id: &RequestId,
+) -> Result<u64, DomainError> {
+    let mut transaction = pool.begin().await?;
+    // Only the party seated now can have their lines voided, earlier parties have left
+    let session_id = open_session_id(&mut transaction, *table_no).await?;
+    if let Some(session_id) = session_id {
+        lock_session_for_void(&mut transaction, session_id, timezone, tax).await?;
+    }
+    // Matching bundles are voided with their components, components of other bundles are kept
+    let voided = sqlx::query!(
         r#"
     DELETE FROM orders
-    WHERE table_no = $1 AND item = $2
+    WHERE id IN (
+            SELECT id FROM orders
+            WHERE table_no = $1 AND item = $2 AND ($3::TEXT IS NULL OR variant = $3)
+                AND parent_id IS NULL
+                AND (session_id IS NULL OR session_id = $4)
+        )
+        OR parent_id IN (
+            SELECT id FROM orders
+            WHERE table_no = $1 AND item = $2 AND ($3::TEXT IS NULL OR variant = $3)
+                AND parent_id IS NULL
+                AND (session_id IS NULL OR session_id = $4)
+        )
+    RETURNING item, variant, quantity, parent_id
     "#,
         table_no,
-        item
+        item,
+        variant,
+        session_id
     )
-    .execute(pool)
+    .fetch_all(&mut *transaction)
     .await
     .map_err(|e| {
         tracing::error!("Failed to execute query: {:?}", e);
@@ -76,5 +181,30 @@ pub async fn delete_item_request(
         // Using the `?` operator to return early
         // if the function failed, returning a sqlx::Error
     })?;
-    Ok(())
+
+    let ingredients: Vec<(&str, &str, i32)> = voided
+        .iter()
+        .map(|line| (line.item.as_str(), line.variant.as_str(), line.quantity))
+        .collect();
+    restore_stock(&mut transaction, &ingredients_for(&ingredients)).await?;
+    let voided = voided
+        .iter()
+        .filter(|line| line.parent_id.is_none())
+        .count() as u64;
+
+    record_audit(
+        &mut transaction,
+        request_id,
+        "order_voided",
+        serde_json::json!({
+            "table_no": table_no,
+            "item": item,
+            "variant": variant,
+            "voided": voided,
+        }),
+    )
+    .await?;
+
+    transaction.commit().await?;
+    Ok(voided)
 }
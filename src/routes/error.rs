@@ -0,0 +1,60 @@
+use crate::error::DomainError;
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::HttpResponse;
+
+// How long clients are asked to wait before retrying a request the database was too busy for
+pub const RETRY_AFTER_SECONDS: u64 = 1;
+
+// Postgres error code for a statement cancelled by `statement_timeout`
+const QUERY_CANCELED: &str = "57014";
+
+// The database ran out of time for the request rather than failing it outright
+pub fn is_timeout(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(e) => e.code().as_deref() == Some(QUERY_CANCELED),
+        _ => false,
+    }
+}
+
+// Handle database errors in one place, timeouts become a 503 so clients know to retry
+pub fn database_error_response(e: &sqlx::Error) -> HttpResponse {
+    if is_timeout(e) {
+        timeout_response()
+    } else {
+        HttpResponse::InternalServerError().finish()
+    }
+}
+
+// The database ran out of time, the same request is likely to succeed a moment later
+pub fn timeout_response() -> HttpResponse {
+    HttpResponse::ServiceUnavailable()
+        .insert_header((RETRY_AFTER, RETRY_AFTER_SECONDS))
+        .json(serde_json::json!({
+            "error": "The database is busy, please retry.",
+            "retry_after_seconds": RETRY_AFTER_SECONDS,
+        }))
+}
+
+// Bad input is a 400, a clash with how things stand a 409 with a code saying which,
+// a declined charge a 402 and a discount needing a manager's code a 403
+pub fn domain_error_response(e: DomainError) -> HttpResponse {
+    match e {
+        DomainError::Invalid(error) => bad_request(error),
+        DomainError::NotFound(error) => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": error }))
+        }
+        DomainError::Conflict { code, message } => {
+            HttpResponse::Conflict().json(serde_json::json!({ "error": message, "code": code }))
+        }
+        DomainError::Declined(reason) => HttpResponse::PaymentRequired()
+            .json(serde_json::json!({ "error": reason, "code": "declined" })),
+        DomainError::ApprovalRequired(error) => HttpResponse::Forbidden()
+            .json(serde_json::json!({ "error": error, "code": "approval_required" })),
+        DomainError::Database(e) => database_error_response(&e),
+    }
+}
+
+pub fn bad_request(error: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({ "error": error }))
+}
@@ -1,5 +1,153 @@
-use actix_web::HttpResponse;
+use crate::migration::pending_migrations;
+use crate::shutdown::Shutdown;
+use actix_web::rt::time::timeout;
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
 
+// How long readiness waits on the database before declaring it unreachable
+pub struct ReadinessTimeout(pub Duration);
+
+#[derive(Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Failing,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ComponentStatus {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ComponentStatus {
+    fn ok() -> Self {
+        Self {
+            status: Status::Ok,
+            detail: None,
+        }
+    }
+
+    fn failing(detail: String) -> Self {
+        Self {
+            status: Status::Failing,
+            detail: Some(detail),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReadinessChecks {
+    pub server: ComponentStatus,
+    pub database: ComponentStatus,
+    pub migrations: ComponentStatus,
+    pub pool: ComponentStatus,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReadinessReport {
+    pub status: Status,
+    pub checks: ReadinessChecks,
+}
+
+// Kept for existing probes, behaves the same as liveness
 pub async fn health_check() -> HttpResponse {
     HttpResponse::Ok().finish()
 }
+
+// The process is up and able to answer requests
+pub async fn health_live() -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+// The process can actually serve traffic, i.e. its dependencies are usable
+#[tracing::instrument(name = "Checking readiness", skip(pool, readiness_timeout, shutdown))]
+pub async fn health_ready(
+    pool: web::Data<PgPool>,
+    readiness_timeout: web::Data<ReadinessTimeout>,
+    shutdown: web::Data<Shutdown>,
+) -> HttpResponse {
+    let checks = ReadinessChecks {
+        server: check_server(&shutdown),
+        database: check_database(&pool, readiness_timeout.0).await,
+        migrations: check_migrations(&pool, readiness_timeout.0).await,
+        pool: check_pool(&pool),
+    };
+
+    let all_ok = [
+        &checks.server,
+        &checks.database,
+        &checks.migrations,
+        &checks.pool,
+    ]
+    .iter()
+    .all(|c| c.status == Status::Ok);
+
+    if all_ok {
+        HttpResponse::Ok().json(ReadinessReport {
+            status: Status::Ok,
+            checks,
+        })
+    } else {
+        HttpResponse::ServiceUnavailable().json(ReadinessReport {
+            status: Status::Failing,
+            checks,
+        })
+    }
+}
+
+// A server that is draining should not be sent new traffic
+fn check_server(shutdown: &Shutdown) -> ComponentStatus {
+    if shutdown.is_triggered() {
+        ComponentStatus::failing("Shutting down".to_string())
+    } else {
+        ComponentStatus::ok()
+    }
+}
+
+async fn check_database(pool: &PgPool, limit: Duration) -> ComponentStatus {
+    let ping = async {
+        let mut connection = pool.acquire().await?;
+        sqlx::query("SELECT 1").execute(&mut *connection).await
+    };
+
+    match timeout(limit, ping).await {
+        Ok(Ok(_)) => ComponentStatus::ok(),
+        Ok(Err(e)) => ComponentStatus::failing(format!("Failed to query the database: {}", e)),
+        Err(_) => ComponentStatus::failing(format!(
+            "No database connection within {}ms",
+            limit.as_millis()
+        )),
+    }
+}
+
+async fn check_migrations(pool: &PgPool, limit: Duration) -> ComponentStatus {
+    match timeout(limit, pending_migrations(pool)).await {
+        Ok(Ok(pending)) if pending.is_empty() => ComponentStatus::ok(),
+        Ok(Ok(pending)) => {
+            let versions: Vec<String> = pending.iter().map(|m| m.version.to_string()).collect();
+            ComponentStatus::failing(format!("Pending migrations: {}", versions.join(", ")))
+        }
+        Ok(Err(e)) => ComponentStatus::failing(format!("Failed to list migrations: {}", e)),
+        Err(_) => ComponentStatus::failing(format!(
+            "Migrations could not be listed within {}ms",
+            limit.as_millis()
+        )),
+    }
+}
+
+// A pool with every connection checked out means requests are queueing for a connection
+fn check_pool(pool: &PgPool) -> ComponentStatus {
+    let max = pool.options().get_max_connections();
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+
+    if size >= max && idle == 0 {
+        ComponentStatus::failing(format!("All {} connections are in use", max))
+    } else {
+        ComponentStatus::ok()
+    }
+}
@@ -0,0 +1,52 @@
+use crate::domain::RECIPES;
+use crate::inventory::{list_ingredients, set_stock, Ingredient};
+use crate::routes::error::{bad_request, database_error_response};
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+#[derive(serde::Deserialize)]
+pub struct StockLevel {
+    stock: i32,
+    low_stock_threshold: i32,
+}
+
+#[tracing::instrument(name = "Listing stock levels", skip(pool))]
+pub async fn get_inventory(pool: web::Data<PgPool>) -> HttpResponse {
+    match list_ingredients(&pool).await {
+        Ok(ingredients) => HttpResponse::Ok().json(ingredients),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+/*
+ * Sets the stock of an ingredient after a delivery or a stock take, e.g.
+ * `{"stock": 40, "low_stock_threshold": 10}`. Only ingredients used by a recipe can be tracked.
+ */
+#[tracing::instrument(name = "Setting a stock level", skip(pool, level))]
+pub async fn update_inventory(
+    ingredient: web::Path<String>,
+    level: web::Json<StockLevel>,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let name = ingredient.into_inner();
+    let used = RECIPES
+        .iter()
+        .flat_map(|recipe| recipe.ingredients.iter())
+        .any(|usage| usage.ingredient == name);
+    if !used {
+        return bad_request(format!("{} is not used by any recipe.", name));
+    }
+    if level.stock < 0 || level.low_stock_threshold < 0 {
+        return bad_request("Stock and the low stock threshold cannot be negative.".into());
+    }
+
+    let ingredient = Ingredient {
+        name,
+        stock: level.stock,
+        low_stock_threshold: level.low_stock_threshold,
+    };
+    match set_stock(&pool, &ingredient).await {
+        Ok(_) => HttpResponse::Ok().json(ingredient),
+        Err(e) => database_error_response(&e),
+    }
+}
@@ -0,0 +1,72 @@
+use crate::audit::record_audit;
+use crate::clock::Clock;
+use crate::domain::Course;
+use crate::error::DomainError;
+use crate::events::{Event, EventBus};
+use crate::id_generator::IdGenerator;
+use crate::kitchen::{fire_course, kitchen_queue};
+use crate::printing::queue_tickets;
+use crate::request_id::RequestId;
+use crate::routes::error::{database_error_response, domain_error_response};
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/*
+ * Sends the held lines of a course to the kitchen, e.g. `POST /sessions/{id}/fire/main`,
+ * with a ticket for every station that has something to make.
+ */
+#[tracing::instrument(name = "Firing a course", skip(pool, events, clock, ids, request_id))]
+pub async fn post_fire(
+    path: web::Path<(Uuid, String)>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let (session_id, course) = path.into_inner();
+    let course = match Course::parse(&course) {
+        Ok(course) => course,
+        Err(e) => return domain_error_response(DomainError::Invalid(e)),
+    };
+
+    let fired = async {
+        let mut transaction = pool.begin().await?;
+        let fired = fire_course(&mut transaction, session_id, course, clock.now()).await?;
+        queue_tickets(
+            &mut transaction,
+            ids.get_ref(),
+            &fired.order_ids,
+            Some(course),
+            fired.fired_at,
+        )
+        .await?;
+        record_audit(
+            &mut transaction,
+            &request_id,
+            "course_fired",
+            serde_json::json!(fired),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok::<_, DomainError>(fired)
+    };
+    match fired.await {
+        Ok(fired) => {
+            let response = HttpResponse::Ok().json(&fired);
+            events.publish(Event::CourseFired(fired));
+            response
+        }
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// What the kitchen is cooking, soonest due first
+#[tracing::instrument(name = "Listing the kitchen queue", skip(pool, clock))]
+pub async fn get_kitchen(pool: web::Data<PgPool>, clock: web::Data<dyn Clock>) -> HttpResponse {
+    match kitchen_queue(&pool, clock.now()).await {
+        Ok(tickets) => HttpResponse::Ok().json(tickets),
+        Err(e) => database_error_response(&e),
+    }
+}
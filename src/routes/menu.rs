@@ -0,0 +1,81 @@
+use crate::availability::{eighty_sixed_items, EightySixedItem};
+use crate::clock::Clock;
+use crate::domain::{menu_item, split_variant, Bundle, MenuItem, BUNDLES, MENU};
+use crate::routes::error::database_error_response;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+struct MenuListing {
+    items: Vec<MenuEntry>,
+    bundles: Vec<BundleEntry>,
+}
+
+// A menu item with whether it can be ordered right now, so tablets can grey it out
+#[derive(Serialize)]
+struct MenuEntry {
+    #[serde(flatten)]
+    item: &'static MenuItem,
+    available: bool,
+    restore_at: Option<DateTime<Utc>>,
+    // What each variant costs right now, after e.g. happy hour prices
+    current_price_cents: BTreeMap<&'static str, i64>,
+}
+
+// A bundle can be ordered as long as the default item of every slot can
+#[derive(Serialize)]
+struct BundleEntry {
+    #[serde(flatten)]
+    bundle: &'static Bundle,
+    available: bool,
+}
+
+#[tracing::instrument(name = "Listing the menu", skip(pool, clock, timezone))]
+pub async fn menu(
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    timezone: web::Data<Tz>,
+) -> HttpResponse {
+    let now = clock.now();
+    let eighty_sixed = match eighty_sixed_items(&pool, now).await {
+        Ok(items) => items,
+        Err(e) => return database_error_response(&e),
+    };
+    let now = now.with_timezone(timezone.get_ref()).naive_local();
+    let flag = |name: &str| -> Option<&EightySixedItem> {
+        eighty_sixed.iter().find(|flag| flag.item == name)
+    };
+    let available = |name: &str| -> bool {
+        flag(name).is_none() && menu_item(name).is_some_and(|item| item.is_served_at(now))
+    };
+
+    let items = MENU
+        .iter()
+        .map(|item| MenuEntry {
+            item,
+            available: available(item.name),
+            restore_at: flag(item.name).and_then(|flag| flag.restore_at),
+            current_price_cents: item
+                .variants
+                .iter()
+                .map(|variant| (variant.name, variant.price_cents_at(now)))
+                .collect(),
+        })
+        .collect();
+    let bundles = BUNDLES
+        .iter()
+        .map(|bundle| BundleEntry {
+            bundle,
+            available: bundle
+                .slots
+                .iter()
+                .all(|slot| available(split_variant(slot.default_item).0)),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(MenuListing { items, bundles })
+}
@@ -0,0 +1,61 @@
+use crate::clock::Clock;
+use crate::domain::MENU_ITEMS;
+use crate::metrics::Metrics;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+#[tracing::instrument(name = "Exporting metrics", skip(metrics, pool, clock))]
+pub async fn export_metrics(
+    metrics: web::Data<Metrics>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+) -> HttpResponse {
+    metrics.observe_pool(&pool);
+
+    // An unreachable database shouldn't stop the remaining metrics from being scraped
+    match kitchen_backlog_request(&pool, clock.now()).await {
+        Ok(backlog) => {
+            // Bundles and items taken off the menu would otherwise keep their last count
+            metrics.kitchen_backlog.reset();
+            for item in MENU_ITEMS {
+                metrics.kitchen_backlog.with_label_values(&[item]).set(0);
+            }
+            for (item, orders) in backlog {
+                metrics
+                    .kitchen_backlog
+                    .with_label_values(&[&item])
+                    .set(orders);
+            }
+        }
+        Err(e) => tracing::error!("Failed to compute the kitchen backlog: {:?}", e),
+    }
+
+    match metrics.encode() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(body),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[tracing::instrument(name = "Retrieving the kitchen backlog from the database", skip(pool))]
+pub async fn kitchen_backlog_request(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    // An order is in the backlog from being fired until its preparation time has elapsed
+    let rows = sqlx::query!(
+        r#"
+    SELECT item, COUNT(*) AS "orders!" FROM orders
+    WHERE fired_at IS NOT NULL
+        AND fired_at + make_interval(mins => preparation_time) > $1
+    GROUP BY item
+    "#,
+        now
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.item, r.orders)).collect())
+}
@@ -1,9 +1,37 @@
+mod admin;
+mod availability;
+mod bill;
 mod delete;
+mod error;
 mod health_check;
+mod inventory;
+mod kitchen;
+mod menu;
+mod metrics;
 mod order;
+mod payments;
+mod printing;
+mod promotions;
 mod query;
+mod receipts;
+mod reservations;
+mod tables;
 
+pub use admin::*;
+pub use availability::*;
+pub use bill::*;
 pub use delete::*;
+pub use error::*;
 pub use health_check::*;
+pub use inventory::*;
+pub use kitchen::*;
+pub use menu::*;
+pub use metrics::*;
 pub use order::*;
+pub use payments::*;
+pub use printing::*;
+pub use promotions::*;
 pub use query::*;
+pub use receipts::*;
+pub use reservations::*;
+pub use tables::*;
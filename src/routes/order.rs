@@ -1,8 +1,25 @@
-use crate::domain::{Item, Nat, NewOrder};
+use crate::audit::record_audit;
+use crate::availability::eighty_sixed_items;
+use crate::clock::Clock;
+use crate::configuration::RuntimeSettings;
+use crate::domain::{
+    bundle, ingredients_for, menu_item, Course, Item, Nat, NewBundleOrder, NewOrder, Note,
+    OrderValidationError,
+};
+use crate::events::EventBus;
+use crate::id_generator::IdGenerator;
+use crate::inventory::{deduct_stock, LowStock, StockError};
+use crate::metrics::Metrics;
+use crate::printing::queue_tickets;
+use crate::request_id::RequestId;
+use crate::rng::Rng;
+use crate::routes::error::database_error_response;
+use crate::runtime_settings::RuntimeSettingsStore;
+use crate::sessions::open_session_id;
 use actix_web::{web, HttpResponse};
-use chrono::Utc;
-use rand::Rng;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 #[derive(serde::Deserialize)]
@@ -10,26 +27,240 @@ pub struct FormData {
     table_no: i32,
     item: String,
     quantity: i32,
+    // Comma separated, e.g. `no onions,extra cheese`
+    modifiers: Option<String>,
+    note: Option<String>,
+    // The item's own course when not given
+    course: Option<String>,
+    // `true` keeps the line from the kitchen until its course is fired
+    hold: Option<bool>,
+    // Eaten in when not given
+    takeaway: Option<bool>,
 }
 
 impl TryFrom<FormData> for NewOrder {
-    type Error = String;
+    type Error = OrderValidationError;
 
     fn try_from(value: FormData) -> Result<Self, Self::Error> {
-        let table_no = Nat::parse(value.table_no)?;
-        let item = Item::parse(value.item)?;
-        let quantity = Nat::parse(value.quantity)?;
+        let table_no = Nat::parse(value.table_no).map_err(OrderValidationError::InvalidTableNo)?;
+        let item = Item::parse(value.item).map_err(OrderValidationError::InvalidItem)?;
+        let quantity =
+            Nat::parse_quantity(value.quantity).map_err(OrderValidationError::InvalidQuantity)?;
+
+        let modifier_names: Vec<String> = value
+            .modifiers
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let menu_item = menu_item(item.as_ref()).ok_or_else(|| {
+            OrderValidationError::InvalidItem(format!("{} is not on the menu.", item.as_ref()))
+        })?;
+        let variant = menu_item.variant(item.variant()).ok_or_else(|| {
+            OrderValidationError::InvalidItem(format!(
+                "{} is not a variant of {}.",
+                item.variant(),
+                item.as_ref()
+            ))
+        })?;
+        let modifiers = menu_item
+            .select_modifiers(&modifier_names)
+            .map_err(OrderValidationError::InvalidModifiers)?;
+
+        let note = parse_note(value.note)?;
+        let course = match value.course {
+            Some(course) if !course.is_empty() => {
+                Course::parse(&course).map_err(OrderValidationError::InvalidCourse)?
+            }
+            _ => menu_item.course,
+        };
+
         Ok(Self {
             table_no,
             item,
             quantity,
+            menu_item,
+            variant,
+            modifiers,
+            note,
+            course,
+            hold: value.hold.unwrap_or(false),
+            takeaway: value.takeaway.unwrap_or(false),
+        })
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct BundleFormData {
+    table_no: i32,
+    bundle: String,
+    quantity: i32,
+    // Comma separated `slot=item` pairs, e.g. `drink=cola:large,main=hamburger:double`
+    substitutions: Option<String>,
+    note: Option<String>,
+    hold: Option<bool>,
+    takeaway: Option<bool>,
+}
+
+impl TryFrom<BundleFormData> for NewBundleOrder {
+    type Error = OrderValidationError;
+
+    fn try_from(value: BundleFormData) -> Result<Self, Self::Error> {
+        let table_no = Nat::parse(value.table_no).map_err(OrderValidationError::InvalidTableNo)?;
+        let quantity =
+            Nat::parse_quantity(value.quantity).map_err(OrderValidationError::InvalidQuantity)?;
+        let bundle = bundle(&value.bundle).ok_or_else(|| {
+            OrderValidationError::InvalidBundle(format!("{} is not a bundle.", value.bundle))
+        })?;
+
+        let substitutions = value
+            .substitutions
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|substitution| !substitution.is_empty())
+            .map(|substitution| {
+                substitution
+                    .split_once('=')
+                    .map(|(slot, item)| (slot.trim().to_string(), item.trim().to_string()))
+                    .ok_or_else(|| {
+                        OrderValidationError::InvalidBundle(format!(
+                            "{} is not a slot=item substitution.",
+                            substitution
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let components = bundle
+            .components(&substitutions)
+            .map_err(OrderValidationError::InvalidBundle)?;
+
+        Ok(Self {
+            table_no,
+            bundle,
+            quantity,
+            components,
+            note: parse_note(value.note)?,
+            hold: value.hold.unwrap_or(false),
+            takeaway: value.takeaway.unwrap_or(false),
         })
     }
 }
 
+// An empty note is the same as no note at all
+fn parse_note(note: Option<String>) -> Result<Option<Note>, OrderValidationError> {
+    match note {
+        Some(note) if !note.is_empty() => Ok(Some(
+            Note::parse(note).map_err(OrderValidationError::InvalidNote)?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+// Counts the rejection and tells the client why, unavailable items are a conflict not a bad request
+fn reject(metrics: &Metrics, e: OrderValidationError) -> HttpResponse {
+    metrics
+        .order_validation_failures
+        .with_label_values(&[e.reason()])
+        .inc();
+    match e {
+        OrderValidationError::EightySixed(_)
+        | OrderValidationError::NotServedNow(_)
+        | OrderValidationError::NotSeated(_)
+        | OrderValidationError::SoldOut(_) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": e.to_string(),
+            "code": e.reason(),
+        })),
+        _ => HttpResponse::BadRequest().finish(),
+    }
+}
+
+/*
+ * Checks the items of an order against what the kitchen has 86'd.
+ * `ordered` names what the waiter asked for, the item itself or a bundle containing it.
+ */
+async fn check_not_eighty_sixed(
+    pool: &PgPool,
+    items: &[&str],
+    ordered: &str,
+    now: DateTime<Utc>,
+) -> Result<Result<(), OrderValidationError>, sqlx::Error> {
+    let eighty_sixed = eighty_sixed_items(pool, now).await?;
+    match eighty_sixed
+        .iter()
+        .find(|flag| items.contains(&flag.item.as_str()))
+    {
+        None => Ok(Ok(())),
+        Some(flag) => {
+            let until = match flag.restore_at {
+                Some(restore_at) => format!(" until {}", restore_at.format("%H:%M UTC")),
+                None => String::new(),
+            };
+            let message = if flag.item == ordered {
+                format!("{} is 86'd{}.", flag.item, until)
+            } else {
+                format!("{} is 86'd{}, so is the {}.", flag.item, until, ordered)
+            };
+            Ok(Err(OrderValidationError::EightySixed(message)))
+        }
+    }
+}
+
+// Why an order that passed validation could not be placed
+#[derive(Debug)]
+pub enum PlaceOrderError {
+    SoldOut(String),
+    // Lines were to be held but nobody is seated at the table
+    NotSeated,
+    Database(sqlx::Error),
+}
+
+impl From<StockError> for PlaceOrderError {
+    fn from(e: StockError) -> Self {
+        match e {
+            StockError::SoldOut(ingredient) => PlaceOrderError::SoldOut(ingredient),
+            StockError::Database(e) => PlaceOrderError::Database(e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for PlaceOrderError {
+    fn from(e: sqlx::Error) -> Self {
+        PlaceOrderError::Database(e)
+    }
+}
+
+fn not_seated(table_no: i32) -> OrderValidationError {
+    OrderValidationError::NotSeated(format!(
+        "Nobody is seated at table {}, so nothing can be held for it.",
+        table_no
+    ))
+}
+
+fn sold_out(item: &str, ingredient: &str) -> OrderValidationError {
+    OrderValidationError::SoldOut(format!(
+        "{} is sold out, there is no {} left.",
+        item, ingredient
+    ))
+}
+
+// Low stock is announced once the order that caused it has been committed
+fn announce_low_stock(events: &EventBus, low_stock: Vec<LowStock>) {
+    for low in low_stock {
+        tracing::warn!(
+            ingredient = %low.ingredient,
+            stock = low.stock,
+            "Ingredient is running low"
+        );
+        events.publish(low.into());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(
   name = "Adding a new subscriber",
-  skip(form, pool),
+  skip(form, pool, metrics, runtime_settings, events, clock, timezone, ids, rng, request_id),
   fields(
     order_tableNo = %form.table_no,
     order_item = %form.item,
@@ -37,43 +268,435 @@ impl TryFrom<FormData> for NewOrder {
   )
 )]
 
-pub async fn order(form: web::Form<FormData>, pool: web::Data<PgPool>) -> HttpResponse {
-    let new_order = match form.0.try_into() {
+pub async fn order(
+    form: web::Form<FormData>,
+    pool: web::Data<PgPool>,
+    metrics: web::Data<Metrics>,
+    runtime_settings: web::Data<RuntimeSettingsStore>,
+    events: web::Data<EventBus>,
+    clock: web::Data<dyn Clock>,
+    timezone: web::Data<Tz>,
+    ids: web::Data<dyn IdGenerator>,
+    rng: web::Data<dyn Rng>,
+    request_id: RequestId,
+) -> HttpResponse {
+    // The same settings are used for the whole request even if they change meanwhile
+    let runtime_settings = runtime_settings.get();
+
+    let new_order: NewOrder = match form.0.try_into() {
+        Ok(form) => form,
+        Err(e) => return reject(&metrics, e),
+    };
+
+    // Schedules and prices are worked out in the restaurant's own time
+    let placed_at = clock.now().with_timezone(timezone.get_ref());
+    if !new_order.menu_item.is_served_at(placed_at.naive_local()) {
+        return reject(
+            &metrics,
+            OrderValidationError::NotServedNow(format!(
+                "{} is not served at this time.",
+                new_order.item.as_ref()
+            )),
+        );
+    }
+
+    match check_not_eighty_sixed(
+        &pool,
+        &[new_order.item.as_ref()],
+        new_order.item.as_ref(),
+        placed_at.with_timezone(&Utc),
+    )
+    .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return reject(&metrics, e),
+        Err(e) => return database_error_response(&e),
+    }
+
+    match insert_order(
+        &pool,
+        &new_order,
+        &placed_at,
+        &runtime_settings,
+        &events,
+        ids.get_ref(),
+        rng.get_ref(),
+        &request_id,
+    )
+    .await
+    {
+        Ok(_) => {
+            metrics
+                .orders_placed
+                .with_label_values(&[new_order.item.as_ref()])
+                .inc();
+            HttpResponse::Ok().finish()
+        }
+        Err(PlaceOrderError::SoldOut(ingredient)) => {
+            reject(&metrics, sold_out(new_order.item.as_ref(), &ingredient))
+        }
+        Err(PlaceOrderError::NotSeated) => {
+            reject(&metrics, not_seated(*new_order.table_no.as_ref()))
+        }
+        Err(PlaceOrderError::Database(e)) => database_error_response(&e),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(
+  name = "Adding a new bundle order",
+  skip(form, pool, metrics, runtime_settings, events, clock, timezone, ids, rng, request_id),
+  fields(
+    order_tableNo = %form.table_no,
+    order_bundle = %form.bundle,
+    order_quantity = %form.quantity,
+  )
+)]
+pub async fn order_bundle(
+    form: web::Form<BundleFormData>,
+    pool: web::Data<PgPool>,
+    metrics: web::Data<Metrics>,
+    runtime_settings: web::Data<RuntimeSettingsStore>,
+    events: web::Data<EventBus>,
+    clock: web::Data<dyn Clock>,
+    timezone: web::Data<Tz>,
+    ids: web::Data<dyn IdGenerator>,
+    rng: web::Data<dyn Rng>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let runtime_settings = runtime_settings.get();
+
+    let new_order: NewBundleOrder = match form.0.try_into() {
         Ok(form) => form,
-        Err(_) => return HttpResponse::BadRequest().finish(),
+        Err(e) => return reject(&metrics, e),
     };
 
-    match insert_order(&pool, &new_order).await {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    let placed_at = clock.now().with_timezone(timezone.get_ref());
+    if let Some(component) = new_order.components.iter().find(|component| {
+        !menu_item(component.item.as_ref())
+            .is_some_and(|item| item.is_served_at(placed_at.naive_local()))
+    }) {
+        return reject(
+            &metrics,
+            OrderValidationError::NotServedNow(format!(
+                "{} is not served at this time, so neither is the {}.",
+                component.item.as_ref(),
+                new_order.bundle.name
+            )),
+        );
+    }
+
+    let items: Vec<&str> = new_order
+        .components
+        .iter()
+        .map(|component| component.item.as_ref())
+        .collect();
+    match check_not_eighty_sixed(
+        &pool,
+        &items,
+        new_order.bundle.name,
+        placed_at.with_timezone(&Utc),
+    )
+    .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return reject(&metrics, e),
+        Err(e) => return database_error_response(&e),
+    }
+
+    match insert_bundle_order(
+        &pool,
+        &new_order,
+        &placed_at,
+        &runtime_settings,
+        &events,
+        ids.get_ref(),
+        rng.get_ref(),
+        &request_id,
+    )
+    .await
+    {
+        Ok(_) => {
+            metrics
+                .orders_placed
+                .with_label_values(&[new_order.bundle.name])
+                .inc();
+            HttpResponse::Ok().finish()
+        }
+        Err(PlaceOrderError::SoldOut(ingredient)) => {
+            reject(&metrics, sold_out(new_order.bundle.name, &ingredient))
+        }
+        Err(PlaceOrderError::NotSeated) => {
+            reject(&metrics, not_seated(*new_order.table_no.as_ref()))
+        }
+        Err(PlaceOrderError::Database(e)) => database_error_response(&e),
     }
 }
 
-#[tracing::instrument(name = "Adding new order to database", skip(order, pool))]
-pub async fn insert_order(pool: &PgPool, order: &NewOrder) -> Result<(), sqlx::Error> {
-    let order_id = Uuid::new_v4();
-    let preparation_time = rand::thread_rng().gen_range(5..15);
+// A row of the orders table
+struct OrderLine<'a> {
+    id: Uuid,
+    table_no: i32,
+    item: &'a str,
+    variant: &'a str,
+    quantity: i32,
+    preparation_time: i32,
+    modifiers: Vec<String>,
+    note: Option<&'a str>,
+    unit_price_cents: i64,
+    parent_id: Option<Uuid>,
+    session_id: Option<Uuid>,
+    course: &'static str,
+    placed_at: DateTime<Utc>,
+    // `None` while the line is held
+    fired_at: Option<DateTime<Utc>>,
+    takeaway: bool,
+}
 
+async fn insert_line(
+    transaction: &mut Transaction<'_, Postgres>,
+    line: &OrderLine<'_>,
+) -> Result<(), sqlx::Error> {
     sqlx::query!(
         r#"
-    INSERT INTO orders (id, table_no, item, quantity, preparation_time, placed_at)
-    VALUES ($1, $2, $3, $4, $5, $6)
+    INSERT INTO orders (
+        id, table_no, item, variant, quantity, preparation_time, placed_at, modifiers, note,
+        unit_price_cents, parent_id, session_id, course, fired_at, takeaway
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
     "#,
-        order_id,
-        order.table_no.as_ref(),
-        order.item.as_ref(),
-        order.quantity.as_ref(),
-        preparation_time,
-        Utc::now()
+        line.id,
+        line.table_no,
+        line.item,
+        line.variant,
+        line.quantity,
+        line.preparation_time,
+        line.placed_at,
+        &line.modifiers,
+        line.note,
+        line.unit_price_cents,
+        line.parent_id,
+        line.session_id,
+        line.course,
+        line.fired_at,
+        line.takeaway,
     )
-    .execute(pool)
+    .execute(&mut **transaction)
     .await
     .map_err(|e| {
         tracing::error!("Failed to execute query: {:?}", e);
         e
         // Using the `?` operator to return early
         // if the function failed, returning a sqlx::Error
-        // // We will talk about error handling in depth later!
     })?;
     Ok(())
 }
+
+fn preparation_time(item: &Item, runtime_settings: &RuntimeSettings, rng: &dyn Rng) -> i32 {
+    let minutes = menu_item(item.as_ref())
+        .and_then(|menu_item| menu_item.variant(item.variant()))
+        .map(|variant| rng.gen_range(variant.preparation_minutes.clone()))
+        .unwrap_or(5);
+    runtime_settings.scale_preparation_time(minutes)
+}
+
+#[tracing::instrument(
+    name = "Adding new order to database",
+    skip(order, pool, runtime_settings, events, ids, rng, request_id)
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_order(
+    pool: &PgPool,
+    order: &NewOrder,
+    placed_at: &DateTime<Tz>,
+    runtime_settings: &RuntimeSettings,
+    events: &EventBus,
+    ids: &dyn IdGenerator,
+    rng: &dyn Rng,
+    request_id: &RequestId,
+) -> Result<(), PlaceOrderError> {
+    let mut transaction = pool.begin().await?;
+    // Orders belong to whoever is seated at the table
+    let session_id = open_session_id(&mut transaction, *order.table_no.as_ref()).await?;
+    if order.hold && session_id.is_none() {
+        return Err(PlaceOrderError::NotSeated);
+    }
+    // Happy hour is in the restaurant's time, lines are stored in UTC
+    let unit_price_cents = order.unit_price_cents(placed_at.naive_local());
+    let placed_at = placed_at.with_timezone(&Utc);
+
+    let line = OrderLine {
+        id: ids.new_id(),
+        table_no: *order.table_no.as_ref(),
+        item: order.item.as_ref(),
+        variant: order.item.variant(),
+        quantity: *order.quantity.as_ref(),
+        preparation_time: preparation_time(&order.item, runtime_settings, rng),
+        modifiers: order.modifiers.iter().map(|m| m.name.to_string()).collect(),
+        note: order.note.as_ref().map(|note| note.as_ref()),
+        unit_price_cents,
+        parent_id: None,
+        session_id,
+        course: order.course.as_str(),
+        placed_at,
+        fired_at: (!order.hold).then_some(placed_at),
+        takeaway: order.takeaway,
+    };
+
+    let low_stock = deduct_stock(
+        &mut transaction,
+        &ingredients_for(&[(line.item, line.variant, line.quantity)]),
+    )
+    .await?;
+    insert_line(&mut transaction, &line).await?;
+    if line.fired_at.is_some() {
+        queue_tickets(&mut transaction, ids, &[line.id], None, placed_at).await?;
+    }
+
+    record_audit(
+        &mut transaction,
+        request_id,
+        "order_placed",
+        serde_json::json!({
+            "order_id": line.id,
+            "table_no": line.table_no,
+            "item": line.item,
+            "variant": line.variant,
+            "quantity": line.quantity,
+            "modifiers": line.modifiers,
+            "note": line.note,
+            "unit_price_cents": line.unit_price_cents,
+            "takeaway": line.takeaway,
+        }),
+    )
+    .await?;
+
+    transaction.commit().await?;
+    announce_low_stock(events, low_stock);
+    Ok(())
+}
+
+/*
+ * Places a bundle as a line carrying the bundle's price and a line per component,
+ * so the kitchen sees the individual items. Components are not charged on their own.
+ */
+#[tracing::instrument(
+    name = "Adding new bundle order to database",
+    skip(order, pool, runtime_settings, events, ids, rng, request_id)
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_bundle_order(
+    pool: &PgPool,
+    order: &NewBundleOrder,
+    placed_at: &DateTime<Tz>,
+    runtime_settings: &RuntimeSettings,
+    events: &EventBus,
+    ids: &dyn IdGenerator,
+    rng: &dyn Rng,
+    request_id: &RequestId,
+) -> Result<(), PlaceOrderError> {
+    let bundle_id = ids.new_id();
+    let table_no = *order.table_no.as_ref();
+    let quantity = *order.quantity.as_ref();
+    let note = order.note.as_ref().map(|note| note.as_ref());
+
+    let mut transaction = pool.begin().await?;
+    let session_id = open_session_id(&mut transaction, table_no).await?;
+    if order.hold && session_id.is_none() {
+        return Err(PlaceOrderError::NotSeated);
+    }
+    let placed_at = placed_at.with_timezone(&Utc);
+    let fired_at = (!order.hold).then_some(placed_at);
+
+    let components: Vec<OrderLine> = order
+        .components
+        .iter()
+        .map(|component| OrderLine {
+            id: ids.new_id(),
+            table_no,
+            item: component.item.as_ref(),
+            variant: component.item.variant(),
+            quantity,
+            preparation_time: preparation_time(&component.item, runtime_settings, rng),
+            modifiers: vec![],
+            note: None,
+            unit_price_cents: 0,
+            parent_id: Some(bundle_id),
+            session_id,
+            course: menu_item(component.item.as_ref())
+                .map(|item| item.course)
+                .unwrap_or(Course::Main)
+                .as_str(),
+            placed_at,
+            fired_at,
+            takeaway: order.takeaway,
+        })
+        .collect();
+    // The bundle is ready when its slowest component is
+    let bundle = OrderLine {
+        id: bundle_id,
+        table_no,
+        item: order.bundle.name,
+        variant: "regular",
+        quantity,
+        preparation_time: components
+            .iter()
+            .map(|line| line.preparation_time)
+            .max()
+            .unwrap_or(1),
+        modifiers: vec![],
+        note,
+        unit_price_cents: order.unit_price_cents(),
+        parent_id: None,
+        session_id,
+        course: Course::Main.as_str(),
+        placed_at,
+        fired_at,
+        takeaway: order.takeaway,
+    };
+
+    let ingredients: Vec<(&str, &str, i32)> = components
+        .iter()
+        .map(|line| (line.item, line.variant, line.quantity))
+        .collect();
+    let low_stock = deduct_stock(&mut transaction, &ingredients_for(&ingredients)).await?;
+    insert_line(&mut transaction, &bundle).await?;
+    for line in &components {
+        insert_line(&mut transaction, line).await?;
+    }
+    if fired_at.is_some() {
+        let component_ids: Vec<Uuid> = components.iter().map(|line| line.id).collect();
+        queue_tickets(&mut transaction, ids, &component_ids, None, placed_at).await?;
+    }
+
+    record_audit(
+        &mut transaction,
+        request_id,
+        "order_placed",
+        serde_json::json!({
+            "order_id": bundle.id,
+            "table_no": table_no,
+            "bundle": bundle.item,
+            "quantity": quantity,
+            "components": order
+                .components
+                .iter()
+                .zip(&components)
+                .map(|(component, line)| serde_json::json!({
+                    "order_id": line.id,
+                    "slot": component.slot,
+                    "item": line.item,
+                    "variant": line.variant,
+                }))
+                .collect::<Vec<_>>(),
+            "note": note,
+            "unit_price_cents": bundle.unit_price_cents,
+            "takeaway": bundle.takeaway,
+        }),
+    )
+    .await?;
+
+    transaction.commit().await?;
+    announce_low_stock(events, low_stock);
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use crate::audit::record_audit;
+use crate::clock::Clock;
+use crate::configuration::TaxSettings;
+use crate::domain::NewPayment;
+use crate::error::DomainError;
+use crate::id_generator::IdGenerator;
+use crate::payment_provider::PaymentProvider;
+use crate::payments::{account, close_session, record_payment, refund_payment};
+use crate::request_id::RequestId;
+use crate::routes::error::domain_error_response;
+use actix_web::{web, HttpResponse};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct PaymentRequest {
+    // `cash`, `card` or `voucher`
+    tender: String,
+    amount_cents: i64,
+    tip_cents: Option<i64>,
+    // Cash handed over, change is given for anything beyond the amount and tip
+    tendered_cents: Option<i64>,
+    // The voucher's code, or the card terminal's reference when taken by hand
+    reference: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct RefundRequest {
+    // Whatever is left of the payment when not given
+    amount_cents: Option<i64>,
+}
+
+#[tracing::instrument(
+    name = "Taking a payment",
+    skip(request, pool, provider, clock, ids, timezone, tax, request_id),
+    fields(tender = %request.tender, amount_cents = %request.amount_cents)
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn post_payment(
+    session_id: web::Path<Uuid>,
+    request: web::Json<PaymentRequest>,
+    pool: web::Data<PgPool>,
+    provider: web::Data<dyn PaymentProvider>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let request = request.into_inner();
+    let payment = match NewPayment::parse(
+        &request.tender,
+        request.amount_cents,
+        request.tip_cents,
+        request.tendered_cents,
+        request.reference,
+    ) {
+        Ok(payment) => payment,
+        Err(e) => return domain_error_response(DomainError::Invalid(e)),
+    };
+
+    let payment = record_payment(
+        &pool,
+        provider.get_ref(),
+        ids.new_id(),
+        session_id.into_inner(),
+        &payment,
+        clock.now(),
+        **timezone,
+        &tax,
+        &request_id,
+    );
+    match payment.await {
+        Ok(payment) => HttpResponse::Created().json(payment),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// What a session was charged, what has been paid and what is left
+#[tracing::instrument(name = "Retrieving a session's payments", skip(pool, timezone, tax))]
+pub async fn get_payments(
+    session_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+) -> HttpResponse {
+    let account = async {
+        let mut transaction = pool.begin().await?;
+        let account = account(&mut transaction, session_id.into_inner(), **timezone, &tax).await?;
+        transaction.commit().await?;
+        Ok::<_, DomainError>(account)
+    };
+    match account.await {
+        Ok(account) => HttpResponse::Ok().json(account),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+#[tracing::instrument(
+    name = "Refunding a payment",
+    skip(request, pool, provider, clock, ids, timezone, tax, request_id)
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn post_refund(
+    payment_id: web::Path<Uuid>,
+    request: web::Json<RefundRequest>,
+    pool: web::Data<PgPool>,
+    provider: web::Data<dyn PaymentProvider>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let refund = refund_payment(
+        &pool,
+        provider.get_ref(),
+        ids.new_id(),
+        payment_id.into_inner(),
+        request.amount_cents,
+        clock.now(),
+        **timezone,
+        &tax,
+        &request_id,
+    );
+    match refund.await {
+        Ok(refund) => HttpResponse::Created().json(refund),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// Ends a settled session so the table can be seated again
+#[tracing::instrument(
+    name = "Closing a table session",
+    skip(pool, clock, timezone, tax, request_id)
+)]
+pub async fn post_close_session(
+    session_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let account = async {
+        let mut transaction = pool.begin().await?;
+        let account = close_session(
+            &mut transaction,
+            session_id.into_inner(),
+            clock.now(),
+            **timezone,
+            &tax,
+        )
+        .await?;
+        record_audit(
+            &mut transaction,
+            &request_id,
+            "session_closed",
+            serde_json::json!(account),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok::<_, DomainError>(account)
+    };
+    match account.await {
+        Ok(account) => HttpResponse::Ok().json(account),
+        Err(e) => domain_error_response(e),
+    }
+}
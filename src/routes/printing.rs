@@ -0,0 +1,45 @@
+use crate::clock::Clock;
+use crate::error::DomainError;
+use crate::printing::{print_jobs, retry_print_job, PRINT_JOB_STATUSES};
+use crate::routes::error::{database_error_response, domain_error_response};
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct PrintJobsQuery {
+    // `pending`, `printed` or `failed`, every job when not given
+    status: Option<String>,
+}
+
+// The kitchen's print queue, oldest ticket first
+#[tracing::instrument(name = "Listing print jobs", skip(query, pool))]
+pub async fn get_print_jobs(
+    query: web::Query<PrintJobsQuery>,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let status = query.status.as_deref().map(str::trim);
+    if let Some(status) = status.filter(|status| !PRINT_JOB_STATUSES.contains(status)) {
+        return domain_error_response(DomainError::Invalid(format!(
+            "{} is not a print job status, expected pending, printing, printed or failed.",
+            status
+        )));
+    }
+    match print_jobs(&pool, status).await {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+// Sends a ticket that was given up on to its printer again
+#[tracing::instrument(name = "Retrying a print job", skip(pool, clock))]
+pub async fn post_retry_print_job(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+) -> HttpResponse {
+    match retry_print_job(&pool, id.into_inner(), clock.now()).await {
+        Ok(job) => HttpResponse::Ok().json(job),
+        Err(e) => domain_error_response(e),
+    }
+}
@@ -0,0 +1,258 @@
+use crate::audit::record_audit;
+use crate::bills::{bill_for_session, session_bill};
+use crate::clock::Clock;
+use crate::configuration::{PromotionSettings, TaxSettings};
+use crate::domain::{Off, Promotion, Reward, Schedule, Target};
+use crate::error::DomainError;
+use crate::id_generator::IdGenerator;
+use crate::promotions::{
+    give_discount, lock_open_session, normalise_code, promotions, redeem_voucher, remove_promotion,
+    save_promotion, save_voucher, vouchers,
+};
+use crate::request_id::RequestId;
+use crate::routes::error::{database_error_response, domain_error_response};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct VoucherRequest {
+    // Either a percentage or an amount
+    percent_off: Option<i64>,
+    amount_off_cents: Option<i64>,
+    // Any number of parties can use it when not given
+    max_uses: Option<i32>,
+    valid_until: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PromotionRequest {
+    target: Target,
+    min_quantity: i32,
+    reward: Reward,
+    // Applies whenever the lines were ordered when not given
+    schedule: Option<Schedule>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct RedeemRequest {
+    code: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct DiscountRequest {
+    reason: String,
+    percent_off: Option<i64>,
+    amount_off_cents: Option<i64>,
+    // Needed for discounts above the approval threshold
+    manager_code: Option<String>,
+}
+
+// Creates or changes a voucher code, e.g. `PUT /admin/vouchers/TENOFF`
+#[tracing::instrument(name = "Saving a voucher", skip(request, pool))]
+pub async fn put_voucher(
+    code: web::Path<String>,
+    request: web::Json<VoucherRequest>,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let code = normalise_code(&code);
+    if code.is_empty() || code.len() > 32 {
+        return domain_error_response(DomainError::Invalid(
+            "A voucher code is between 1 and 32 characters.".to_string(),
+        ));
+    }
+    if request.max_uses.is_some_and(|max_uses| max_uses < 1) {
+        return domain_error_response(DomainError::Invalid(
+            "A voucher has to be usable at least once.".to_string(),
+        ));
+    }
+    let off = match Off::parse(request.percent_off, request.amount_off_cents) {
+        Ok(off) => off,
+        Err(e) => return domain_error_response(DomainError::Invalid(e)),
+    };
+
+    match save_voucher(&pool, &code, off, request.max_uses, request.valid_until).await {
+        Ok(voucher) => HttpResponse::Ok().json(voucher),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[tracing::instrument(name = "Listing vouchers", skip(pool))]
+pub async fn get_vouchers(pool: web::Data<PgPool>) -> HttpResponse {
+    match vouchers(&pool).await {
+        Ok(vouchers) => HttpResponse::Ok().json(vouchers),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+// Creates or changes a promotion, e.g. `PUT /admin/promotions/2-for-1 fries tuesdays`
+#[tracing::instrument(name = "Saving a promotion", skip(request, pool, clock))]
+pub async fn put_promotion(
+    name: web::Path<String>,
+    request: web::Json<PromotionRequest>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+) -> HttpResponse {
+    let request = request.into_inner();
+    let promotion = Promotion {
+        name: name.trim().to_string(),
+        target: request.target,
+        min_quantity: request.min_quantity,
+        reward: request.reward,
+        schedule: request.schedule,
+    };
+    if let Err(e) = promotion.validate() {
+        return domain_error_response(DomainError::Invalid(e));
+    }
+
+    match save_promotion(&pool, &promotion, clock.now()).await {
+        Ok(()) => HttpResponse::Ok().json(promotion),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[tracing::instrument(name = "Listing promotions", skip(pool))]
+pub async fn get_promotions(pool: web::Data<PgPool>) -> HttpResponse {
+    match promotions(&**pool).await {
+        Ok(promotions) => HttpResponse::Ok().json(promotions),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+// Ends a promotion, bills no longer get it, also those of parties still seated
+#[tracing::instrument(name = "Removing a promotion", skip(pool))]
+pub async fn delete_promotion(name: web::Path<String>, pool: web::Data<PgPool>) -> HttpResponse {
+    match remove_promotion(&pool, name.trim()).await {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => domain_error_response(DomainError::NotFound(format!(
+            "There is no promotion {}.",
+            name
+        ))),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[tracing::instrument(
+    name = "Redeeming a voucher",
+    skip(request, pool, clock, ids, request_id)
+)]
+pub async fn post_voucher(
+    session_id: web::Path<Uuid>,
+    request: web::Json<RedeemRequest>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let discount = async {
+        let mut transaction = pool.begin().await?;
+        let discount = redeem_voucher(
+            &mut transaction,
+            ids.new_id(),
+            session_id.into_inner(),
+            &request.code,
+            clock.now(),
+        )
+        .await?;
+        record_audit(
+            &mut transaction,
+            &request_id,
+            "voucher_redeemed",
+            serde_json::json!(discount),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok::<_, DomainError>(discount)
+    };
+    match discount.await {
+        Ok(discount) => HttpResponse::Created().json(discount),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+/*
+ * Staff taking something off a party's bill. What it is worth is worked out against the bill
+ * as it stands, and above the configured thresholds a manager has to give their code.
+ */
+#[tracing::instrument(
+    name = "Giving a manual discount",
+    skip(request, pool, clock, ids, timezone, tax, settings, request_id),
+    fields(reason = %request.reason)
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn post_discount(
+    session_id: web::Path<Uuid>,
+    request: web::Json<DiscountRequest>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+    settings: web::Data<PromotionSettings>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let session_id = session_id.into_inner();
+    let reason = request.reason.trim();
+    if reason.is_empty() {
+        return domain_error_response(DomainError::Invalid(
+            "A discount needs a reason.".to_string(),
+        ));
+    }
+    let off = match Off::parse(request.percent_off, request.amount_off_cents) {
+        Ok(off) => off,
+        Err(e) => return domain_error_response(DomainError::Invalid(e)),
+    };
+    let manager_approved = settings.approves(request.manager_code.as_deref());
+
+    let discount = async {
+        let mut transaction = pool.begin().await?;
+        let table_no = lock_open_session(&mut transaction, session_id).await?;
+        let bill = session_bill(&mut transaction, session_id, table_no, **timezone, &tax).await?;
+        let discount = give_discount(
+            &mut transaction,
+            ids.new_id(),
+            session_id,
+            reason,
+            off,
+            off.of(bill.discounted_cents()),
+            &settings,
+            manager_approved,
+            clock.now(),
+        )
+        .await?;
+        record_audit(
+            &mut transaction,
+            &request_id,
+            "discount_given",
+            serde_json::json!(discount),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok::<_, DomainError>(discount)
+    };
+    match discount.await {
+        Ok(discount) => HttpResponse::Created().json(discount),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// A party's bill with its discounts, what payments are taken against
+#[tracing::instrument(name = "Preparing a session's bill", skip(pool, timezone, tax))]
+pub async fn get_session_bill(
+    session_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+) -> HttpResponse {
+    let session_id = session_id.into_inner();
+    match bill_for_session(&pool, session_id, **timezone, &tax).await {
+        Ok(Some(bill)) => HttpResponse::Ok().json(bill),
+        Ok(None) => domain_error_response(DomainError::NotFound(format!(
+            "There is no session {}.",
+            session_id
+        ))),
+        Err(e) => database_error_response(&e),
+    }
+}
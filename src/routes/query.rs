@@ -1,4 +1,6 @@
-use crate::domain::DatabaseResponse;
+use crate::domain::{split_variant, DatabaseResponse};
+use crate::routes::error::{is_timeout, timeout_response};
+use actix_web::http::StatusCode;
 use actix_web::{error, Result};
 use actix_web::{web, HttpResponse};
 use derive_more::{Display, Error};
@@ -6,13 +8,39 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 #[derive(Debug, Display, Error)]
-#[display(fmt = "my error: {}", name)]
-pub struct SqlError {
-    name: &'static str,
+pub enum SqlError {
+    #[display(fmt = "my error: Sql error")]
+    Failed,
+    // The pool had no connection in time or Postgres cancelled the statement
+    #[display(fmt = "my error: Sql timeout")]
+    TimedOut,
 }
 
-// Use default implementation for `error_response()` method
-impl error::ResponseError for SqlError {}
+impl From<sqlx::Error> for SqlError {
+    fn from(e: sqlx::Error) -> Self {
+        match is_timeout(&e) {
+            true => SqlError::TimedOut,
+            false => SqlError::Failed,
+        }
+    }
+}
+
+// Timeouts are reported as a 503 with a retry hint, anything else keeps the default response
+impl error::ResponseError for SqlError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SqlError::TimedOut => StatusCode::SERVICE_UNAVAILABLE,
+            SqlError::Failed => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            SqlError::TimedOut => timeout_response(),
+            SqlError::Failed => HttpResponse::build(self.status_code()).body(self.to_string()),
+        }
+    }
+}
 
 // Handle result allowing for one place to change response handling
 fn handle_get_result(
@@ -20,7 +48,7 @@ fn handle_get_result(
 ) -> Result<HttpResponse, SqlError> {
     match res {
         Ok(items) => Ok(HttpResponse::Ok().json(items)),
-        Err(_) => Err(SqlError { name: "Sql error" }),
+        Err(e) => Err(SqlError::from(e)),
     }
 }
 
@@ -53,32 +81,36 @@ pub async fn query_table_request(
     .await
 }
 
+// `cola` matches every size of cola, `cola:large` only the large ones
 #[tracing::instrument(name = "Query with table number", skip(args, pool))]
 pub async fn query_with_item_name(
     args: web::Path<(i32, String)>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, SqlError> {
-    let result = query_item_request(&pool, &args.0, args.1.to_string()).await;
+    let (item, variant) = split_variant(&args.1);
+    let result = query_item_request(&pool, &args.0, item.to_string(), variant).await;
     handle_get_result(result)
 }
 
 #[tracing::instrument(
     name = "Retrieving all matching items from a table in the database",
-    skip(table_no, item, pool)
+    skip(table_no, item, variant, pool)
 )]
 pub async fn query_item_request(
     pool: &PgPool,
     table_no: &i32,
     item: String,
+    variant: Option<&str>,
 ) -> Result<Vec<DatabaseResponse>, sqlx::Error> {
     sqlx::query_as!(
         DatabaseResponse,
         r#"
     SELECT * FROM orders
-    WHERE table_no = $1 AND item = $2
+    WHERE table_no = $1 AND item = $2 AND ($3::TEXT IS NULL OR variant = $3)
     "#,
         table_no,
-        item
+        item,
+        variant
     )
     .fetch_all(pool)
     .await
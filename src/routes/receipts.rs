@@ -0,0 +1,58 @@
+use crate::configuration::{ReceiptSettings, TaxSettings};
+use crate::domain::{render_escpos, render_html, render_text, ReceiptFormat};
+use crate::error::DomainError;
+use crate::receipts::closed_session_receipt;
+use crate::routes::error::domain_error_response;
+use actix_web::{web, HttpResponse};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct ReceiptQuery {
+    // `text`, `html` or `escpos`, plain text when not given
+    format: Option<String>,
+    // The last party to leave the table when not given
+    session_id: Option<Uuid>,
+}
+
+#[tracing::instrument(
+    name = "Printing a receipt",
+    skip(query, pool, timezone, tax, settings),
+    fields(format = ?query.format)
+)]
+pub async fn get_receipt(
+    table_no: web::Path<i32>,
+    query: web::Query<ReceiptQuery>,
+    pool: web::Data<PgPool>,
+    timezone: web::Data<Tz>,
+    tax: web::Data<TaxSettings>,
+    settings: web::Data<ReceiptSettings>,
+) -> HttpResponse {
+    let format = match query.format.as_deref().map(ReceiptFormat::parse) {
+        None => ReceiptFormat::Text,
+        Some(Ok(format)) => format,
+        Some(Err(e)) => return domain_error_response(DomainError::Invalid(e)),
+    };
+    let receipt = match closed_session_receipt(
+        &pool,
+        table_no.into_inner(),
+        query.session_id,
+        **timezone,
+        &tax,
+    )
+    .await
+    {
+        Ok(receipt) => receipt,
+        Err(e) => return domain_error_response(e),
+    };
+
+    let body = match format {
+        ReceiptFormat::Text => render_text(&receipt, &settings).into_bytes(),
+        ReceiptFormat::Html => render_html(&receipt, &settings).into_bytes(),
+        ReceiptFormat::EscPos => render_escpos(&receipt, &settings),
+    };
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .body(body)
+}
@@ -0,0 +1,261 @@
+use crate::clock::Clock;
+use crate::configuration::ReservationSettings;
+use crate::domain::{CustomerName, NewReservation, Phone, Table, TABLES};
+use crate::error::DomainError;
+use crate::id_generator::IdGenerator;
+use crate::reservations::{
+    cancel_reservation, create_reservation, reservations_between, seat_reservation, Reservation,
+    BOOKED,
+};
+use crate::routes::error::{database_error_response, domain_error_response};
+use crate::sessions::{open_sessions, TableSession};
+use crate::waitlist::{join_waitlist, leave_waitlist, seat_from_waitlist, waiting};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct ReservationRequest {
+    party_size: i32,
+    // e.g. `2026-10-19T19:30:00+02:00`
+    starts_at: DateTime<Utc>,
+    duration_minutes: Option<i32>,
+    customer_name: String,
+    phone: String,
+    // Any table the party fits at when not given
+    table_no: Option<i32>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ReservationsQuery {
+    // A day in the restaurant's timezone, today when not given
+    date: Option<NaiveDate>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct WaitlistRequest {
+    party_size: i32,
+    customer_name: String,
+    phone: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SeatRequest {
+    table_no: i32,
+}
+
+// A table on the floor plan with whoever is at it and who booked it next
+#[derive(Serialize)]
+struct TableStatus {
+    #[serde(flatten)]
+    table: &'static Table,
+    session: Option<TableSession>,
+    next_reservation: Option<Reservation>,
+}
+
+fn turn(settings: &ReservationSettings) -> Duration {
+    Duration::minutes(settings.default_duration_minutes.into())
+}
+
+#[tracing::instrument(
+    name = "Booking a table",
+    skip(request, pool, clock, ids, settings),
+    fields(party_size = %request.party_size, starts_at = %request.starts_at)
+)]
+pub async fn post_reservation(
+    request: web::Json<ReservationRequest>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    settings: web::Data<ReservationSettings>,
+) -> HttpResponse {
+    let now = clock.now();
+    let request = request.into_inner();
+    let reservation = match NewReservation::parse(
+        request.party_size,
+        request.starts_at,
+        request.duration_minutes,
+        request.customer_name,
+        request.phone,
+        request.table_no,
+        now,
+        &settings,
+    ) {
+        Ok(reservation) => reservation,
+        Err(e) => return domain_error_response(DomainError::Invalid(e)),
+    };
+
+    match create_reservation(&pool, ids.new_id(), &reservation, now).await {
+        Ok(reservation) => HttpResponse::Created().json(reservation),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// Reservations for a day, including those already seated
+#[tracing::instrument(name = "Listing reservations", skip(query, pool, clock, timezone))]
+pub async fn get_reservations(
+    query: web::Query<ReservationsQuery>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    timezone: web::Data<Tz>,
+) -> HttpResponse {
+    let date = query
+        .date
+        .unwrap_or_else(|| clock.now().with_timezone(timezone.get_ref()).date_naive());
+    // Days start at midnight local time, whatever the clocks did that night
+    let start_of = |date: NaiveDate| -> DateTime<Utc> {
+        timezone
+            .from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+            .earliest()
+            .map(|start| start.with_timezone(&Utc))
+            .unwrap_or_else(|| date.and_time(chrono::NaiveTime::MIN).and_utc())
+    };
+    let from = start_of(date);
+    let until = start_of(date.succ_opt().unwrap_or(date));
+
+    match reservations_between(&pool, from, until).await {
+        Ok(reservations) => HttpResponse::Ok().json(reservations),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+#[tracing::instrument(name = "Cancelling a reservation", skip(pool))]
+pub async fn post_cancel_reservation(id: web::Path<Uuid>, pool: web::Data<PgPool>) -> HttpResponse {
+    match cancel_reservation(&pool, id.into_inner()).await {
+        Ok(reservation) => HttpResponse::Ok().json(reservation),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// The party has arrived, seat them at the table they booked
+#[tracing::instrument(name = "Seating a reservation", skip(pool, clock, ids))]
+pub async fn post_seat_reservation(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+) -> HttpResponse {
+    match seat_reservation(&pool, id.into_inner(), ids.new_id(), clock.now()).await {
+        Ok(session) => HttpResponse::Ok().json(session),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+#[tracing::instrument(
+    name = "Adding a party to the waitlist",
+    skip(request, pool, clock, ids, settings),
+    fields(party_size = %request.party_size)
+)]
+pub async fn post_waitlist(
+    request: web::Json<WaitlistRequest>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    settings: web::Data<ReservationSettings>,
+) -> HttpResponse {
+    let request = request.into_inner();
+    if request.party_size < 1 {
+        return domain_error_response(DomainError::Invalid(
+            "A party is at least one person.".to_string(),
+        ));
+    }
+    let contact = CustomerName::parse(request.customer_name)
+        .and_then(|name| Phone::parse(request.phone).map(|phone| (name, phone)));
+    let (customer_name, phone) = match contact {
+        Ok(contact) => contact,
+        Err(e) => return domain_error_response(DomainError::Invalid(e)),
+    };
+
+    match join_waitlist(
+        &pool,
+        ids.new_id(),
+        request.party_size,
+        &customer_name,
+        &phone,
+        clock.now(),
+        turn(&settings),
+    )
+    .await
+    {
+        Ok(entry) => HttpResponse::Created().json(entry),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+#[tracing::instrument(name = "Listing the waitlist", skip(pool))]
+pub async fn get_waitlist(pool: web::Data<PgPool>) -> HttpResponse {
+    match waiting(&pool).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => database_error_response(&e),
+    }
+}
+
+// Seat a waiting party at the table in the body, e.g. `{"table_no": 4}`
+#[tracing::instrument(
+    name = "Seating a party from the waitlist",
+    skip(request, pool, clock, ids, settings),
+    fields(table_no = %request.table_no)
+)]
+pub async fn post_seat_waitlist(
+    id: web::Path<Uuid>,
+    request: web::Json<SeatRequest>,
+    pool: web::Data<PgPool>,
+    clock: web::Data<dyn Clock>,
+    ids: web::Data<dyn IdGenerator>,
+    settings: web::Data<ReservationSettings>,
+) -> HttpResponse {
+    match seat_from_waitlist(
+        &pool,
+        id.into_inner(),
+        request.table_no,
+        ids.new_id(),
+        clock.now(),
+        turn(&settings),
+    )
+    .await
+    {
+        Ok(session) => HttpResponse::Ok().json(session),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+#[tracing::instrument(name = "Removing a party from the waitlist", skip(pool))]
+pub async fn delete_waitlist(id: web::Path<Uuid>, pool: web::Data<PgPool>) -> HttpResponse {
+    match leave_waitlist(&pool, id.into_inner()).await {
+        Ok(entry) => HttpResponse::Ok().json(entry),
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// The floor plan as the host stand sees it
+#[tracing::instrument(name = "Listing tables", skip(pool, clock))]
+pub async fn get_tables(pool: web::Data<PgPool>, clock: web::Data<dyn Clock>) -> HttpResponse {
+    let now = clock.now();
+    let sessions = match open_sessions(&pool).await {
+        Ok(sessions) => sessions,
+        Err(e) => return database_error_response(&e),
+    };
+    let upcoming = match reservations_between(&pool, now, now + Duration::days(1)).await {
+        Ok(reservations) => reservations,
+        Err(e) => return database_error_response(&e),
+    };
+
+    let tables: Vec<TableStatus> = TABLES
+        .iter()
+        .map(|table| TableStatus {
+            table,
+            session: sessions
+                .iter()
+                .find(|session| session.table_no == table.table_no)
+                .cloned(),
+            next_reservation: upcoming
+                .iter()
+                .find(|r| r.table_no == table.table_no && r.status == BOOKED)
+                .cloned(),
+        })
+        .collect();
+    HttpResponse::Ok().json(tables)
+}
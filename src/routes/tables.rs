@@ -0,0 +1,85 @@
+use crate::audit::record_audit;
+use crate::clock::Clock;
+use crate::domain::Nat;
+use crate::error::DomainError;
+use crate::events::{Event, EventBus};
+use crate::request_id::RequestId;
+use crate::routes::error::domain_error_response;
+use crate::sessions::{merge_sessions, transfer_table};
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/*
+ * Moves a party and their open orders to another table, e.g. from the bar to table 4.
+ * Any table number orders can be placed at works, the bar included.
+ */
+#[tracing::instrument(name = "Transferring a table", skip(pool, events, request_id))]
+pub async fn post_transfer(
+    tables: web::Path<(i32, i32)>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let (from_table, to_table) = tables.into_inner();
+    if let Err(e) = Nat::parse(from_table).and(Nat::parse(to_table)) {
+        return domain_error_response(DomainError::Invalid(e));
+    }
+
+    let transfer = async {
+        let mut transaction = pool.begin().await?;
+        let transfer = transfer_table(&mut transaction, from_table, to_table).await?;
+        record_audit(
+            &mut transaction,
+            &request_id,
+            "table_transferred",
+            serde_json::json!(transfer),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok::<_, DomainError>(transfer)
+    };
+    match transfer.await {
+        Ok(transfer) => {
+            let response = HttpResponse::Ok().json(&transfer);
+            events.publish(Event::TableTransferred(transfer));
+            response
+        }
+        Err(e) => domain_error_response(e),
+    }
+}
+
+// Combines the party of one session with another's when their tables are pushed together
+#[tracing::instrument(name = "Merging table sessions", skip(pool, events, clock, request_id))]
+pub async fn post_merge(
+    sessions: web::Path<(Uuid, Uuid)>,
+    pool: web::Data<PgPool>,
+    events: web::Data<EventBus>,
+    clock: web::Data<dyn Clock>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let (session_id, into_session_id) = sessions.into_inner();
+
+    let merge = async {
+        let mut transaction = pool.begin().await?;
+        let merge =
+            merge_sessions(&mut transaction, session_id, into_session_id, clock.now()).await?;
+        record_audit(
+            &mut transaction,
+            &request_id,
+            "sessions_merged",
+            serde_json::json!(merge),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok::<_, DomainError>(merge)
+    };
+    match merge.await {
+        Ok(merge) => {
+            let response = HttpResponse::Ok().json(&merge);
+            events.publish(Event::SessionsMerged(merge));
+            response
+        }
+        Err(e) => domain_error_response(e),
+    }
+}
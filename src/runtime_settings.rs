@@ -0,0 +1,151 @@
+use crate::configuration::{
+    load_configuration, ConfigurationSource, RuntimeSettings, CONFIG_FILE_EXTENSIONS,
+};
+use crate::events::{ChangeSource, Event, EventBus};
+use crate::shutdown::Shutdown;
+use crate::telemetry::set_log_level;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+/*
+ * The runtime settings currently in effect, shared by every actix worker.
+ * Readers take a cheap snapshot, a change swaps in a whole new set of settings so
+ * no request ever sees half of an update.
+ */
+pub struct RuntimeSettingsStore {
+    current: RwLock<Arc<RuntimeSettings>>,
+    events: EventBus,
+}
+
+impl RuntimeSettingsStore {
+    pub fn new(settings: RuntimeSettings, events: EventBus) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(settings)),
+            events,
+        }
+    }
+
+    pub fn get(&self) -> Arc<RuntimeSettings> {
+        // A writer never panics while holding the lock, but recover the value regardless
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /*
+     * Derive new settings from the ones in effect while holding the write lock,
+     * so two changes made at the same time cannot undo one another.
+     * Returns whether anything changed, an event is only published when it did.
+     */
+    pub fn modify<F>(&self, change: F, source: ChangeSource) -> Result<bool, String>
+    where
+        F: FnOnce(&RuntimeSettings) -> Result<RuntimeSettings, String>,
+    {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        let settings = change(&current)?;
+        settings.validate()?;
+
+        if **current == settings {
+            return Ok(false);
+        }
+        if current.log_level != settings.log_level {
+            set_log_level(&settings.log_level)?;
+        }
+        *current = Arc::new(settings.clone());
+        drop(current);
+
+        tracing::info!(
+            change_source = %source,
+            preparation_time_multiplier = settings.preparation_time_multiplier,
+            log_level = %settings.log_level,
+            "Runtime settings changed"
+        );
+        self.events
+            .publish(Event::RuntimeSettingsChanged { source, settings });
+        Ok(true)
+    }
+}
+
+/*
+ * Reload runtime settings whenever one of the configuration files is modified.
+ * Only the settings that differ from the previous version of the files are applied,
+ * so a reload keeps whatever was changed through `/admin/settings` in the meantime.
+ * Whichever changed a setting last, the files or an admin, wins.
+ */
+pub async fn watch_configuration_files(
+    store: Arc<RuntimeSettingsStore>,
+    source: ConfigurationSource,
+    interval: Duration,
+    shutdown: Shutdown,
+) {
+    let mut last_modified = modified_times(&source);
+    // What the files said last time, so a reload can tell which settings they changed
+    let mut last_loaded = load_configuration(&source.directory, &source.environment)
+        .map(|settings| settings.runtime)
+        .unwrap_or_else(|_| store.get().as_ref().clone());
+
+    loop {
+        tokio::select! {
+            _ = shutdown.triggered() => return,
+            _ = actix_web::rt::time::sleep(interval) => {},
+        }
+
+        let modified = modified_times(&source);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        // A half written or invalid file keeps the settings already in effect
+        match load_configuration(&source.directory, &source.environment) {
+            Ok(settings) => {
+                let loaded = settings.runtime;
+                let change = |current: &RuntimeSettings| {
+                    Ok(changed_in_files(current, &last_loaded, &loaded))
+                };
+                match store.modify(change, ChangeSource::File) {
+                    Ok(_) => last_loaded = loaded,
+                    Err(e) => tracing::error!("Rejected reloaded runtime settings: {}", e),
+                }
+            }
+            Err(e) => tracing::error!("Failed to reload the configuration: {}", e),
+        }
+    }
+}
+
+// The settings in effect, with every setting the files changed from `before` to `after` applied
+fn changed_in_files(
+    current: &RuntimeSettings,
+    before: &RuntimeSettings,
+    after: &RuntimeSettings,
+) -> RuntimeSettings {
+    let mut settings = current.clone();
+    if before.preparation_time_multiplier != after.preparation_time_multiplier {
+        settings.preparation_time_multiplier = after.preparation_time_multiplier;
+    }
+    if before.log_level != after.log_level {
+        settings.log_level = after.log_level.clone();
+    }
+    if before.rate_limits != after.rate_limits {
+        settings.rate_limits = after.rate_limits.clone();
+    }
+    settings
+}
+
+// Modification time of every file that could make up the configuration, missing files included
+fn modified_times(source: &ConfigurationSource) -> Vec<Option<SystemTime>> {
+    ["base", source.environment.as_str()]
+        .iter()
+        .flat_map(|name| {
+            CONFIG_FILE_EXTENSIONS
+                .iter()
+                .map(move |extension| format!("{}.{}", name, extension))
+        })
+        .map(|file| {
+            std::fs::metadata(source.directory.join(file))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
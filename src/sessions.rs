@@ -0,0 +1,318 @@
+use crate::error::{DomainError, UNIQUE_VIOLATION};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// A party sitting at a table, from being seated until the table is closed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TableSession {
+    pub id: Uuid,
+    pub table_no: i32,
+    pub party_size: i32,
+    // The booking the party came in on, `None` for walk-ins
+    pub reservation_id: Option<Uuid>,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    // Set when the party joined another table's party
+    pub merged_into: Option<Uuid>,
+}
+
+/*
+ * Seats a party at a table. The database only allows one open session per table,
+ * so two hosts seating the same table at once cannot both succeed.
+ */
+#[tracing::instrument(name = "Opening a table session", skip(transaction))]
+pub async fn open_session(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    table_no: i32,
+    party_size: i32,
+    reservation_id: Option<Uuid>,
+    opened_at: DateTime<Utc>,
+) -> Result<TableSession, DomainError> {
+    let session = TableSession {
+        id,
+        table_no,
+        party_size,
+        reservation_id,
+        opened_at,
+        closed_at: None,
+        merged_into: None,
+    };
+    sqlx::query!(
+        r#"
+    INSERT INTO table_sessions (id, table_no, party_size, reservation_id, opened_at)
+    VALUES ($1, $2, $3, $4, $5)
+    "#,
+        session.id,
+        session.table_no,
+        session.party_size,
+        session.reservation_id,
+        session.opened_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some(UNIQUE_VIOLATION) => {
+            DomainError::Conflict {
+                code: "table_occupied",
+                message: format!("Table {} already has a party at it.", table_no),
+            }
+        }
+        _ => {
+            tracing::error!("Failed to execute query: {:?}", e);
+            DomainError::Database(e)
+        }
+    })?;
+    Ok(session)
+}
+
+#[tracing::instrument(name = "Retrieving open table sessions from the database", skip(pool))]
+pub async fn open_sessions(pool: &PgPool) -> Result<Vec<TableSession>, sqlx::Error> {
+    sqlx::query_as!(
+        TableSession,
+        r#"
+    SELECT id, table_no, party_size, reservation_id, opened_at, closed_at, merged_into
+    FROM table_sessions
+    WHERE closed_at IS NULL
+    ORDER BY table_no
+    "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// The session of whoever is seated at a table right now
+pub async fn open_session_id(
+    transaction: &mut Transaction<'_, Postgres>,
+    table_no: i32,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let session = sqlx::query!(
+        "SELECT id FROM table_sessions WHERE table_no = $1 AND closed_at IS NULL",
+        table_no
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    Ok(session.map(|session| session.id))
+}
+
+// What a transfer moved, for the audit trail and subscribers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    pub from_table: i32,
+    pub to_table: i32,
+    // The party's session, which moves with them, `None` when nobody was seated
+    pub session_id: Option<Uuid>,
+    pub order_ids: Vec<Uuid>,
+}
+
+/*
+ * Orders still to be paid for at a table: those of its open session, or the ones placed
+ * without a session when nobody is seated. Sessionless orders left by earlier parties stay
+ * where they are when a seated party moves.
+ */
+async fn move_open_orders(
+    transaction: &mut Transaction<'_, Postgres>,
+    from_table: i32,
+    session_id: Option<Uuid>,
+    to_table: i32,
+    to_session_id: Option<Uuid>,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let moved = sqlx::query!(
+        r#"
+    UPDATE orders SET table_no = $3, session_id = $4
+    WHERE table_no = $1 AND session_id IS NOT DISTINCT FROM $2
+    RETURNING id
+    "#,
+        from_table,
+        session_id,
+        to_table,
+        to_session_id
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    Ok(moved.into_iter().map(|row| row.id).collect())
+}
+
+async fn open_session_for_update(
+    transaction: &mut Transaction<'_, Postgres>,
+    table_no: i32,
+) -> Result<Option<TableSession>, sqlx::Error> {
+    sqlx::query_as!(
+        TableSession,
+        r#"
+    SELECT id, table_no, party_size, reservation_id, opened_at, closed_at, merged_into
+    FROM table_sessions
+    WHERE table_no = $1 AND closed_at IS NULL
+    FOR UPDATE
+    "#,
+        table_no
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+}
+
+/*
+ * Moves a party and everything they have ordered but not paid for to another table,
+ * e.g. from the bar to a table. The destination has to be free, parties already seated
+ * together are merged instead. Audit records are written in `transaction` too, so it is
+ * left for the caller to commit.
+ */
+#[tracing::instrument(name = "Transferring a table", skip(transaction))]
+pub async fn transfer_table(
+    transaction: &mut Transaction<'_, Postgres>,
+    from_table: i32,
+    to_table: i32,
+) -> Result<Transfer, DomainError> {
+    if from_table == to_table {
+        return Err(DomainError::Invalid(
+            "A table cannot be transferred to itself.".to_string(),
+        ));
+    }
+
+    let session = open_session_for_update(transaction, from_table).await?;
+    if open_session_for_update(transaction, to_table)
+        .await?
+        .is_some()
+    {
+        return Err(table_occupied(to_table));
+    }
+    if let Some(session) = &session {
+        sqlx::query!(
+            "UPDATE table_sessions SET table_no = $2 WHERE id = $1",
+            session.id,
+            to_table
+        )
+        .execute(&mut **transaction)
+        .await
+        .map_err(|e| match &e {
+            // Someone sat down at the destination meanwhile
+            sqlx::Error::Database(db) if db.code().as_deref() == Some(UNIQUE_VIOLATION) => {
+                table_occupied(to_table)
+            }
+            _ => DomainError::Database(e),
+        })?;
+    }
+    let session_id = session.map(|session| session.id);
+    let order_ids =
+        move_open_orders(transaction, from_table, session_id, to_table, session_id).await?;
+
+    if session_id.is_none() && order_ids.is_empty() {
+        return Err(DomainError::NotFound(format!(
+            "Table {} has nothing to transfer.",
+            from_table
+        )));
+    }
+    Ok(Transfer {
+        from_table,
+        to_table,
+        session_id,
+        order_ids,
+    })
+}
+
+fn table_occupied(table_no: i32) -> DomainError {
+    DomainError::Conflict {
+        code: "table_occupied",
+        message: format!(
+            "Table {} already has a party at it, merge the sessions instead.",
+            table_no
+        ),
+    }
+}
+
+// What a merge combined, for the audit trail and subscribers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    pub session_id: Uuid,
+    pub into_session_id: Uuid,
+    pub table_no: i32,
+    pub party_size: i32,
+    pub order_ids: Vec<Uuid>,
+}
+
+/*
+ * Combines two parties when their tables are pushed together. The party of `session_id`
+ * joins the one of `into_session_id`, taking their orders along, and their session is closed.
+ * Like `transfer_table` the caller commits.
+ */
+#[tracing::instrument(name = "Merging table sessions", skip(transaction))]
+pub async fn merge_sessions(
+    transaction: &mut Transaction<'_, Postgres>,
+    session_id: Uuid,
+    into_session_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Merge, DomainError> {
+    if session_id == into_session_id {
+        return Err(DomainError::Invalid(
+            "A session cannot be merged into itself.".to_string(),
+        ));
+    }
+
+    // Both rows are locked in id order, so two merges of the same sessions cannot deadlock
+    let sessions = sqlx::query_as!(
+        TableSession,
+        r#"
+    SELECT id, table_no, party_size, reservation_id, opened_at, closed_at, merged_into
+    FROM table_sessions
+    WHERE id = ANY($1)
+    ORDER BY id
+    FOR UPDATE
+    "#,
+        &[session_id, into_session_id][..]
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+    let find = |id: Uuid| -> Result<&TableSession, DomainError> {
+        let session = sessions
+            .iter()
+            .find(|session| session.id == id)
+            .ok_or_else(|| DomainError::NotFound(format!("There is no session {}.", id)))?;
+        match session.closed_at {
+            None => Ok(session),
+            Some(_) => Err(DomainError::Conflict {
+                code: "session_closed",
+                message: format!("Session {} is already closed.", id),
+            }),
+        }
+    };
+    let (from, into) = (find(session_id)?, find(into_session_id)?);
+
+    let order_ids = move_open_orders(
+        transaction,
+        from.table_no,
+        Some(from.id),
+        into.table_no,
+        Some(into.id),
+    )
+    .await?;
+    let party_size = from.party_size + into.party_size;
+    sqlx::query!(
+        "UPDATE table_sessions SET party_size = $2 WHERE id = $1",
+        into.id,
+        party_size
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        "UPDATE table_sessions SET closed_at = $2, merged_into = $3 WHERE id = $1",
+        from.id,
+        now,
+        into.id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(Merge {
+        session_id: from.id,
+        into_session_id: into.id,
+        table_no: into.table_no,
+        party_size,
+        order_ids,
+    })
+}
@@ -1,66 +1,423 @@
-use crate::configuration::DatabaseSettings;
+use crate::auth::StaffTokens;
+use crate::clock::{Clock, SystemClock};
 use crate::configuration::Settings;
+use crate::configuration::{
+    DatabaseSettings, PromotionSettings, RateLimitBackend, ReceiptSettings, ReservationSettings,
+    TaxSettings,
+};
+use crate::events::EventBus;
+use crate::id_generator::{IdGenerator, RandomIds};
+use crate::metrics::{track_requests, Metrics};
+use crate::migration::{pending_migrations, run_migrations};
+use crate::payment_provider::{OfflineProvider, PaymentProvider};
+use crate::printer::{configured_printer, PrinterSink};
+use crate::printing::run_print_queue;
+use crate::rate_limit::{rate_limit, run_bucket_expiry, RateLimiter};
+use crate::request_id::{echo_request_id, RequestIdRootSpanBuilder};
+use crate::rng::{Rng, SystemRng};
 use crate::routes::{
-    delete_with_id, delete_with_item_name, health_check, order, query_all, query_with_id,
-    query_with_item_name, query_with_table_number,
+    bill, delete_promotion, delete_waitlist, delete_with_id, delete_with_item_name, export_metrics,
+    get_inventory, get_kitchen, get_payments, get_print_jobs, get_promotions, get_receipt,
+    get_reservations, get_runtime_settings, get_session_bill, get_tables, get_vouchers,
+    get_waitlist, health_check, health_live, health_ready, menu, order, order_bundle,
+    post_cancel_reservation, post_close_session, post_discount, post_fire, post_merge,
+    post_payment, post_refund, post_reservation, post_retry_print_job, post_seat_reservation,
+    post_seat_waitlist, post_transfer, post_voucher, post_waitlist, put_promotion, put_voucher,
+    query_all, query_with_id, query_with_item_name, query_with_table_number, stream_events,
+    update_availability, update_inventory, update_runtime_settings, ReadinessTimeout,
 };
-use actix_web::dev::Server;
+use crate::runtime_settings::{watch_configuration_files, RuntimeSettingsStore};
+use crate::shutdown::{shutdown_signal, Shutdown};
+use actix_web::dev::{Server, ServerHandle};
+use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
+use chrono_tz::Tz;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing_actix_web::TracingLogger;
 
 // A new type to hold the newly built server and its port
 pub struct Application {
     port: u16,
     server: Server,
+    // Serves `/metrics` when it is kept off the public port
+    admin: Option<(u16, Server)>,
+    db_pool: PgPool,
+    shutdown: Shutdown,
+    drain_delay: Duration,
+}
+
+/*
+ * Where the time, new ids and random choices come from, who takes card payments and where
+ * kitchen tickets print. Tests swap in fixed, seeded or mock ones so what the application
+ * does can be asserted exactly.
+ */
+#[derive(Clone)]
+pub struct Dependencies {
+    pub clock: Arc<dyn Clock>,
+    pub ids: Arc<dyn IdGenerator>,
+    pub rng: Arc<dyn Rng>,
+    pub payments: Arc<dyn PaymentProvider>,
+    // The printers of the `printing` settings when `None`
+    pub printer: Option<Arc<dyn PrinterSink>>,
+}
+
+impl Default for Dependencies {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            ids: Arc::new(RandomIds),
+            rng: Arc::new(SystemRng),
+            payments: Arc::new(OfflineProvider),
+            printer: None,
+        }
+    }
 }
 
 impl Application {
     pub async fn build(configuration: Settings) -> Result<Self, std::io::Error> {
+        Self::build_with(configuration, Dependencies::default()).await
+    }
+
+    pub async fn build_with(
+        configuration: Settings,
+        dependencies: Dependencies,
+    ) -> Result<Self, std::io::Error> {
         let connection_pool = get_connection_pool(&configuration.database);
 
+        if configuration.database.migrate_on_startup {
+            run_migrations(&connection_pool)
+                .await
+                .map_err(std::io::Error::other)?;
+        }
+        check_schema_version(&connection_pool).await?;
+
         let address = format!(
             "{}:{}",
             configuration.application.host, configuration.application.port
         );
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr().unwrap().port();
-        let server = run(listener, connection_pool)?;
+        let readiness_timeout =
+            Duration::from_millis(configuration.application.readiness_timeout_milliseconds);
+        let shutdown = Shutdown::new();
+        let shutdown_timeout =
+            Duration::from_secs(configuration.application.shutdown_timeout_seconds);
+        let events = EventBus::new();
+        let runtime_settings = web::Data::new(RuntimeSettingsStore::new(
+            configuration.runtime.clone(),
+            events.clone(),
+        ));
+
+        // Tickets are sent in the background, so an order never waits for a printer
+        if let Some(printer) = dependencies
+            .printer
+            .clone()
+            .or_else(|| configured_printer(&configuration.printing))
+        {
+            tokio::spawn(run_print_queue(
+                connection_pool.clone(),
+                printer,
+                dependencies.clock.clone(),
+                configuration.application.timezone,
+                configuration.printing.clone(),
+                shutdown.clone(),
+            ));
+        }
+
+        let state = AppState {
+            db_pool: web::Data::new(connection_pool.clone()),
+            readiness_timeout: web::Data::new(ReadinessTimeout(readiness_timeout)),
+            metrics: web::Data::new(Metrics::new().map_err(std::io::Error::other)?),
+            shutdown: web::Data::new(shutdown.clone()),
+            runtime_settings: runtime_settings.clone(),
+            events: web::Data::new(events),
+            clock: web::Data::from(dependencies.clock),
+            ids: web::Data::from(dependencies.ids),
+            rng: web::Data::from(dependencies.rng),
+            payment_provider: web::Data::from(dependencies.payments),
+            timezone: web::Data::new(configuration.application.timezone),
+            reservation_settings: web::Data::new(configuration.reservations.clone()),
+            promotion_settings: web::Data::new(configuration.promotions.clone()),
+            tax_settings: web::Data::new(configuration.tax.clone()),
+            receipt_settings: web::Data::new(configuration.receipts.clone()),
+            rate_limiter: web::Data::new(RateLimiter::new(
+                &configuration.rate_limiting,
+                connection_pool.clone(),
+            )),
+            staff_tokens: web::Data::new(StaffTokens::new(&configuration.auth)),
+        };
+
+        if configuration.rate_limiting.backend == RateLimitBackend::Postgres {
+            tokio::spawn(run_bucket_expiry(connection_pool.clone(), shutdown.clone()));
+        }
+
+        if let Some(source) = configuration.source.clone() {
+            tokio::spawn(watch_configuration_files(
+                runtime_settings.into_inner(),
+                source,
+                Duration::from_millis(
+                    configuration
+                        .application
+                        .settings_reload_interval_milliseconds,
+                ),
+                shutdown.clone(),
+            ));
+        }
 
-        Ok(Self { port, server })
+        let admin = match configuration.application.admin_port {
+            Some(admin_port) => {
+                let address = format!("{}:{}", configuration.application.host, admin_port);
+                let listener = TcpListener::bind(address)?;
+                let admin_port = listener.local_addr().unwrap().port();
+                let server = run_admin(listener, state.clone(), shutdown_timeout)?;
+                Some((admin_port, server))
+            }
+            None => None,
+        };
+
+        let server = run(listener, state, admin.is_none(), shutdown_timeout)?;
+
+        Ok(Self {
+            port,
+            server,
+            admin,
+            db_pool: connection_pool,
+            shutdown,
+            drain_delay: Duration::from_millis(
+                configuration.application.shutdown_drain_delay_milliseconds,
+            ),
+        })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    pub fn admin_port(&self) -> Option<u16> {
+        self.admin.as_ref().map(|(port, _)| *port)
+    }
+
+    // Triggering it shuts the application down as if it had received SIGTERM
+    pub fn shutdown(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
     // A more expressive name that makes it clear that
     // this function only returns when the application is stopped.
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.server.await
+        let handles: Vec<ServerHandle> = std::iter::once(self.server.handle())
+            .chain(self.admin.as_ref().map(|(_, admin)| admin.handle()))
+            .collect();
+        let shutdown = self.shutdown.clone();
+        let drain_delay = self.drain_delay;
+
+        /*
+         * Shutdown happens in stages: readiness fails first, then after the drain delay
+         * the servers stop accepting connections and wait for in-flight requests to finish,
+         * up to the configured shutdown timeout.
+         */
+        let stop = async move {
+            tokio::select! {
+                _ = shutdown_signal() => shutdown.trigger(),
+                _ = shutdown.triggered() => {},
+            }
+            tracing::info!("Shutting down, no longer ready for traffic");
+            actix_web::rt::time::sleep(drain_delay).await;
+            tracing::info!("Draining in-flight requests");
+            for handle in handles {
+                handle.stop(true).await;
+            }
+        };
+
+        let serve = async move {
+            match self.admin {
+                Some((_, admin)) => futures::future::try_join(self.server, admin)
+                    .await
+                    .map(|_| ()),
+                None => self.server.await,
+            }
+        };
+        tokio::pin!(serve);
+
+        // The servers only return early if they fail, otherwise they run until stopped
+        let result = tokio::select! {
+            result = &mut serve => result,
+            _ = stop => serve.await,
+        };
+
+        // Every request has finished, so no connection is in use any more
+        self.db_pool.close().await;
+        tracing::info!("Shutdown complete");
+        result
+    }
+}
+
+// Refuse to serve when the database is missing migrations this binary depends on,
+// otherwise every request touching the missing schema would fail with a 500
+async fn check_schema_version(pool: &PgPool) -> Result<(), std::io::Error> {
+    let pending = pending_migrations(pool)
+        .await
+        .map_err(std::io::Error::other)?;
+
+    if pending.is_empty() {
+        Ok(())
+    } else {
+        let versions: Vec<String> = pending.iter().map(|m| m.version.to_string()).collect();
+        Err(std::io::Error::other(format!(
+            "The database schema is behind this binary, pending migrations: {}. Run `restaurant migrate` or enable `database.migrate_on_startup`.",
+            versions.join(", ")
+        )))
     }
 }
 
 pub fn get_connection_pool(configuration: &DatabaseSettings) -> PgPool {
-    PgPoolOptions::new().connect_lazy_with(configuration.with_db())
+    PgPoolOptions::new()
+        .max_connections(configuration.max_connections)
+        .min_connections(configuration.min_connections)
+        .acquire_timeout(Duration::from_millis(
+            configuration.acquire_timeout_milliseconds,
+        ))
+        .idle_timeout(configuration.idle_timeout_seconds.map(Duration::from_secs))
+        .max_lifetime(configuration.max_lifetime_seconds.map(Duration::from_secs))
+        .connect_lazy_with(configuration.with_db())
+}
+
+/*
+ * Everything handlers can take as `web::Data`, shared by every worker of both servers.
+ * Each item is registered on its own so handlers only ask for what they need.
+ */
+#[derive(Clone)]
+pub struct AppState {
+    pub db_pool: web::Data<PgPool>,
+    pub readiness_timeout: web::Data<ReadinessTimeout>,
+    pub metrics: web::Data<Metrics>,
+    pub shutdown: web::Data<Shutdown>,
+    pub runtime_settings: web::Data<RuntimeSettingsStore>,
+    pub events: web::Data<EventBus>,
+    pub clock: web::Data<dyn Clock>,
+    pub ids: web::Data<dyn IdGenerator>,
+    pub rng: web::Data<dyn Rng>,
+    pub payment_provider: web::Data<dyn PaymentProvider>,
+    pub timezone: web::Data<Tz>,
+    pub reservation_settings: web::Data<ReservationSettings>,
+    pub promotion_settings: web::Data<PromotionSettings>,
+    pub tax_settings: web::Data<TaxSettings>,
+    pub receipt_settings: web::Data<ReceiptSettings>,
+    pub rate_limiter: web::Data<RateLimiter>,
+    pub staff_tokens: web::Data<StaffTokens>,
+}
+
+impl AppState {
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.app_data(self.db_pool.clone())
+            .app_data(self.readiness_timeout.clone())
+            .app_data(self.metrics.clone())
+            .app_data(self.shutdown.clone())
+            .app_data(self.runtime_settings.clone())
+            .app_data(self.events.clone())
+            .app_data(self.clock.clone())
+            .app_data(self.ids.clone())
+            .app_data(self.rng.clone())
+            .app_data(self.payment_provider.clone())
+            .app_data(self.timezone.clone())
+            .app_data(self.reservation_settings.clone())
+            .app_data(self.promotion_settings.clone())
+            .app_data(self.tax_settings.clone())
+            .app_data(self.receipt_settings.clone())
+            .app_data(self.rate_limiter.clone())
+            .app_data(self.staff_tokens.clone());
+    }
 }
 
-pub fn run(listener: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Error> {
-    // Wrap the pool using web::Data, which boils down to an Arc smart pointer
-    let db_pool = web::Data::new(db_pool);
+// Operational endpoints, served on the admin port when there is one
+fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(export_metrics))
+        // view and change runtime settings
+        .route("/admin/settings", web::get().to(get_runtime_settings))
+        .route("/admin/settings", web::patch().to(update_runtime_settings))
+        // view and set stock levels
+        .route("/admin/inventory", web::get().to(get_inventory))
+        .route(
+            "/admin/inventory/{ingredient}",
+            web::put().to(update_inventory),
+        )
+        // 86 an item or put it back on
+        .route(
+            "/admin/availability/{item}",
+            web::put().to(update_availability),
+        )
+        // create and list voucher codes
+        .route("/admin/vouchers", web::get().to(get_vouchers))
+        .route("/admin/vouchers/{code}", web::put().to(put_voucher))
+        // set up, list and end promotions
+        .route("/admin/promotions", web::get().to(get_promotions))
+        .route("/admin/promotions/{name}", web::put().to(put_promotion))
+        .route(
+            "/admin/promotions/{name}",
+            web::delete().to(delete_promotion),
+        )
+        // kitchen tickets waiting for or sent to their printers
+        .route("/admin/print_jobs", web::get().to(get_print_jobs))
+        .route(
+            "/admin/print_jobs/{id}/retry",
+            web::post().to(post_retry_print_job),
+        )
+        // server-sent events, e.g. runtime settings changes
+        .route("/admin/events", web::get().to(stream_events));
+}
+
+// Serves operational endpoints on their own port so they need not be exposed publicly
+pub fn run_admin(
+    listener: TcpListener,
+    state: AppState,
+    shutdown_timeout: Duration,
+) -> Result<Server, std::io::Error> {
+    let server = HttpServer::new(move || {
+        App::new()
+            .wrap(TracingLogger::<RequestIdRootSpanBuilder>::new())
+            .configure(admin_routes)
+            .configure(|cfg| state.register(cfg))
+    })
+    // Signals are handled by `Application::run_until_stopped`
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout.as_secs())
+    .listen(listener)?
+    .run();
+    Ok(server)
+}
+
+pub fn run(
+    listener: TcpListener,
+    state: AppState,
+    serve_admin: bool,
+    shutdown_timeout: Duration,
+) -> Result<Server, std::io::Error> {
     // Capture `connection` from the surrounding environment
     // move converts any variables captured by reference or mutable reference
     // to variables captured by value.a
     let server = HttpServer::new(move || {
-        App::new()
+        let app = App::new()
             // Middlewares are added using the `wrap` method on `App`
-            .wrap(TracingLogger::default())
+            .wrap(from_fn(rate_limit))
+            .wrap(from_fn(echo_request_id))
+            .wrap(from_fn(track_requests))
+            .wrap(TracingLogger::<RequestIdRootSpanBuilder>::new())
             // health check route
             .route("/health_check", web::get().to(health_check))
+            // the process is running
+            .route("/health/live", web::get().to(health_live))
+            // the process can serve traffic
+            .route("/health/ready", web::get().to(health_ready))
+            // the menu, with what can be ordered right now
+            .route("/menu", web::get().to(menu))
             // place an order
             .route("/order", web::post().to(order))
+            // place a bundle, which expands into its components
+            .route("/order_bundle", web::post().to(order_bundle))
             // query for all items in the database
             .route("/query_all", web::get().to(query_all))
             // query a specific item using unique id
@@ -75,6 +432,8 @@ pub fn run(listener: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Er
                 "/query_table/{table_no}",
                 web::get().to(query_with_table_number),
             )
+            // the bill for a table, including modifiers
+            .route("/bill/{table_no}", web::get().to(bill))
             // delete item using unique id
             .route("/delete/{id}", web::delete().to(delete_with_id))
             // delete all occurences of an item from a specified table
@@ -82,9 +441,62 @@ pub fn run(listener: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Er
                 "/delete_item/{table_no}/{item}",
                 web::delete().to(delete_with_item_name),
             )
-            // Get a pointer copy and attach it to the application state
-            .app_data(db_pool.clone())
+            // book a table, or list a day's bookings
+            .route("/reservations", web::post().to(post_reservation))
+            .route("/reservations", web::get().to(get_reservations))
+            .route(
+                "/reservations/{id}/cancel",
+                web::post().to(post_cancel_reservation),
+            )
+            // the party has arrived, open a session at their table
+            .route(
+                "/reservations/{id}/seat",
+                web::post().to(post_seat_reservation),
+            )
+            // walk-ins waiting for a table
+            .route("/waitlist", web::post().to(post_waitlist))
+            .route("/waitlist", web::get().to(get_waitlist))
+            .route("/waitlist/{id}/seat", web::post().to(post_seat_waitlist))
+            .route("/waitlist/{id}", web::delete().to(delete_waitlist))
+            // every table with its open session and next booking
+            .route("/tables", web::get().to(get_tables))
+            // move a party and their open orders to another table
+            .route(
+                "/tables/{from}/transfer/{to}",
+                web::post().to(post_transfer),
+            )
+            // the last party's receipt, as text, HTML or for a thermal printer
+            .route("/tables/{table_no}/receipt", web::get().to(get_receipt))
+            // combine two parties whose tables were pushed together
+            .route("/sessions/{id}/merge/{into}", web::post().to(post_merge))
+            // send a course that was held back to the kitchen
+            .route("/sessions/{id}/fire/{course}", web::post().to(post_fire))
+            // what the kitchen is cooking
+            .route("/kitchen", web::get().to(get_kitchen))
+            // take and list payments towards a session's bill
+            .route("/sessions/{id}/payments", web::post().to(post_payment))
+            .route("/sessions/{id}/payments", web::get().to(get_payments))
+            // give money back for a payment
+            .route("/payments/{id}/refund", web::post().to(post_refund))
+            // end a settled session
+            .route("/sessions/{id}/close", web::post().to(post_close_session))
+            // a party's bill with its promotions, vouchers and discounts
+            .route("/sessions/{id}/bill", web::get().to(get_session_bill))
+            .route("/sessions/{id}/vouchers", web::post().to(post_voucher))
+            .route("/sessions/{id}/discounts", web::post().to(post_discount))
+            // Get a pointer copy of the shared state and attach it to the application
+            .configure(|cfg| state.register(cfg));
+
+        // expose operational endpoints here unless there is a separate admin port
+        if serve_admin {
+            app.configure(admin_routes)
+        } else {
+            app
+        }
     })
+    // Signals are handled by `Application::run_until_stopped`
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout.as_secs())
     .listen(listener)?
     .run();
     //Now run server instead of await
@@ -1,26 +1,269 @@
+use crate::configuration::OtlpSettings;
+use futures::future::BoxFuture;
+use opentelemetry::trace::{TraceError, TracerProvider as _};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::{Arc, OnceLock};
 use tracing::subscriber::set_global_default;
 use tracing::Subscriber;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
 use tracing_subscriber::fmt::MakeWriter;
-use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Registry};
+use tracing_subscriber::{layer::SubscriberExt, reload, EnvFilter, Registry};
+
+// Lets the log level be changed after the subscriber has been installed
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
 
 pub fn get_user<Sink>(
     name: String,
     env_filter: String,
     // A function that returns a sink - a place we can write log to
     sink: Sink,
+    // Spans are additionally exported through this provider when one is given
+    tracer_provider: Option<&TracerProvider>,
 ) -> impl Subscriber + Sync + Send
 where
     Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
 {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    // Only the first subscriber is ever installed, so its handle is the one to keep
+    let _ = LOG_FILTER.set(handle);
+    let otel_layer = tracer_provider
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(name.clone())));
     let formatting_layer = BunyanFormattingLayer::new(name, sink);
     Registry::default()
         .with(env_filter)
         .with(JsonStorageLayer)
         .with(formatting_layer)
+        .with(otel_layer)
+}
+
+// Replace the log filter, e.g. with `debug` while investigating an issue mid-service
+pub fn set_log_level(directives: &str) -> Result<(), String> {
+    let env_filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+    match LOG_FILTER.get() {
+        Some(handle) => handle.reload(env_filter).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Build a provider exporting spans to an OTLP collector over HTTP.
+///
+/// Spans are batched on a background thread, so the provider must be flushed
+/// before the process exits to avoid losing the last batch. Attributes named in
+/// `redacted_fields` are redacted before they are sent, as they are in the logs.
+pub fn get_tracer_provider(
+    settings: &OtlpSettings,
+    redacted_fields: &[String],
+) -> Result<TracerProvider, TraceError> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(format!(
+            "{}/v1/traces",
+            settings.endpoint.trim_end_matches('/')
+        ))
+        .build_span_exporter()?;
+
+    // Follow the sampling decision of the caller when a request carries a `traceparent`
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        settings.sampling_ratio,
+    )));
+    let config = opentelemetry_sdk::trace::config()
+        .with_sampler(sampler)
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            settings.service_name.clone(),
+        )]));
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(
+            RedactingExporter::new(exporter, redacted_fields),
+            runtime::TokioCurrentThread,
+        )
+        .with_config(config)
+        .build())
+}
+
+// Placeholder written instead of the value of a redacted field
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A sink that redacts sensitive fields from every log line before it is written.
+///
+/// Bunyan log lines are JSON objects, so the values of any key on the denylist are
+/// replaced no matter which span or event recorded them. A key matches when it is
+/// equal to a denylisted field, or ends with it after a `.` or `_` separator so that
+/// e.g. `order_customer_name` is caught by `customer_name`.
+pub struct Redact<Sink> {
+    sink: Sink,
+    denylist: Arc<HashSet<String>>,
+}
+
+impl<Sink> Redact<Sink> {
+    pub fn new(sink: Sink, denylist: &[String]) -> Self {
+        Self {
+            sink,
+            denylist: lowercase(denylist),
+        }
+    }
+}
+
+fn lowercase(denylist: &[String]) -> Arc<HashSet<String>> {
+    Arc::new(denylist.iter().map(|f| f.to_lowercase()).collect())
+}
+
+impl<'a, Sink> MakeWriter<'a> for Redact<Sink>
+where
+    Sink: MakeWriter<'a>,
+{
+    type Writer = RedactingWriter<Sink::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.sink.make_writer(),
+            denylist: self.denylist.clone(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+// Buffers a log line until it is complete, so it can be redacted as a whole
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+    denylist: Arc<HashSet<String>>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    fn write_line(&mut self, line: &[u8]) -> std::io::Result<()> {
+        let redacted = redact_line(line, &self.denylist);
+        self.inner.write_all(&redacted)
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.write_line(&line)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.write_line(&line)?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<W> {
+    fn drop(&mut self) {
+        // A writer is created per log line, make sure nothing is left behind
+        let _ = self.flush();
+    }
+}
+
+// Lines that aren't JSON are passed through untouched
+fn redact_line(line: &[u8], denylist: &HashSet<String>) -> Vec<u8> {
+    let trailing_newline = line.ends_with(b"\n");
+    match serde_json::from_slice::<serde_json::Value>(line) {
+        Ok(mut value) => {
+            redact_value(&mut value, denylist);
+            let mut redacted = serde_json::to_vec(&value).unwrap_or_else(|_| line.to_vec());
+            if trailing_newline {
+                redacted.push(b'\n');
+            }
+            redacted
+        }
+        Err(_) => line.to_vec(),
+    }
+}
+
+fn redact_value(value: &mut serde_json::Value, denylist: &HashSet<String>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                if is_denylisted(key, denylist) {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value, denylist);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                redact_value(value, denylist);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// An exporter that redacts span and event attributes before they leave the process.
+///
+/// tracing-opentelemetry turns span fields into attributes, which never go through the
+/// log sink, so they are matched against the same denylist here.
+#[derive(Debug)]
+pub struct RedactingExporter<E> {
+    inner: E,
+    denylist: Arc<HashSet<String>>,
+}
+
+impl<E> RedactingExporter<E> {
+    pub fn new(inner: E, denylist: &[String]) -> Self {
+        Self {
+            inner,
+            denylist: lowercase(denylist),
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for RedactingExporter<E> {
+    fn export(&mut self, mut batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        for span in &mut batch {
+            redact_attributes(&mut span.attributes, &self.denylist);
+            for event in span.events.events.iter_mut() {
+                redact_attributes(&mut event.attributes, &self.denylist);
+            }
+        }
+        self.inner.export(batch)
+    }
+
+    fn shutdown(&mut self) {
+        self.inner.shutdown()
+    }
+
+    fn force_flush(&mut self) -> BoxFuture<'static, ExportResult> {
+        self.inner.force_flush()
+    }
+}
+
+fn redact_attributes(attributes: &mut [KeyValue], denylist: &HashSet<String>) {
+    for attribute in attributes {
+        if is_denylisted(attribute.key.as_str(), denylist) {
+            attribute.value = Value::from(REDACTED);
+        }
+    }
+}
+
+fn is_denylisted(key: &str, denylist: &HashSet<String>) -> bool {
+    let key = key.to_lowercase();
+    denylist.iter().any(|field| {
+        key == *field
+            || key.ends_with(&format!(".{}", field))
+            || key.ends_with(&format!("_{}", field))
+    })
 }
 
 /// Register a user as global default to process span data.
@@ -29,4 +272,70 @@ where
 pub fn init_user(subscriber: impl Subscriber + Send + Sync) {
     LogTracer::init().expect("Failed to set logger");
     set_global_default(subscriber).expect("Failed to set subscriber");
+    // Read W3C `traceparent` headers so our spans join the caller's trace
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::telemetry::{redact_line, Redact, REDACTED};
+    use std::collections::HashSet;
+    use std::io::Write;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    fn denylist() -> HashSet<String> {
+        ["password", "customer_name"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect()
+    }
+
+    fn redact(line: &str) -> serde_json::Value {
+        serde_json::from_slice(&redact_line(line.as_bytes(), &denylist())).unwrap()
+    }
+
+    #[test]
+    fn denylisted_fields_are_redacted() {
+        let log = redact(r#"{"msg":"hello","password":"hunter2"}"#);
+        assert_eq!(log["password"], REDACTED);
+        assert_eq!(log["msg"], "hello");
+    }
+
+    #[test]
+    fn prefixed_fields_are_redacted() {
+        let log = redact(r#"{"order_customer_name":"Ada","db.password":"hunter2"}"#);
+        assert_eq!(log["order_customer_name"], REDACTED);
+        assert_eq!(log["db.password"], REDACTED);
+    }
+
+    #[test]
+    fn fields_only_containing_a_denylisted_name_are_kept() {
+        let log = redact(r#"{"password_changed_at":"today"}"#);
+        assert_eq!(log["password_changed_at"], "today");
+    }
+
+    #[test]
+    fn nested_fields_are_redacted() {
+        let log = redact(r#"{"customer":{"Customer_Name":"Ada"}}"#);
+        assert_eq!(log["customer"]["Customer_Name"], REDACTED);
+    }
+
+    #[test]
+    fn lines_that_are_not_json_are_passed_through() {
+        let line = b"password=hunter2\n";
+        assert_eq!(redact_line(line, &denylist()), line.to_vec());
+    }
+
+    #[test]
+    fn a_line_split_across_writes_is_redacted() {
+        let redacting = Redact::new(std::sync::Mutex::new(Vec::new()), &["password".to_string()]);
+        {
+            let mut writer = redacting.make_writer();
+            writer.write_all(br#"{"password":"#).unwrap();
+            writer.write_all(b"\"hunter2\"}\n").unwrap();
+        }
+        let written = String::from_utf8(redacting.sink.lock().unwrap().clone()).unwrap();
+        assert!(!written.contains("hunter2"));
+        assert!(written.ends_with('\n'));
+    }
 }
@@ -0,0 +1,247 @@
+use crate::domain::{quote_wait_minutes, table, CustomerName, Phone, TableTurn, TABLES};
+use crate::error::DomainError;
+use crate::reservations::{booked_tables, lock_bookings, BOOKED};
+use crate::sessions::{open_session, open_sessions, TableSession};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// Waitlist statuses as stored in the database
+pub const WAITING: &str = "waiting";
+pub const SEATED: &str = "seated";
+pub const LEFT: &str = "left";
+
+// A walk-in party waiting for a table
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WaitlistEntry {
+    pub id: Uuid,
+    pub party_size: i32,
+    pub customer_name: String,
+    pub phone: String,
+    // What the party was told when they arrived
+    pub quoted_wait_minutes: i32,
+    pub status: String,
+    pub added_at: DateTime<Utc>,
+}
+
+/*
+ * Adds a walk-in party to the end of the waitlist, quoting how long they are likely to wait.
+ * A table is expected to free up `turn` after its party was seated, or when a booking holding
+ * it now ends.
+ */
+#[tracing::instrument(
+    name = "Adding a party to the waitlist",
+    skip(pool, customer_name, phone)
+)]
+pub async fn join_waitlist(
+    pool: &PgPool,
+    id: Uuid,
+    party_size: i32,
+    customer_name: &CustomerName,
+    phone: &Phone,
+    now: DateTime<Utc>,
+    turn: Duration,
+) -> Result<WaitlistEntry, DomainError> {
+    let sessions = open_sessions(pool).await?;
+    let bookings = booked_until(pool, now, now + turn).await?;
+
+    let tables: Vec<TableTurn> = TABLES
+        .iter()
+        .map(|table| {
+            let seated_until = sessions
+                .iter()
+                .find(|session| session.table_no == table.table_no)
+                .map(|session| session.opened_at + turn);
+            let booked_until = bookings
+                .iter()
+                .filter(|(table_no, _)| *table_no == table.table_no)
+                .map(|(_, ends_at)| *ends_at)
+                .max();
+            TableTurn {
+                capacity: table.capacity,
+                free_at: seated_until.max(booked_until).unwrap_or(now),
+            }
+        })
+        .collect();
+    let parties_ahead: Vec<i32> = waiting(pool)
+        .await?
+        .iter()
+        .map(|entry| entry.party_size)
+        .collect();
+    let quoted_wait_minutes = quote_wait_minutes(now, &tables, &parties_ahead, party_size, turn)
+        .ok_or_else(|| DomainError::Invalid(format!("No table seats {}.", party_size)))?;
+
+    let entry = WaitlistEntry {
+        id,
+        party_size,
+        customer_name: customer_name.as_ref().to_string(),
+        phone: phone.as_ref().to_string(),
+        quoted_wait_minutes: quoted_wait_minutes as i32,
+        status: WAITING.to_string(),
+        added_at: now,
+    };
+    sqlx::query!(
+        r#"
+    INSERT INTO waitlist (
+        id, party_size, customer_name, phone, quoted_wait_minutes, status, added_at
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
+    "#,
+        entry.id,
+        entry.party_size,
+        entry.customer_name,
+        entry.phone,
+        entry.quoted_wait_minutes,
+        entry.status,
+        entry.added_at
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    Ok(entry)
+}
+
+// When each booked table's bookings overlapping `from` until `until` end
+async fn booked_until(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<(i32, DateTime<Utc>)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+    SELECT table_no, ends_at FROM reservations
+    WHERE status = $1 AND starts_at < $3 AND ends_at > $2
+    "#,
+        BOOKED,
+        from,
+        until
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.table_no, row.ends_at))
+        .collect())
+}
+
+// Parties still waiting, first come first served
+#[tracing::instrument(name = "Retrieving the waitlist from the database", skip(pool))]
+pub async fn waiting(pool: &PgPool) -> Result<Vec<WaitlistEntry>, sqlx::Error> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+    SELECT id, party_size, customer_name, phone, quoted_wait_minutes, status, added_at
+    FROM waitlist
+    WHERE status = $1
+    ORDER BY position
+    "#,
+        WAITING
+    )
+    .fetch_all(pool)
+    .await
+}
+
+async fn waiting_entry_for_update(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+) -> Result<WaitlistEntry, DomainError> {
+    let entry = sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+    SELECT id, party_size, customer_name, phone, quoted_wait_minutes, status, added_at
+    FROM waitlist
+    WHERE id = $1
+    FOR UPDATE
+    "#,
+        id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .ok_or_else(|| DomainError::NotFound(format!("There is no waitlist entry {}.", id)))?;
+
+    if entry.status != WAITING {
+        return Err(DomainError::Conflict {
+            code: "not_waiting",
+            message: format!("The party has already {}.", entry.status),
+        });
+    }
+    Ok(entry)
+}
+
+/*
+ * Seats a waiting party at a table of the host's choosing. The table has to be big enough,
+ * free, and not booked for the next `turn`, so walk-ins never take a table someone booked.
+ */
+#[tracing::instrument(name = "Seating a party from the waitlist", skip(pool))]
+pub async fn seat_from_waitlist(
+    pool: &PgPool,
+    id: Uuid,
+    table_no: i32,
+    session_id: Uuid,
+    now: DateTime<Utc>,
+    turn: Duration,
+) -> Result<TableSession, DomainError> {
+    let table = table(table_no)
+        .ok_or_else(|| DomainError::Invalid(format!("There is no table {}.", table_no)))?;
+
+    let mut transaction = pool.begin().await?;
+    lock_bookings(&mut transaction).await?;
+    let entry = waiting_entry_for_update(&mut transaction, id).await?;
+    if table.capacity < entry.party_size {
+        return Err(DomainError::Conflict {
+            code: "table_too_small",
+            message: format!(
+                "Table {} seats {}, not {}.",
+                table_no, table.capacity, entry.party_size
+            ),
+        });
+    }
+    if booked_tables(&mut transaction, now, now + turn)
+        .await?
+        .contains(&table_no)
+    {
+        return Err(DomainError::Conflict {
+            code: "table_booked",
+            message: format!("Table {} is booked soon.", table_no),
+        });
+    }
+
+    let session = open_session(
+        &mut transaction,
+        session_id,
+        table_no,
+        entry.party_size,
+        None,
+        now,
+    )
+    .await?;
+    sqlx::query!(
+        "UPDATE waitlist SET status = $2, session_id = $3 WHERE id = $1",
+        id,
+        SEATED,
+        session.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    Ok(session)
+}
+
+// The party gave up waiting
+#[tracing::instrument(name = "Removing a party from the waitlist", skip(pool))]
+pub async fn leave_waitlist(pool: &PgPool, id: Uuid) -> Result<WaitlistEntry, DomainError> {
+    let mut transaction = pool.begin().await?;
+    let mut entry = waiting_entry_for_update(&mut transaction, id).await?;
+    sqlx::query!("UPDATE waitlist SET status = $2 WHERE id = $1", id, LEFT)
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+
+    entry.status = LEFT.to_string();
+    Ok(entry)
+}
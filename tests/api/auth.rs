@@ -0,0 +1,85 @@
+use crate::client::{
+    gen_body, spawn_app, spawn_app_with_configuration, test_configuration, KITCHEN_TOKEN,
+    MANAGER_TOKEN,
+};
+
+async fn eighty_six_fries(address: &str, token: Option<&str>) -> reqwest::Response {
+    let mut request = reqwest::Client::new()
+        .put(format!("{}/admin/availability/fries", address))
+        .json(&serde_json::json!({ "available": false }));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await.expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn eighty_sixing_an_item_needs_a_staff_token() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for token in [None, Some("made-up-token")] {
+        // Act
+        let response = eighty_six_fries(&app.address, token).await;
+
+        // Assert
+        assert_eq!(
+            401,
+            response.status().as_u16(),
+            "Fries were 86'd with the token {:?}.",
+            token
+        );
+        assert_eq!(response.headers()["WWW-Authenticate"], "Bearer");
+    }
+    // Fries can still be ordered
+    assert_eq!(
+        200,
+        app.post_order(gen_body(1, "fries", 1))
+            .await
+            .status()
+            .as_u16()
+    );
+}
+
+#[actix_rt::test]
+async fn the_kitchen_and_managers_can_86_items() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for token in [KITCHEN_TOKEN, MANAGER_TOKEN] {
+        // Act
+        let response = eighty_six_fries(&app.address, Some(token)).await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16());
+    }
+}
+
+#[actix_rt::test]
+async fn the_admin_port_needs_a_staff_token_too() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.application.admin_port = Some(0);
+    let app = spawn_app_with_configuration(configuration).await;
+    let admin_address = app.admin_address.clone().unwrap();
+
+    // Act
+    let response = eighty_six_fries(&admin_address, None).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn without_staff_tokens_nobody_can_86_items() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.auth.staff = vec![];
+    let app = spawn_app_with_configuration(configuration).await;
+
+    // Act
+    let response = eighty_six_fries(&app.address, Some(MANAGER_TOKEN)).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
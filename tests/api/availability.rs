@@ -0,0 +1,163 @@
+use crate::client::{
+    gen_body, gen_bundle_body, next_event_containing, noon, spawn_app, spawn_app_with_clock,
+    TestClient,
+};
+use restaurant::clock::FixedClock;
+use std::sync::Arc;
+
+async fn menu_entry(app: &TestClient, item: &str) -> serde_json::Value {
+    let menu: serde_json::Value = app.get_menu().await.json().await.unwrap();
+    menu["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["name"] == item)
+        .cloned()
+        .expect("The item is not on the menu.")
+}
+
+#[actix_rt::test]
+async fn an_86d_item_cannot_be_ordered_until_put_back() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let eighty_six = app
+        .put_availability("fries", serde_json::json!({"available": false}))
+        .await;
+    let while_86d = app.post_order(gen_body(1, "fries", 1)).await;
+    let bundle_while_86d = app
+        .post_bundle_order(gen_bundle_body(1, "burger meal", 1, &[]))
+        .await;
+    app.put_availability("fries", serde_json::json!({"available": true}))
+        .await;
+    let after = app.post_order(gen_body(1, "fries", 1)).await;
+
+    // Assert
+    assert_eq!(200, eighty_six.status().as_u16());
+    assert_eq!(409, while_86d.status().as_u16());
+    let error: serde_json::Value = while_86d.json().await.unwrap();
+    assert_eq!(error["code"], "item_86d");
+    assert_eq!(409, bundle_while_86d.status().as_u16());
+    assert_eq!(200, after.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn an_86d_item_comes_back_at_its_restore_time() {
+    // Arrange
+    let app = spawn_app().await;
+    app.put_availability(
+        "cola",
+        serde_json::json!({"available": false, "restore_after_minutes": 30}),
+    )
+    .await;
+    let before = menu_entry(&app, "cola").await;
+
+    // Act
+    // Move the restore time into the past rather than waiting for it
+    sqlx::query!(
+        "UPDATE item_availability SET restore_at = now() - interval '1 minute' WHERE item = 'cola'"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    let response = app.post_order(gen_body(1, "cola", 1)).await;
+
+    // Assert
+    assert_eq!(before["available"], false);
+    assert!(before["restore_at"].is_string());
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(menu_entry(&app, "cola").await["available"], true);
+}
+
+#[actix_rt::test]
+async fn the_menu_shows_what_can_be_ordered() {
+    // Arrange
+    let app = spawn_app_with_clock(Arc::new(FixedClock::new(noon()))).await;
+    app.put_availability("water", serde_json::json!({"available": false}))
+        .await;
+    app.put_availability("fries", serde_json::json!({"available": false}))
+        .await;
+
+    // Act
+    let menu: serde_json::Value = app.get_menu().await.json().await.unwrap();
+
+    // Assert
+    let available: Vec<(&str, bool)> = menu["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| {
+            (
+                entry["name"].as_str().unwrap(),
+                entry["available"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        available,
+        vec![
+            ("hamburger", true),
+            ("fries", false),
+            ("cola", true),
+            ("water", false),
+            // Breakfast is over
+            ("pancakes", false)
+        ]
+    );
+    assert!(menu["items"][0]["variants"].is_array());
+    // The burger meal comes with fries
+    assert_eq!(menu["bundles"][0]["available"], false);
+}
+
+#[actix_rt::test]
+async fn availability_changes_are_published_as_events() {
+    // Arrange
+    let app = spawn_app().await;
+    let mut events = app.get_events().await;
+
+    // Act
+    app.put_availability("hamburger", serde_json::json!({"available": false}))
+        .await;
+
+    // Assert
+    let event = next_event_containing(&mut events, "availability_changed").await;
+    assert!(event.contains(r#""item":"hamburger""#));
+    assert!(event.contains(r#""available":false"#));
+}
+
+#[actix_rt::test]
+async fn invalid_availability_changes_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            "unicorn",
+            serde_json::json!({"available": false}),
+            "an item not on the menu",
+        ),
+        (
+            "fries",
+            serde_json::json!({"available": true, "restore_after_minutes": 5}),
+            "a restore time when putting an item back",
+        ),
+        (
+            "fries",
+            serde_json::json!({"available": false, "restore_after_minutes": 0}),
+            "a restore time of zero",
+        ),
+    ];
+
+    for (item, change, description) in test_cases {
+        // Act
+        let response = app.put_availability(item, change).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the change had {}.",
+            description
+        );
+    }
+}
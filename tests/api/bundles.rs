@@ -0,0 +1,132 @@
+use crate::client::{gen_body, gen_bundle_body, noon, spawn_app, spawn_app_with_clock};
+use chrono::Duration;
+use restaurant::clock::FixedClock;
+use restaurant::domain::DatabaseResponse;
+use std::sync::Arc;
+
+#[actix_rt::test]
+async fn a_bundle_is_placed_with_a_line_per_component() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = gen_bundle_body(1, "burger meal", 2, &[("drink", "water")]);
+
+    // Act
+    let response = app.post_bundle_order(body).await;
+    let orders: Vec<DatabaseResponse> = app.query_table(1).await.json().await.unwrap();
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(4, orders.len());
+
+    let bundle = orders.iter().find(|o| o.parent_id.is_none()).unwrap();
+    assert_eq!(bundle.item, "burger meal");
+    assert_eq!(bundle.quantity, 2);
+
+    let mut components: Vec<(&str, &str)> = orders
+        .iter()
+        .filter(|o| o.parent_id == Some(bundle.id))
+        .map(|o| (o.item.as_str(), o.variant.as_str()))
+        .collect();
+    components.sort();
+    assert_eq!(
+        components,
+        vec![
+            ("fries", "regular"),
+            ("hamburger", "single"),
+            ("water", "regular")
+        ]
+    );
+    // Every component is made as many times as bundles were ordered
+    assert!(orders.iter().all(|o| o.quantity == 2));
+}
+
+#[actix_rt::test]
+async fn bundle_returns_a_400_for_invalid_substitutions() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            gen_bundle_body(1, "burger meal", 1, &[("drink", "fries")]),
+            "an item the slot does not allow",
+        ),
+        (
+            gen_bundle_body(1, "burger meal", 1, &[("dessert", "cola")]),
+            "a slot the bundle does not have",
+        ),
+        (
+            gen_bundle_body(1, "burger meal", 1, &[("drink", "cola:huge")]),
+            "a variant that does not exist",
+        ),
+        (
+            gen_bundle_body(1, "picnic basket", 1, &[]),
+            "an unknown bundle",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_bundle_order(body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the payload had {}.",
+            description
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn the_bill_charges_the_bundle_price_not_its_components() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_with_clock(clock.clone()).await;
+    app.post_bundle_order(gen_bundle_body(
+        2,
+        "burger meal",
+        1,
+        &[("drink", "cola:large")],
+    ))
+    .await;
+    clock.advance(Duration::minutes(1));
+    app.post_order(gen_body(2, "water", 1)).await;
+
+    // Act
+    let bill: serde_json::Value = app.get_bill(2).await.json().await.unwrap();
+
+    // Assert
+    assert_eq!(bill["lines"].as_array().unwrap().len(), 2);
+    assert_eq!(bill["lines"][0]["item"], "burger meal");
+    assert_eq!(bill["lines"][0]["total_cents"], 1250);
+    assert_eq!(bill["lines"][0]["components"].as_array().unwrap().len(), 3);
+    assert_eq!(bill["total_cents"], 1250 + 150);
+}
+
+#[actix_rt::test]
+async fn voiding_a_bundle_voids_its_components() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_bundle_order(gen_bundle_body(3, "burger meal", 1, &[]))
+        .await;
+    app.post_order(gen_body(3, "hamburger", 1)).await;
+    let orders: Vec<DatabaseResponse> = app.query_table(3).await.json().await.unwrap();
+    let bundle = orders.iter().find(|o| o.item == "burger meal").unwrap();
+    let component = orders
+        .iter()
+        .find(|o| o.parent_id == Some(bundle.id))
+        .unwrap();
+
+    // Act
+    // A component on its own is left alone, as is a hamburger deleted by name
+    app.delete_with_id(component.id).await;
+    app.delete_with_item(3, "hamburger".to_string()).await;
+    let after_component: Vec<DatabaseResponse> = app.query_table(3).await.json().await.unwrap();
+    let response = app.delete_with_id(bundle.id).await;
+    let after_bundle: Vec<DatabaseResponse> = app.query_table(3).await.json().await.unwrap();
+
+    // Assert
+    assert_eq!(4, after_component.len());
+    assert_eq!(200, response.status().as_u16());
+    assert!(after_bundle.is_empty());
+}
@@ -1,30 +1,98 @@
+use chrono::{DateTime, TimeZone, Utc};
 use once_cell::sync::Lazy;
-use restaurant::configuration::{get_configuration, DatabaseSettings};
-use restaurant::startup::{get_connection_pool, Application};
-use restaurant::telemetry::{get_user, init_user};
+use restaurant::auth::Role;
+use restaurant::clock::FixedClock;
+use restaurant::configuration::{
+    get_configuration, DatabaseSettings, Settings, StaffToken, TelemetrySettings,
+};
+use restaurant::shutdown::Shutdown;
+use restaurant::startup::{get_connection_pool, Application, Dependencies};
+use restaurant::telemetry::{get_user, init_user, Redact};
+use secrecy::Secret;
 use sqlx::{Connection, Executor, PgConnection, PgPool};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio;
 use uuid::Uuid;
 
+// Staff tokens of the test configuration, the client sends the manager's when 86-ing items
+pub const KITCHEN_TOKEN: &str = "kitchen-token";
+pub const MANAGER_TOKEN: &str = "manager-token";
+
+// Every log line written during the test run, so tests can assert on what was logged
+static CAPTURED_LOGS: Lazy<Mutex<Vec<u8>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
 // Ensure that the `tracing` stack is only initialised once using `once_cell`
 static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
     let user_name = "test".to_string();
-    // We cannot assign the output of `get_user` to a variable based on the value of `TEST_LOG`
-    // because the sink is part of the type returned by `get_user`, therefore they are not the
-    // same type. We could work around it, but this is the most straight-forward way of moving forward.
-    if std::env::var("TEST_LOG").is_ok() {
-        let user = get_user(user_name, default_filter_level, std::io::stdout);
-        init_user(user);
-    } else {
-        let user = get_user(user_name, default_filter_level, std::io::sink);
-        init_user(user);
-    };
+    // Logs are always captured, `LogCapture` also prints them when `TEST_LOG` is set
+    let sink = Redact::new(
+        LogCapture::new,
+        &TelemetrySettings::default().redacted_fields,
+    );
+    let user = get_user(user_name, default_filter_level, sink, None);
+    init_user(user);
 });
 
+// A sink keeping a copy of every log line in `CAPTURED_LOGS`
+pub struct LogCapture {
+    echo: bool,
+}
+
+impl LogCapture {
+    fn new() -> Self {
+        Self {
+            echo: std::env::var("TEST_LOG").is_ok(),
+        }
+    }
+}
+
+impl Write for LogCapture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        CAPTURED_LOGS.lock().unwrap().extend_from_slice(buf);
+        if self.echo {
+            std::io::stdout().write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
+
+// Log lines captured so far that mention `needle`
+pub fn captured_log_lines(needle: &str) -> Vec<String> {
+    String::from_utf8_lossy(&CAPTURED_LOGS.lock().unwrap())
+        .lines()
+        .filter(|line| line.contains(needle))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+// Read from an event stream until a complete event containing `needle` arrives
+pub async fn next_event_containing(events: &mut reqwest::Response, needle: &str) -> String {
+    let mut received = String::new();
+    loop {
+        let chunk = actix_web::rt::time::timeout(Duration::from_secs(5), events.chunk())
+            .await
+            .expect("No event within 5 seconds.")
+            .expect("Failed to read the event stream.")
+            .expect("The event stream ended.");
+        received.push_str(&String::from_utf8_lossy(&chunk));
+        if received.contains(needle) && received.ends_with("\n\n") {
+            return received;
+        }
+    }
+}
+
 pub struct TestClient {
     pub address: String,
+    pub admin_address: Option<String>,
     pub db_pool: PgPool,
+    pub shutdown: Shutdown,
 }
 
 impl TestClient {
@@ -38,6 +106,124 @@ impl TestClient {
             .expect("Failed to execute request.")
     }
 
+    pub async fn post_bundle_order(&self, body: String) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/order_bundle", &self.address))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn health_ready(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/health/ready", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Operational endpoints live on the admin port when one is configured
+    fn operational_address(&self) -> &str {
+        self.admin_address.as_ref().unwrap_or(&self.address)
+    }
+
+    pub async fn get_metrics(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/metrics", self.operational_address()))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_runtime_settings(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/settings", self.operational_address()))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn patch_runtime_settings(&self, changes: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .patch(format!("{}/admin/settings", self.operational_address()))
+            .json(&changes)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_inventory(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/inventory", self.operational_address()))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_inventory(
+        &self,
+        ingredient: &str,
+        stock: i32,
+        low_stock_threshold: i32,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/inventory/{}",
+                self.operational_address(),
+                ingredient
+            ))
+            .json(&serde_json::json!({
+                "stock": stock,
+                "low_stock_threshold": low_stock_threshold,
+            }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_availability(
+        &self,
+        item: &str,
+        change: serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/availability/{}",
+                self.operational_address(),
+                item
+            ))
+            .bearer_auth(MANAGER_TOKEN)
+            .json(&change)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_events(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/events", self.operational_address()))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_order_with_request_id(
+        &self,
+        body: String,
+        request_id: &str,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/order", &self.address))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("X-Request-Id", request_id)
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn query_table(&self, table_no: i32) -> reqwest::Response {
         reqwest::Client::new()
             .get(&format!("{}/query_table/{}", &self.address, table_no))
@@ -46,6 +232,330 @@ impl TestClient {
             .expect("Failed to get data.")
     }
 
+    pub async fn get_menu(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/menu", &self.address))
+            .send()
+            .await
+            .expect("Failed to get data.")
+    }
+
+    pub async fn post_reservation(&self, reservation: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/reservations", &self.address))
+            .json(&reservation)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Reservations on a day given as `2026-10-19`, today when `None`
+    pub async fn get_reservations(&self, date: Option<&str>) -> reqwest::Response {
+        let query = date
+            .map(|date| format!("?date={}", date))
+            .unwrap_or_default();
+        reqwest::Client::new()
+            .get(format!("{}/reservations{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to get data.")
+    }
+
+    pub async fn cancel_reservation(&self, id: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/reservations/{}/cancel", &self.address, id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn seat_reservation(&self, id: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/reservations/{}/seat", &self.address, id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_waitlist(&self, party_size: i32) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/waitlist", &self.address))
+            .json(&serde_json::json!({
+                "party_size": party_size,
+                "customer_name": "Ana García",
+                "phone": "+34 600 123 456",
+            }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_waitlist(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/waitlist", &self.address))
+            .send()
+            .await
+            .expect("Failed to get data.")
+    }
+
+    pub async fn seat_from_waitlist(&self, id: Uuid, table_no: i32) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/waitlist/{}/seat", &self.address, id))
+            .json(&serde_json::json!({ "table_no": table_no }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn leave_waitlist(&self, id: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .delete(format!("{}/waitlist/{}", &self.address, id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_tables(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/tables", &self.address))
+            .send()
+            .await
+            .expect("Failed to get data.")
+    }
+
+    pub async fn transfer_table(&self, from: i32, to: i32) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/tables/{}/transfer/{}", &self.address, from, to))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn merge_sessions(&self, session_id: Uuid, into: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/sessions/{}/merge/{}",
+                &self.address, session_id, into
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn fire_course(&self, session_id: Uuid, course: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/sessions/{}/fire/{}",
+                &self.address, session_id, course
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_kitchen(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/kitchen", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_payment(
+        &self,
+        session_id: Uuid,
+        payment: serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/sessions/{}/payments",
+                &self.address, session_id
+            ))
+            .json(&payment)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_payments(&self, session_id: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/sessions/{}/payments",
+                &self.address, session_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Refunds whatever is left of the payment when `amount_cents` is `None`
+    pub async fn refund_payment(
+        &self,
+        payment_id: Uuid,
+        amount_cents: Option<i64>,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/payments/{}/refund", &self.address, payment_id))
+            .json(&serde_json::json!({ "amount_cents": amount_cents }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn close_session(&self, session_id: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/sessions/{}/close", &self.address, session_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_voucher(&self, code: &str, voucher: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/vouchers/{}",
+                self.operational_address(),
+                code
+            ))
+            .json(&voucher)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_vouchers(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/vouchers", self.operational_address()))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_promotion(
+        &self,
+        name: &str,
+        promotion: serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(format!(
+                "{}/admin/promotions/{}",
+                self.operational_address(),
+                name
+            ))
+            .json(&promotion)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_promotions(&self) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/promotions", self.operational_address()))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_promotion(&self, name: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .delete(format!(
+                "{}/admin/promotions/{}",
+                self.operational_address(),
+                name
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_print_jobs(&self, status: Option<&str>) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/admin/print_jobs", self.operational_address()))
+            .query(&[("status", status)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn retry_print_job(&self, id: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/admin/print_jobs/{}/retry",
+                self.operational_address(),
+                id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn redeem_voucher(&self, session_id: Uuid, code: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/sessions/{}/vouchers",
+                &self.address, session_id
+            ))
+            .json(&serde_json::json!({ "code": code }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_discount(
+        &self,
+        session_id: Uuid,
+        discount: serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/sessions/{}/discounts",
+                &self.address, session_id
+            ))
+            .json(&discount)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_session_bill(&self, session_id: Uuid) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/sessions/{}/bill", &self.address, session_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // `query` picks the format and session, e.g. `{"format": "html"}`
+    pub async fn get_receipt(&self, table_no: i32, query: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/tables/{}/receipt", &self.address, table_no))
+            .query(&query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Seats a walk-in party at a table, returning their session's id
+    pub async fn seat_walk_in(&self, party_size: i32, table_no: i32) -> Uuid {
+        let entry: serde_json::Value = self.post_waitlist(party_size).await.json().await.unwrap();
+        let id: Uuid = entry["id"].as_str().unwrap().parse().unwrap();
+        let session: serde_json::Value = self
+            .seat_from_waitlist(id, table_no)
+            .await
+            .json()
+            .await
+            .unwrap();
+        session["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    pub async fn get_bill(&self, table_no: i32) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/bill/{}", &self.address, table_no))
+            .send()
+            .await
+            .expect("Failed to get data.")
+    }
+
     pub async fn query_all(&self) -> reqwest::Response {
         reqwest::Client::new()
             .get(&format!("{}/query_all", &self.address))
@@ -208,6 +718,71 @@ pub fn gen_body(table_no: i32, item: &str, quantity: i32) -> String {
     format!("table_no={}&item={}&quantity={}", table_no, item, quantity,)
 }
 
+// Modifiers are sent comma separated, spaces are encoded so names like `no onions` survive
+pub fn gen_body_with_modifiers(
+    table_no: i32,
+    item: &str,
+    quantity: i32,
+    modifiers: &[&str],
+    note: &str,
+) -> String {
+    format!(
+        "{}&modifiers={}&note={}",
+        gen_body(table_no, item, quantity),
+        modifiers.join(",").replace(' ', "%20"),
+        note.replace(' ', "%20"),
+    )
+}
+
+// An order to take away, which may be taxed differently from eating in
+pub fn gen_takeaway_body(table_no: i32, item: &str, quantity: i32) -> String {
+    format!("{}&takeaway=true", gen_body(table_no, item, quantity))
+}
+
+// An order for a course, kept from the kitchen until the course is fired
+pub fn gen_held_body(table_no: i32, item: &str, quantity: i32, course: &str) -> String {
+    format!(
+        "{}&course={}&hold=true",
+        gen_body(table_no, item, quantity),
+        course
+    )
+}
+
+// Substitutions are `slot=item` pairs, e.g. `("drink", "water")`
+pub fn gen_bundle_body(
+    table_no: i32,
+    bundle: &str,
+    quantity: i32,
+    substitutions: &[(&str, &str)],
+) -> String {
+    let substitutions: Vec<String> = substitutions
+        .iter()
+        .map(|(slot, item)| format!("{}={}", slot, item))
+        .collect();
+    format!(
+        "table_no={}&bundle={}&quantity={}&substitutions={}",
+        table_no,
+        bundle.replace(' ', "%20"),
+        quantity,
+        substitutions.join(",").replace('=', "%3D"),
+    )
+}
+
+// A booking for `party_size` at `starts_at`, at any table unless `table_no` is given
+pub fn gen_reservation(
+    party_size: i32,
+    starts_at: DateTime<Utc>,
+    table_no: Option<i32>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "party_size": party_size,
+        "starts_at": starts_at,
+        "customer_name": "Ana García",
+        "phone": "+34 600 123 456",
+        "table_no": table_no,
+    })
+}
+
 pub fn gen_multi_item_bodies(table_no: i32, items: Vec<(&str, i32)>) -> Vec<String> {
     let mut orders = Vec::new();
 
@@ -218,41 +793,107 @@ pub fn gen_multi_item_bodies(table_no: i32, items: Vec<(&str, i32)>) -> Vec<Stri
     orders
 }
 
+// Randomise configuration to ensure test isolation
+pub fn test_configuration() -> Settings {
+    let mut c = get_configuration().expect("Failed to read configuration.");
+    // Use a different database for each test case
+    c.database.database_name = Uuid::new_v4().to_string();
+    // Use a random OS port
+    c.application.port = 0;
+    c.auth.staff = vec![
+        StaffToken {
+            name: "pass".to_string(),
+            role: Role::Kitchen,
+            token: Secret::new(KITCHEN_TOKEN.to_string()),
+        },
+        StaffToken {
+            name: "manager".to_string(),
+            role: Role::Manager,
+            token: Secret::new(MANAGER_TOKEN.to_string()),
+        },
+    ];
+    c
+}
+
 // Launch our application in the background
 pub async fn spawn_app() -> TestClient {
+    spawn_app_with_configuration(test_configuration()).await
+}
+
+// Launch our application in the background with a customised configuration
+// Noon on Monday 2026-10-19, after breakfast and before happy hour
+pub fn noon() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap()
+}
+
+pub async fn spawn_app_with_clock(clock: Arc<FixedClock>) -> TestClient {
+    spawn_app_with(Dependencies {
+        clock,
+        ..Dependencies::default()
+    })
+    .await
+}
+
+// Launch our application with e.g. a fixed clock, sequential ids or a seeded generator
+pub async fn spawn_app_with(dependencies: Dependencies) -> TestClient {
+    let configuration = test_configuration();
+    configure_database(&configuration.database).await;
+    start_app_with(configuration, dependencies).await
+}
+
+pub async fn spawn_app_with_configuration(configuration: Settings) -> TestClient {
     // The first time `initialize` is invoked the code in `TRACING` is executed.
     // All other invocations will instead skip execution.
     Lazy::force(&TRACING);
 
-    // Randomise configuration to ensure test isolation
-    let configuration = {
-        let mut c = get_configuration().expect("Failed to read configuration.");
-        // Use a different database for each test case
-        c.database.database_name = Uuid::new_v4().to_string();
-        // Use a random OS port
-        c.application.port = 0;
-        c
-    };
-
     // Create and migrate the database
     configure_database(&configuration.database).await;
 
-    let application = Application::build(configuration.clone())
+    start_app(configuration).await
+}
+
+// Start another instance of the application on a database that already exists
+pub async fn start_app(configuration: Settings) -> TestClient {
+    start_app_with(configuration, Dependencies::default()).await
+}
+
+// Starts an app on an existing database with the time coming from `clock`
+pub async fn start_app_with_clock(configuration: Settings, clock: Arc<FixedClock>) -> TestClient {
+    start_app_with(
+        configuration,
+        Dependencies {
+            clock,
+            ..Dependencies::default()
+        },
+    )
+    .await
+}
+
+// Starts an app on an existing database with the given dependencies
+pub async fn start_app_with(configuration: Settings, dependencies: Dependencies) -> TestClient {
+    Lazy::force(&TRACING);
+
+    let application = Application::build_with(configuration.clone(), dependencies)
         .await
         .expect("Failed to build application.");
     // Get the port before spawning the application
     let address = format!("http://127.0.0.1:{}", application.port());
+    let admin_address = application
+        .admin_port()
+        .map(|port| format!("http://127.0.0.1:{}", port));
+    let shutdown = application.shutdown();
     let _ = tokio::spawn(application.run_until_stopped());
 
     TestClient {
         address,
+        admin_address,
         db_pool: get_connection_pool(&configuration.database),
+        shutdown,
     }
 }
 
-// TODO: Add cleanup to remove empty databases
-pub async fn configure_database(config: &DatabaseSettings) -> PgPool {
-    // Create Database
+// Create an empty database without running any migrations
+pub async fn create_database(config: &DatabaseSettings) -> PgPool {
     let mut connection = PgConnection::connect_with(&config.without_db())
         .await
         .expect("Failed to connect to Postgres");
@@ -261,10 +902,17 @@ pub async fn configure_database(config: &DatabaseSettings) -> PgPool {
         .await
         .expect("Failed to create database.");
 
-    // Migrate database
-    let connection_pool = PgPool::connect_with(config.with_db())
+    PgPool::connect_with(config.with_db())
         .await
-        .expect("Failed to connect to Postgres.");
+        .expect("Failed to connect to Postgres.")
+}
+
+// TODO: Add cleanup to remove empty databases
+pub async fn configure_database(config: &DatabaseSettings) -> PgPool {
+    // Create Database
+    let connection_pool = create_database(config).await;
+
+    // Migrate database
     sqlx::migrate!("./migrations")
         .run(&connection_pool)
         .await
@@ -0,0 +1,210 @@
+use crate::client::{
+    gen_body, gen_bundle_body, gen_held_body, next_event_containing, noon, spawn_app_with_clock,
+    TestClient,
+};
+use restaurant::clock::FixedClock;
+use restaurant::domain::{Course, DatabaseResponse};
+use restaurant::kitchen::{Fired, KitchenTicket};
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn spawn_app_at_noon() -> TestClient {
+    spawn_app_with_clock(Arc::new(FixedClock::new(noon()))).await
+}
+
+async fn orders_at(app: &TestClient, table_no: i32) -> Vec<DatabaseResponse> {
+    app.query_table(table_no).await.json().await.unwrap()
+}
+
+async fn kitchen(app: &TestClient) -> Vec<KitchenTicket> {
+    let response = app.get_kitchen().await;
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+async fn error_code(response: reqwest::Response, status: u16) -> String {
+    assert_eq!(status, response.status().as_u16());
+    let error: serde_json::Value = response.json().await.unwrap();
+    error["code"].as_str().unwrap_or_default().to_string()
+}
+
+#[actix_rt::test]
+async fn held_lines_reach_the_kitchen_once_their_course_is_fired() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_held_body(1, "hamburger", 2, "main"))
+        .await
+        .error_for_status()
+        .unwrap();
+    app.post_order(gen_body(1, "cola", 2)).await;
+    let tickets = kitchen(&app).await;
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0].item, "cola");
+    let mut events = app.get_events().await;
+
+    // Act
+    let response = app.fire_course(session_id, "main").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let fired: Fired = response.json().await.unwrap();
+    assert_eq!(fired.course, Course::Main);
+    assert_eq!(fired.table_no, 1);
+    assert_eq!(fired.order_ids.len(), 1);
+    assert_eq!(fired.fired_at, noon());
+
+    let tickets = kitchen(&app).await;
+    assert_eq!(tickets.len(), 2);
+    assert!(tickets.iter().any(|ticket| ticket.item == "hamburger"));
+    assert!(orders_at(&app, 1)
+        .await
+        .iter()
+        .all(|order| order.fired_at == Some(noon())));
+
+    let event = next_event_containing(&mut events, "course_fired").await;
+    assert!(event.contains(&session_id.to_string()));
+    let audit = sqlx::query!("SELECT details FROM audit_log WHERE action = 'course_fired'")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(audit.details["course"], "main");
+}
+
+#[actix_rt::test]
+async fn lines_default_to_the_course_of_their_item() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+
+    // Act
+    app.post_order(gen_body(1, "cola", 1)).await;
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+    app.post_order(format!("{}&course=dessert", gen_body(1, "fries", 1)))
+        .await;
+
+    // Assert
+    let orders = orders_at(&app, 1).await;
+    let course = |item: &str| {
+        orders
+            .iter()
+            .find(|order| order.item == item)
+            .map(|order| order.course.clone())
+            .unwrap()
+    };
+    assert_eq!(course("cola"), "drinks");
+    assert_eq!(course("hamburger"), "main");
+    assert_eq!(course("fries"), "dessert");
+    assert!(orders.iter().all(|order| order.fired_at == Some(noon())));
+}
+
+#[actix_rt::test]
+async fn an_unknown_course_is_rejected() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let session_id = app.seat_walk_in(2, 1).await;
+
+    // Act
+    let order = app
+        .post_order(format!("{}&course=appetiser", gen_body(1, "fries", 1)))
+        .await;
+    let fire = app.fire_course(session_id, "appetiser").await;
+
+    // Assert
+    assert_eq!(400, order.status().as_u16());
+    assert_eq!(400, fire.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn lines_cannot_be_held_for_a_table_nobody_is_seated_at() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+
+    // Act
+    let order = app
+        .post_order(gen_held_body(1, "hamburger", 1, "main"))
+        .await;
+    let bundle = app
+        .post_bundle_order(format!(
+            "{}&hold=true",
+            gen_bundle_body(1, "burger meal", 1, &[])
+        ))
+        .await;
+
+    // Assert
+    assert_eq!(error_code(order, 409).await, "not_seated");
+    assert_eq!(error_code(bundle, 409).await, "not_seated");
+    assert!(orders_at(&app, 1).await.is_empty());
+}
+
+#[actix_rt::test]
+async fn firing_a_course_with_nothing_held_is_a_conflict() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_held_body(1, "hamburger", 1, "main"))
+        .await;
+    app.fire_course(session_id, "main").await;
+
+    // Act
+    let again = app.fire_course(session_id, "main").await;
+    let dessert = app.fire_course(session_id, "dessert").await;
+    let unknown = app.fire_course(Uuid::new_v4(), "main").await;
+
+    // Assert
+    assert_eq!(error_code(again, 409).await, "nothing_to_fire");
+    assert_eq!(error_code(dessert, 409).await, "nothing_to_fire");
+    assert_eq!(404, unknown.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn a_held_bundle_sends_each_component_with_its_own_course() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_bundle_order(format!(
+        "{}&hold=true",
+        gen_bundle_body(1, "burger meal", 1, &[("drink", "water")])
+    ))
+    .await
+    .error_for_status()
+    .unwrap();
+    assert!(kitchen(&app).await.is_empty());
+
+    // Act
+    app.fire_course(session_id, "drinks").await;
+
+    // Assert
+    let tickets = kitchen(&app).await;
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0].item, "water");
+
+    app.fire_course(session_id, "main").await;
+    let tickets = kitchen(&app).await;
+    // The bundle's own line is not cooked, only its components are
+    assert!(tickets.iter().all(|ticket| ticket.item != "burger meal"));
+    assert!(tickets.iter().any(|ticket| ticket.item == "hamburger"));
+}
+
+#[actix_rt::test]
+async fn the_kitchen_queue_is_ordered_by_when_lines_are_due() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_with_clock(clock.clone()).await;
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+    app.post_order(gen_body(2, "fries", 1)).await;
+    app.post_order(gen_body(3, "cola", 1)).await;
+
+    // Act
+    let tickets = kitchen(&app).await;
+
+    // Assert
+    assert_eq!(tickets.len(), 3);
+    assert!(tickets
+        .windows(2)
+        .all(|pair| pair[0].due_at <= pair[1].due_at));
+    assert!(tickets.iter().all(|ticket| ticket.due_at > noon()));
+
+    // Everything on the menu is ready within the hour
+    clock.advance(chrono::Duration::hours(1));
+    assert!(kitchen(&app).await.is_empty());
+}
@@ -139,6 +139,33 @@ async fn delete_item_name_deletes_all_items_from_matching_table() {
     assert_eq!(check_response(&saved, expected_result), true);
 }
 
+#[actix_rt::test]
+async fn delete_item_with_a_variant_only_deletes_that_variant() {
+    // Arrange
+    let app = spawn_app().await;
+    let table_no = 1;
+    let items = [("cola:small", 1), ("cola:large", 2), ("cola", 1)].to_vec();
+    let bodies = gen_multi_item_bodies(table_no, items);
+    assert!(app.post_parallel_orders(bodies).await);
+
+    // Act
+    let response = app
+        .delete_with_item(table_no, "cola:large".to_string())
+        .await;
+
+    // Assert
+    assert!(response.status().is_success());
+    let saved = app
+        .query_all()
+        .await
+        .json::<Vec<DatabaseResponse>>()
+        .await
+        .unwrap();
+    let mut variants: Vec<&str> = saved.iter().map(|s| s.variant.as_str()).collect();
+    variants.sort();
+    assert_eq!(variants, vec!["medium", "small"]);
+}
+
 #[actix_rt::test]
 async fn delete_with_name_does_nothing_when_item_is_not_in_table() {
     // Arrange
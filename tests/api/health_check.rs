@@ -1,4 +1,29 @@
-use crate::client::spawn_app;
+use crate::client::{spawn_app, test_configuration};
+use actix_web::{test, web, App};
+use restaurant::routes::{health_ready, ReadinessTimeout};
+use restaurant::shutdown::Shutdown;
+use restaurant::startup::get_connection_pool;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+
+// Calls `/health/ready` against `pool` without starting the application, which needs a database
+async fn readiness_with(pool: sqlx::PgPool, limit: Duration) -> (u16, serde_json::Value) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .app_data(web::Data::new(ReadinessTimeout(limit)))
+            .app_data(web::Data::new(Shutdown::new()))
+            .route("/health/ready", web::get().to(health_ready)),
+    )
+    .await;
+    let response = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/health/ready").to_request(),
+    )
+    .await;
+    let status = response.status().as_u16();
+    (status, test::read_body_json(response).await)
+}
 
 #[actix_rt::test]
 async fn health_check_works() {
@@ -18,3 +43,110 @@ async fn health_check_works() {
     assert!(response.status().is_success());
     assert_eq!(Some(0), response.content_length());
 }
+
+#[actix_rt::test]
+async fn liveness_works() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(response.status().is_success());
+    assert_eq!(Some(0), response.content_length());
+}
+
+#[actix_rt::test]
+async fn readiness_reports_every_component_as_ok() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.health_ready().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let report = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(report["status"], "ok");
+    for component in ["server", "database", "migrations", "pool"] {
+        assert_eq!(report["checks"][component]["status"], "ok");
+    }
+}
+
+#[actix_rt::test]
+async fn readiness_fails_when_migrations_are_pending() {
+    let app = spawn_app().await;
+
+    // Arrange
+    // Forget that migrations were applied, so the binary believes the schema is behind
+    sqlx::query("DELETE FROM _sqlx_migrations")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.health_ready().await;
+
+    // Assert
+    assert_eq!(503, response.status().as_u16());
+    let report = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(report["status"], "failing");
+    assert_eq!(report["checks"]["database"]["status"], "ok");
+    assert_eq!(report["checks"]["migrations"]["status"], "failing");
+}
+
+#[actix_rt::test]
+async fn readiness_fails_when_the_database_is_unreachable() {
+    // Arrange
+    // Nothing listens on a port that was just released
+    let port = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let mut configuration = test_configuration();
+    configuration.database.host = "127.0.0.1".to_string();
+    configuration.database.port = port;
+    configuration.database.acquire_timeout_milliseconds = 200;
+    let pool = get_connection_pool(&configuration.database);
+    let started = Instant::now();
+
+    // Act
+    let (status, report) = readiness_with(pool, Duration::from_secs(2)).await;
+
+    // Assert
+    assert_eq!(503, status);
+    assert_eq!(report["status"], "failing");
+    assert_eq!(report["checks"]["database"]["status"], "failing");
+    // Gave up once the pool did, without waiting for the whole readiness timeout
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[actix_rt::test]
+async fn readiness_gives_up_on_a_database_that_does_not_answer() {
+    // Arrange
+    // Accepts connections but never speaks, like a database stuck behind a broken network
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let mut configuration = test_configuration();
+    configuration.database.host = "127.0.0.1".to_string();
+    configuration.database.port = port;
+    configuration.database.acquire_timeout_milliseconds = 30_000;
+    let pool = get_connection_pool(&configuration.database);
+    let started = Instant::now();
+
+    // Act
+    let (status, report) = readiness_with(pool, Duration::from_millis(300)).await;
+
+    // Assert
+    assert_eq!(503, status);
+    assert_eq!(
+        report["checks"]["database"]["detail"],
+        "No database connection within 300ms"
+    );
+    // The database and migrations checks each wait for the readiness timeout at most
+    assert!(started.elapsed() < Duration::from_secs(2));
+    drop(listener);
+}
@@ -0,0 +1,145 @@
+use crate::client::{gen_body, gen_bundle_body, next_event_containing, spawn_app};
+use restaurant::domain::DatabaseResponse;
+
+async fn stock_of(app: &crate::client::TestClient, ingredient: &str) -> i64 {
+    let inventory: serde_json::Value = app.get_inventory().await.json().await.unwrap();
+    inventory
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["name"] == ingredient)
+        .map(|i| i["stock"].as_i64().unwrap())
+        .expect("The ingredient is not tracked.")
+}
+
+#[actix_rt::test]
+async fn orders_take_their_ingredients_out_of_stock() {
+    // Arrange
+    let app = spawn_app().await;
+    app.put_inventory("beef patty", 10, 0).await;
+    app.put_inventory("cola syrup", 10, 0).await;
+
+    // Act
+    app.post_order(gen_body(1, "hamburger:double", 2)).await;
+    app.post_bundle_order(gen_bundle_body(
+        1,
+        "burger meal",
+        1,
+        &[("drink", "cola:large")],
+    ))
+    .await;
+
+    // Assert
+    assert_eq!(stock_of(&app, "beef patty").await, 10 - 2 * 2 - 1);
+    assert_eq!(stock_of(&app, "cola syrup").await, 10 - 3);
+}
+
+#[actix_rt::test]
+async fn quantities_too_large_to_count_out_of_stock_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.put_inventory("beef patty", 10, 0).await;
+
+    // Act
+    // Two patties each would overflow the stock deduction
+    let line = app
+        .post_order(gen_body(1, "hamburger:double", i32::MAX / 2 + 1))
+        .await;
+    let bundle = app
+        .post_bundle_order(gen_bundle_body(1, "burger meal", i32::MAX, &[]))
+        .await;
+
+    // Assert
+    assert_eq!(400, line.status().as_u16());
+    assert_eq!(400, bundle.status().as_u16());
+    assert_eq!(stock_of(&app, "beef patty").await, 10);
+}
+
+#[actix_rt::test]
+async fn untracked_ingredients_never_run_out() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_order(gen_body(1, "hamburger", 1000)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn orders_that_would_use_more_than_is_left_are_sold_out() {
+    // Arrange
+    let app = spawn_app().await;
+    app.put_inventory("burger bun", 2, 0).await;
+
+    // Act
+    let too_many = app.post_order(gen_body(1, "hamburger", 3)).await;
+    let in_a_bundle = app
+        .post_bundle_order(gen_bundle_body(1, "burger meal", 3, &[]))
+        .await;
+    let enough = app.post_order(gen_body(1, "hamburger", 2)).await;
+
+    // Assert
+    assert_eq!(409, too_many.status().as_u16());
+    let error: serde_json::Value = too_many.json().await.unwrap();
+    assert_eq!(error["code"], "sold_out");
+    assert!(error["error"].as_str().unwrap().contains("burger bun"));
+
+    assert_eq!(409, in_a_bundle.status().as_u16());
+    assert_eq!(200, enough.status().as_u16());
+    assert_eq!(stock_of(&app, "burger bun").await, 0);
+
+    // Nothing was placed for the rejected orders
+    let orders: Vec<DatabaseResponse> = app.query_table(1).await.json().await.unwrap();
+    assert_eq!(1, orders.len());
+}
+
+#[actix_rt::test]
+async fn voided_orders_return_their_ingredients_to_stock() {
+    // Arrange
+    let app = spawn_app().await;
+    app.put_inventory("potato portion", 5, 0).await;
+    app.post_order(gen_body(1, "fries", 2)).await;
+    app.post_bundle_order(gen_bundle_body(1, "burger meal", 1, &[]))
+        .await;
+    assert_eq!(stock_of(&app, "potato portion").await, 2);
+
+    // Act
+    app.delete_with_item(1, "fries".to_string()).await;
+    app.delete_with_item(1, "burger meal".to_string()).await;
+
+    // Assert
+    assert_eq!(stock_of(&app, "potato portion").await, 5);
+}
+
+#[actix_rt::test]
+async fn dropping_to_the_low_stock_threshold_publishes_an_event() {
+    // Arrange
+    let app = spawn_app().await;
+    app.put_inventory("burger bun", 5, 3).await;
+    let mut events = app.get_events().await;
+
+    // Act
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+
+    // Assert
+    let event = next_event_containing(&mut events, "low_stock").await;
+    assert!(event.contains(r#""ingredient":"burger bun""#));
+    assert!(event.contains(r#""stock":3"#));
+}
+
+#[actix_rt::test]
+async fn only_ingredients_used_by_a_recipe_can_be_stocked() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let unknown = app.put_inventory("unicorn horn", 1, 0).await;
+    let negative = app.put_inventory("burger bun", -1, 0).await;
+
+    // Assert
+    assert_eq!(400, unknown.status().as_u16());
+    assert_eq!(400, negative.status().as_u16());
+}
@@ -1,6 +1,42 @@
+// The original tests predate running clippy on every target and are kept as they were written
+#![allow(
+    unused_parens,
+    clippy::bool_assert_comparison,
+    clippy::let_underscore_future,
+    clippy::needless_borrow,
+    clippy::needless_borrows_for_generic_args,
+    clippy::nonminimal_bool,
+    clippy::ptr_arg,
+    clippy::single_component_path_imports,
+    clippy::useless_conversion
+)]
+
+mod auth;
+mod availability;
+mod bundles;
 mod client;
 mod comparison;
+mod courses;
 mod delete;
 mod health_check;
+mod inventory;
+mod metrics;
+mod migrations;
+mod modifiers;
 mod orders;
+mod payments;
+mod printing;
+mod promotions;
 mod queries;
+mod rate_limit;
+mod receipts;
+mod redaction;
+mod request_id;
+mod reservations;
+mod runtime_settings;
+mod schedules;
+mod shutdown;
+mod tables;
+mod tax;
+mod telemetry;
+mod timeouts;
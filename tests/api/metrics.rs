@@ -0,0 +1,126 @@
+use crate::client::{
+    gen_body, gen_bundle_body, noon, spawn_app, spawn_app_with_clock, spawn_app_with_configuration,
+    test_configuration,
+};
+use chrono::Duration;
+use restaurant::clock::FixedClock;
+use restaurant::domain::DatabaseResponse;
+use std::sync::Arc;
+
+#[actix_rt::test]
+async fn metrics_count_requests_and_orders() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+    app.post_order(gen_body(1, "unicorn", 1)).await;
+
+    // Act
+    let response = app.get_metrics().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body = response.text().await.unwrap();
+    assert!(body.contains(r#"http_requests_total{method="POST",route="/order",status="200"} 1"#));
+    assert!(body.contains(r#"http_requests_total{method="POST",route="/order",status="400"} 1"#));
+    assert!(body.contains(
+        r#"http_request_duration_seconds_count{method="POST",route="/order",status="200"} 1"#
+    ));
+    assert!(body.contains(r#"orders_placed_total{item="hamburger"} 1"#));
+    assert!(body.contains(r#"order_validation_failures_total{reason="invalid_item"} 1"#));
+    // The hamburger has only just been placed so it is still being prepared
+    assert!(body.contains(r#"kitchen_backlog_orders{item="hamburger"} 1"#));
+    assert!(body.contains(r#"kitchen_backlog_orders{item="fries"} 0"#));
+    assert!(body.contains("db_pool_idle_connections"));
+}
+
+#[actix_rt::test]
+async fn metrics_count_voided_orders() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_order(gen_body(1, "fries", 1)).await;
+    let saved = app
+        .query_table(1)
+        .await
+        .json::<Vec<DatabaseResponse>>()
+        .await
+        .unwrap();
+
+    // Act
+    app.delete_with_id(saved[0].id).await;
+    let body = app.get_metrics().await.text().await.unwrap();
+
+    // Assert
+    assert!(body.contains("order_voids_total 1"));
+    assert!(body
+        .contains(r#"http_requests_total{method="DELETE",route="/delete/{id}",status="200"} 1"#));
+}
+
+#[actix_rt::test]
+async fn the_kitchen_backlog_forgets_lines_that_are_gone() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_bundle_order(gen_bundle_body(1, "burger meal", 1, &[]))
+        .await
+        .error_for_status()
+        .unwrap();
+    let before = app.get_metrics().await.text().await.unwrap();
+    let saved = app
+        .query_table(1)
+        .await
+        .json::<Vec<DatabaseResponse>>()
+        .await
+        .unwrap();
+    let meal = saved
+        .iter()
+        .find(|line| line.item == "burger meal")
+        .unwrap();
+
+    // Act
+    app.delete_with_id(meal.id).await;
+    let after = app.get_metrics().await.text().await.unwrap();
+
+    // Assert
+    assert!(before.contains(r#"kitchen_backlog_orders{item="burger meal"} 1"#));
+    assert!(!after.contains(r#"kitchen_backlog_orders{item="burger meal"}"#));
+    assert!(after.contains(r#"kitchen_backlog_orders{item="fries"} 0"#));
+}
+
+#[actix_rt::test]
+async fn the_kitchen_backlog_goes_by_the_application_clock() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_with_clock(clock.clone()).await;
+    app.post_order(gen_body(1, "hamburger", 1))
+        .await
+        .error_for_status()
+        .unwrap();
+    let before = app.get_metrics().await.text().await.unwrap();
+
+    // Act
+    clock.advance(Duration::hours(2));
+    let after = app.get_metrics().await.text().await.unwrap();
+
+    // Assert
+    assert!(before.contains(r#"kitchen_backlog_orders{item="hamburger"} 1"#));
+    assert!(after.contains(r#"kitchen_backlog_orders{item="hamburger"} 0"#));
+}
+
+#[actix_rt::test]
+async fn metrics_are_served_on_the_admin_port_when_configured() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.application.admin_port = Some(0);
+    let app = spawn_app_with_configuration(configuration).await;
+
+    // Act
+    let public = reqwest::Client::new()
+        .get(format!("{}/metrics", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let admin = app.get_metrics().await;
+
+    // Assert
+    assert_eq!(404, public.status().as_u16());
+    assert_eq!(200, admin.status().as_u16());
+}
@@ -0,0 +1,71 @@
+use crate::client::{create_database, test_configuration};
+use restaurant::migration::{pending_migrations, run_migrations, MIGRATOR};
+use restaurant::startup::Application;
+
+#[actix_rt::test]
+async fn application_migrates_a_fresh_database_on_startup() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.database.migrate_on_startup = true;
+    let pool = create_database(&configuration.database).await;
+
+    // Act
+    let application = Application::build(configuration).await;
+
+    // Assert
+    assert!(application.is_ok());
+    assert!(pending_migrations(&pool).await.unwrap().is_empty());
+    // The orders table exists and can be queried
+    sqlx::query("SELECT * FROM orders")
+        .fetch_all(&pool)
+        .await
+        .expect("The orders table was not created.");
+}
+
+#[actix_rt::test]
+async fn application_refuses_to_start_when_schema_is_behind() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.database.migrate_on_startup = false;
+    create_database(&configuration.database).await;
+
+    // Act
+    let application = Application::build(configuration).await;
+
+    // Assert
+    assert!(application.is_err());
+}
+
+#[actix_rt::test]
+async fn dry_run_lists_pending_migrations_without_applying_them() {
+    // Arrange
+    let configuration = test_configuration();
+    let pool = create_database(&configuration.database).await;
+
+    // Act
+    let pending = pending_migrations(&pool).await.unwrap();
+
+    // Assert
+    // Every embedded migration is pending on an empty database
+    assert_eq!(MIGRATOR.iter().count(), pending.len());
+    // Listing did not create the bookkeeping table
+    let has_migrations_table: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(!has_migrations_table);
+}
+
+#[actix_rt::test]
+async fn no_migrations_are_pending_after_migrating() {
+    // Arrange
+    let configuration = test_configuration();
+    let pool = create_database(&configuration.database).await;
+
+    // Act
+    run_migrations(&pool).await.unwrap();
+
+    // Assert
+    assert!(pending_migrations(&pool).await.unwrap().is_empty());
+}
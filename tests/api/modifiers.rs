@@ -0,0 +1,105 @@
+use crate::client::{gen_body, gen_body_with_modifiers, noon, spawn_app, spawn_app_with_clock};
+use chrono::Duration;
+use restaurant::clock::FixedClock;
+use restaurant::domain::DatabaseResponse;
+use std::sync::Arc;
+
+#[actix_rt::test]
+async fn order_with_modifiers_and_a_note_is_stored() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = gen_body_with_modifiers(
+        1,
+        "hamburger",
+        1,
+        &["extra cheese", "no onions"],
+        "well done",
+    );
+
+    // Act
+    let response = app.post_order(body).await;
+    let orders: Vec<DatabaseResponse> = app
+        .query_table(1)
+        .await
+        .json()
+        .await
+        .expect("Failed to parse orders.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(1, orders.len());
+    // Modifiers come back in menu order, not the order they were sent in
+    assert_eq!(orders[0].modifiers, vec!["no onions", "extra cheese"]);
+    assert_eq!(orders[0].note.as_deref(), Some("well done"));
+    assert_eq!(orders[0].unit_price_cents, 950);
+}
+
+#[actix_rt::test]
+async fn order_returns_a_400_for_invalid_modifiers_or_notes() {
+    // Arrange
+    let app = spawn_app().await;
+    let long_note = "a".repeat(201);
+    let test_cases = vec![
+        (
+            gen_body_with_modifiers(1, "hamburger", 1, &["no ice"], ""),
+            "a modifier of another item",
+        ),
+        (
+            gen_body_with_modifiers(1, "fries", 1, &["ketchup", "mayonnaise"], ""),
+            "too many choices in a group",
+        ),
+        (
+            gen_body_with_modifiers(1, "hamburger", 1, &[], &long_note),
+            "a note that is too long",
+        ),
+        (
+            gen_body_with_modifiers(1, "hamburger", 1, &[], "{no salt}"),
+            "a note with forbidden characters",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_order(body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the payload had {}.",
+            description
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn bill_charges_each_order_at_its_price() {
+    // Arrange
+    // Prices depend on the time of day, and lines are listed in the order they were placed
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_with_clock(clock.clone()).await;
+    app.post_order(gen_body_with_modifiers(
+        4,
+        "hamburger",
+        2,
+        &["extra cheese"],
+        "",
+    ))
+    .await;
+    clock.advance(Duration::minutes(1));
+    app.post_order(gen_body(4, "cola", 1)).await;
+    // Another table's orders are not on the bill
+    app.post_order(gen_body(5, "water", 3)).await;
+
+    // Act
+    let response = app.get_bill(4).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let bill: serde_json::Value = response.json().await.expect("Failed to parse bill.");
+    assert_eq!(bill["table_no"], 4);
+    assert_eq!(bill["lines"].as_array().unwrap().len(), 2);
+    assert_eq!(bill["lines"][0]["total_cents"], 1900);
+    assert_eq!(bill["lines"][1]["total_cents"], 250);
+    assert_eq!(bill["total_cents"], 2150);
+}
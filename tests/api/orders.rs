@@ -134,6 +134,36 @@ async fn order_fails_with_invalid_item() {
     assert_eq!(400, response.status().as_u16());
 }
 
+#[actix_rt::test]
+async fn order_fails_with_a_variant_of_another_item() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            gen_body(1, "hamburger:large", 1),
+            "a drink size on a burger",
+        ),
+        (
+            gen_body(1, "water:double", 1),
+            "a variant of an item sold one way",
+        ),
+        (gen_body(1, "cola:", 1), "an empty variant"),
+    ];
+
+    // Act
+    for (invalid_body, error_message) in test_cases {
+        let response = app.post_order(invalid_body.into()).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the payload had {}.",
+            error_message
+        )
+    }
+}
+
 #[actix_rt::test]
 async fn order_fails_with_negative_numbers() {
     // Arrange
@@ -0,0 +1,346 @@
+use crate::client::{gen_body, noon, spawn_app_with, TestClient};
+use restaurant::clock::FixedClock;
+use restaurant::domain::DatabaseResponse;
+use restaurant::payment_provider::MockProvider;
+use restaurant::payments::{Account, Payment};
+use restaurant::startup::Dependencies;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// An app taking card payments through `provider`, with a party at table 1 that ordered dinner
+async fn spawn_app_with_party(provider: Arc<MockProvider>) -> (TestClient, Uuid) {
+    let app = spawn_app_with(Dependencies {
+        clock: Arc::new(FixedClock::new(noon())),
+        payments: provider,
+        ..Dependencies::default()
+    })
+    .await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_body(1, "hamburger", 2)).await;
+    app.post_order(gen_body(1, "cola", 2)).await;
+    (app, session_id)
+}
+
+async fn account(app: &TestClient, session_id: Uuid) -> Account {
+    let response = app.get_payments(session_id).await;
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+async fn pay(app: &TestClient, session_id: Uuid, payment: serde_json::Value) -> Payment {
+    let response = app.post_payment(session_id, payment).await;
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+async fn error_code(response: reqwest::Response, status: u16) -> String {
+    assert_eq!(status, response.status().as_u16());
+    let error: serde_json::Value = response.json().await.unwrap();
+    error["code"].as_str().unwrap_or_default().to_string()
+}
+
+#[actix_rt::test]
+async fn a_bill_can_be_split_across_tenders_and_the_session_closed_once_paid() {
+    // Arrange
+    let provider = Arc::new(MockProvider::new());
+    let (app, session_id) = spawn_app_with_party(provider.clone()).await;
+    let total = account(&app, session_id).await.total_cents;
+    assert!(total > 1000);
+
+    // Act
+    let cash = pay(
+        &app,
+        session_id,
+        json!({ "tender": "cash", "amount_cents": 1000, "tendered_cents": 2000 }),
+    )
+    .await;
+    let card = pay(
+        &app,
+        session_id,
+        json!({ "tender": "card", "amount_cents": total - 1000, "tip_cents": 200 }),
+    )
+    .await;
+    let response = app.close_session(session_id).await;
+
+    // Assert
+    assert_eq!(cash.change_cents, 1000);
+    assert_eq!(cash.provider, None);
+    assert_eq!(card.provider.as_deref(), Some("mock"));
+    assert_eq!(card.reference.as_deref(), Some("mock-1"));
+    assert_eq!(provider.charges()[0].amount_cents, total - 1000 + 200);
+
+    assert_eq!(200, response.status().as_u16());
+    let account: Account = response.json().await.unwrap();
+    assert_eq!(account.balance_cents, 0);
+    assert_eq!(account.paid_cents, total);
+    assert_eq!(account.tips_cents, 200);
+    assert_eq!(account.closed_at, Some(noon()));
+
+    // The table is free for the next party
+    let tables: Vec<serde_json::Value> = app.get_tables().await.json().await.unwrap();
+    assert_eq!(tables[0]["session"], serde_json::Value::Null);
+    let audit = sqlx::query!(
+        "SELECT action FROM audit_log WHERE action IN ('payment_recorded', 'session_closed')"
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(audit.len(), 3);
+}
+
+#[actix_rt::test]
+async fn a_session_cannot_close_with_a_balance_outstanding() {
+    // Arrange
+    let (app, session_id) = spawn_app_with_party(Arc::new(MockProvider::new())).await;
+    pay(
+        &app,
+        session_id,
+        json!({ "tender": "cash", "amount_cents": 500 }),
+    )
+    .await;
+
+    // Act
+    let response = app.close_session(session_id).await;
+
+    // Assert
+    assert_eq!(error_code(response, 409).await, "balance_outstanding");
+    let account = account(&app, session_id).await;
+    assert_eq!(account.closed_at, None);
+    assert_eq!(account.balance_cents, account.total_cents - 500);
+}
+
+#[actix_rt::test]
+async fn paying_more_than_the_balance_is_rejected() {
+    // Arrange
+    let (app, session_id) = spawn_app_with_party(Arc::new(MockProvider::new())).await;
+    let total = account(&app, session_id).await.total_cents;
+
+    // Act
+    let response = app
+        .post_payment(
+            session_id,
+            json!({ "tender": "card", "amount_cents": total + 1 }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(error_code(response, 409).await, "overpayment");
+    assert!(account(&app, session_id).await.payments.is_empty());
+}
+
+#[actix_rt::test]
+async fn a_declined_card_records_no_payment() {
+    // Arrange
+    let provider = Arc::new(MockProvider::new());
+    let (app, session_id) = spawn_app_with_party(provider.clone()).await;
+    provider.decline("insufficient funds");
+
+    // Act
+    let response = app
+        .post_payment(session_id, json!({ "tender": "card", "amount_cents": 500 }))
+        .await;
+
+    // Assert
+    assert_eq!(error_code(response, 402).await, "declined");
+    assert!(account(&app, session_id).await.payments.is_empty());
+}
+
+#[actix_rt::test]
+async fn a_charge_that_cannot_be_recorded_is_refunded() {
+    // Arrange
+    let provider = Arc::new(MockProvider::new());
+    let (app, session_id) = spawn_app_with_party(provider.clone()).await;
+    // The database fails once the card has been charged
+    sqlx::raw_sql(
+        r#"
+    CREATE FUNCTION reject_payments() RETURNS trigger AS $$
+    BEGIN
+        RAISE EXCEPTION 'the database went away';
+    END;
+    $$ LANGUAGE plpgsql;
+    CREATE TRIGGER reject_payments BEFORE INSERT ON audit_log
+    FOR EACH ROW WHEN (NEW.action = 'payment_recorded') EXECUTE FUNCTION reject_payments();
+    "#,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let response = app
+        .post_payment(
+            session_id,
+            json!({ "tender": "card", "amount_cents": 500, "tip_cents": 50 }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(500, response.status().as_u16());
+    assert_eq!(provider.charges().len(), 1);
+    let refunds = provider.refunds();
+    assert_eq!(refunds.len(), 1);
+    assert_eq!(refunds[0].amount_cents, 550);
+    assert_eq!(refunds[0].reference.as_deref(), Some("mock-1"));
+    let account = account(&app, session_id).await;
+    assert!(account.payments.is_empty());
+    assert_eq!(account.balance_cents, account.total_cents);
+}
+
+#[actix_rt::test]
+async fn a_refund_goes_back_through_the_provider_and_reopens_the_balance() {
+    // Arrange
+    let provider = Arc::new(MockProvider::new());
+    let (app, session_id) = spawn_app_with_party(provider.clone()).await;
+    let total = account(&app, session_id).await.total_cents;
+    let card = pay(
+        &app,
+        session_id,
+        json!({ "tender": "card", "amount_cents": total }),
+    )
+    .await;
+
+    // Act
+    let response = app.refund_payment(card.id, Some(300)).await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let refund: Payment = response.json().await.unwrap();
+    assert_eq!(refund.refund_of, Some(card.id));
+    assert_eq!(refund.amount_cents, -300);
+    assert_eq!(provider.refunds()[0].reference.as_deref(), Some("mock-1"));
+    assert_eq!(account(&app, session_id).await.balance_cents, 300);
+
+    let too_much = app.refund_payment(card.id, Some(total)).await;
+    assert_eq!(error_code(too_much, 409).await, "refund_exceeds_payment");
+    let of_refund = app.refund_payment(refund.id, None).await;
+    assert_eq!(400, of_refund.status().as_u16());
+
+    // The rest of the payment is refunded when no amount is given
+    let rest: Payment = app
+        .refund_payment(card.id, None)
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(rest.amount_cents, 300 - total);
+    assert_eq!(account(&app, session_id).await.balance_cents, total);
+}
+
+#[actix_rt::test]
+async fn invalid_payments_are_rejected() {
+    // Arrange
+    let (app, session_id) = spawn_app_with_party(Arc::new(MockProvider::new())).await;
+    let test_cases = vec![
+        (
+            json!({ "tender": "cheque", "amount_cents": 500 }),
+            "unknown tender",
+        ),
+        (
+            json!({ "tender": "cash", "amount_cents": 0 }),
+            "nothing to pay",
+        ),
+        (
+            json!({ "tender": "cash", "amount_cents": 500, "tendered_cents": 400 }),
+            "too little cash",
+        ),
+        (
+            json!({ "tender": "card", "amount_cents": 500, "tendered_cents": 600 }),
+            "change on a card",
+        ),
+        (
+            json!({ "tender": "voucher", "amount_cents": 500 }),
+            "a voucher without its code",
+        ),
+        (
+            json!({ "tender": "card", "amount_cents": i64::MAX, "tip_cents": 1 }),
+            "an amount and tip too large to add up",
+        ),
+    ];
+
+    for (payment, description) in test_cases {
+        // Act
+        let response = app.post_payment(session_id, payment).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject a payment with {}.",
+            description
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn closed_and_unknown_sessions_take_no_payments() {
+    // Arrange
+    let (app, _) = spawn_app_with_party(Arc::new(MockProvider::new())).await;
+    // Nothing was ordered at table 2, so its party can leave straight away
+    let session_id = app.seat_walk_in(2, 2).await;
+    app.close_session(session_id).await;
+
+    // Act
+    let closed = app
+        .post_payment(session_id, json!({ "tender": "cash", "amount_cents": 100 }))
+        .await;
+    let unknown = app
+        .post_payment(
+            Uuid::new_v4(),
+            json!({ "tender": "cash", "amount_cents": 100 }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(error_code(closed, 409).await, "session_closed");
+    assert_eq!(404, unknown.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn a_paid_session_refuses_voids() {
+    // Arrange
+    let (app, session_id) = spawn_app_with_party(Arc::new(MockProvider::new())).await;
+    let total = account(&app, session_id).await.total_cents;
+    pay(
+        &app,
+        session_id,
+        json!({ "tender": "cash", "amount_cents": total }),
+    )
+    .await;
+    let lines: Vec<DatabaseResponse> = app.query_table(1).await.json().await.unwrap();
+
+    // Act
+    let by_id = app.delete_with_id(lines[0].id).await;
+    let by_item = app.delete_with_item(1, "cola".to_string()).await;
+
+    // Assert
+    assert_eq!(error_code(by_id, 409).await, "session_paid");
+    assert_eq!(error_code(by_item, 409).await, "session_paid");
+    assert_eq!(account(&app, session_id).await.total_cents, total);
+}
+
+#[actix_rt::test]
+async fn a_closed_session_refuses_voids_and_refunds() {
+    // Arrange
+    let (app, session_id) = spawn_app_with_party(Arc::new(MockProvider::new())).await;
+    let total = account(&app, session_id).await.total_cents;
+    let payment = pay(
+        &app,
+        session_id,
+        json!({ "tender": "card", "amount_cents": total }),
+    )
+    .await;
+    let lines: Vec<DatabaseResponse> = app.query_table(1).await.json().await.unwrap();
+    assert_eq!(200, app.close_session(session_id).await.status().as_u16());
+
+    // Act
+    let void = app.delete_with_id(lines[0].id).await;
+    let refund = app.refund_payment(payment.id, None).await;
+
+    // Assert
+    assert_eq!(error_code(void, 409).await, "session_closed");
+    assert_eq!(error_code(refund, 409).await, "session_closed");
+    let account = account(&app, session_id).await;
+    assert_eq!(account.total_cents, total);
+    assert_eq!(account.paid_cents, total);
+}
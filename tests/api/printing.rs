@@ -0,0 +1,335 @@
+use crate::client::{
+    configure_database, gen_body, gen_body_with_modifiers, gen_bundle_body, gen_held_body, noon,
+    start_app_with, test_configuration, TestClient,
+};
+use chrono::Duration;
+use chrono_tz::Tz;
+use futures::future::BoxFuture;
+use restaurant::clock::FixedClock;
+use restaurant::configuration::{PrinterKind, Settings};
+use restaurant::domain::Station;
+use restaurant::id_generator::SequentialIds;
+use restaurant::printer::{FakePrinter, PrinterSink, PrinterUnavailable};
+use restaurant::printing::print_due_tickets;
+use restaurant::startup::Dependencies;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+// Tickets are checked for every 10 milliseconds and given up on after failing twice
+fn printing_configuration() -> Settings {
+    let mut configuration = test_configuration();
+    configuration.printing.poll_interval_milliseconds = 10;
+    configuration.printing.max_attempts = 2;
+    configuration.printing.retry_seconds = 60;
+    configuration
+}
+
+// Tickets queued at the same time print in the order of their sequential ids
+async fn spawn_app_printing_to(printer: Arc<FakePrinter>, clock: Arc<FixedClock>) -> TestClient {
+    let configuration = printing_configuration();
+    configure_database(&configuration.database).await;
+    start_app_with(
+        configuration,
+        Dependencies {
+            clock,
+            ids: Arc::new(SequentialIds::new()),
+            printer: Some(printer),
+            ..Dependencies::default()
+        },
+    )
+    .await
+}
+
+// Holds on to every ticket until it is let go, like a printer taking its time
+#[derive(Default)]
+struct SlowPrinter {
+    go: Notify,
+    started: AtomicUsize,
+    finished: AtomicUsize,
+}
+
+impl PrinterSink for SlowPrinter {
+    fn print<'a>(
+        &'a self,
+        _station: Station,
+        _job_id: Uuid,
+        _bytes: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), PrinterUnavailable>> {
+        Box::pin(async move {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            self.go.notified().await;
+            self.finished.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        })
+    }
+}
+
+// Tickets are printed in the background, so tests wait for them for up to five seconds
+async fn eventually<F: Future<Output = bool>>(mut condition: impl FnMut() -> F) {
+    for _ in 0..200 {
+        if condition().await {
+            return;
+        }
+        actix_web::rt::time::sleep(std::time::Duration::from_millis(25)).await;
+    }
+    panic!("The print queue did not get there in time.");
+}
+
+async fn print_jobs(app: &TestClient, status: Option<&str>) -> Vec<Value> {
+    let response = app.get_print_jobs(status).await;
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).to_string()
+}
+
+#[actix_rt::test]
+async fn a_ticket_is_printed_at_each_station_an_order_needs() {
+    // Arrange
+    let printer = Arc::new(FakePrinter::new());
+    let app = spawn_app_printing_to(printer.clone(), Arc::new(FixedClock::new(noon()))).await;
+
+    // Act
+    app.post_order(gen_body_with_modifiers(
+        3,
+        "hamburger",
+        2,
+        &["no onions"],
+        "well done",
+    ))
+    .await
+    .error_for_status()
+    .unwrap();
+    app.post_bundle_order(gen_bundle_body(3, "burger meal", 1, &[]))
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Assert
+    eventually(|| async { printer.printed().len() == 4 }).await;
+    let printed = printer.printed();
+    let stations: Vec<Station> = printed.iter().map(|ticket| ticket.station).collect();
+    assert_eq!(
+        stations,
+        vec![Station::Grill, Station::Grill, Station::Fryer, Station::Bar]
+    );
+    let hamburger = text(&printed[0].bytes);
+    assert!(hamburger.contains("TABLE 3"));
+    assert!(hamburger.contains("GRILL - NEW ORDER"));
+    assert!(hamburger.contains("2 x hamburger (single)"));
+    assert!(hamburger.contains("  + no onions"));
+    assert!(hamburger.contains("  NOTE: well done"));
+    // The bundle's own line is never printed, only what it is made of
+    assert!(!printed
+        .iter()
+        .any(|ticket| text(&ticket.bytes).contains("burger meal")));
+
+    eventually(|| async { print_jobs(&app, Some("printed")).await.len() == 4 }).await;
+    let job = &print_jobs(&app, None).await[0];
+    assert_eq!(job["id"], printed[0].job_id.to_string());
+    assert_eq!(job["attempts"], 1);
+    assert_eq!(job["ticket"]["lines"][0]["item"], "hamburger");
+}
+
+#[actix_rt::test]
+async fn held_lines_print_when_their_course_is_fired() {
+    // Arrange
+    let printer = Arc::new(FakePrinter::new());
+    let app = spawn_app_printing_to(printer.clone(), Arc::new(FixedClock::new(noon()))).await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_held_body(1, "hamburger", 2, "main"))
+        .await
+        .error_for_status()
+        .unwrap();
+    app.post_order(gen_held_body(1, "fries", 1, "main"))
+        .await
+        .error_for_status()
+        .unwrap();
+    assert!(print_jobs(&app, None).await.is_empty());
+
+    // Act
+    app.fire_course(session_id, "main")
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Assert
+    eventually(|| async { printer.printed().len() == 2 }).await;
+    let grill = text(&printer.printed()[0].bytes);
+    assert!(grill.contains("GRILL - FIRE MAIN"));
+    assert!(grill.contains("2 x hamburger (single)"));
+    assert!(text(&printer.printed()[1].bytes).contains("FRYER - FIRE MAIN"));
+}
+
+#[actix_rt::test]
+async fn a_failing_printer_is_retried_until_the_ticket_is_given_up_on() {
+    // Arrange
+    let printer = Arc::new(FakePrinter::new());
+    printer.jam("out of paper");
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_printing_to(printer.clone(), clock.clone()).await;
+
+    // Act
+    app.post_order(gen_body(1, "cola", 1))
+        .await
+        .error_for_status()
+        .unwrap();
+    eventually(|| async { print_jobs(&app, None).await[0]["attempts"] == 1 }).await;
+    // Not tried again before the retry is due
+    actix_web::rt::time::sleep(std::time::Duration::from_millis(100)).await;
+    let waiting = print_jobs(&app, None).await;
+    clock.advance(Duration::seconds(60));
+    eventually(|| async { print_jobs(&app, Some("failed")).await.len() == 1 }).await;
+
+    // Assert
+    assert_eq!(waiting[0]["status"], "pending");
+    assert_eq!(waiting[0]["attempts"], 1);
+    assert_eq!(waiting[0]["last_error"], "out of paper");
+    let failed = &print_jobs(&app, Some("failed")).await[0];
+    assert_eq!(failed["attempts"], 2);
+    assert!(printer.printed().is_empty());
+
+    // Act
+    printer.fix();
+    let id: Uuid = failed["id"].as_str().unwrap().parse().unwrap();
+    let retried = app.retry_print_job(id).await;
+    let again = app.retry_print_job(id).await;
+
+    // Assert
+    assert_eq!(200, retried.status().as_u16());
+    assert_eq!(409, again.status().as_u16());
+    eventually(|| async { printer.printed().len() == 1 }).await;
+    assert_eq!(printer.printed()[0].job_id, id);
+    assert_eq!(
+        404,
+        app.retry_print_job(Uuid::new_v4()).await.status().as_u16()
+    );
+    assert_eq!(
+        400,
+        app.get_print_jobs(Some("lost")).await.status().as_u16()
+    );
+}
+
+#[actix_rt::test]
+async fn tickets_are_sent_to_network_printers_as_escpos() {
+    // Arrange
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let mut configuration = printing_configuration();
+    configuration.printing.sink = PrinterKind::Tcp;
+    configuration.printing.printers = Station::ALL
+        .into_iter()
+        .map(|station| (station, address.clone()))
+        .collect();
+    configure_database(&configuration.database).await;
+    let app = start_app_with(configuration, Dependencies::default()).await;
+
+    // Act
+    app.post_order(gen_body(2, "fries", 1))
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Assert
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut bytes = vec![];
+    stream.read_to_end(&mut bytes).await.unwrap();
+    assert_eq!(&bytes[..2], &[0x1b, b'@']);
+    assert!(text(&bytes).contains("FRYER - NEW ORDER"));
+    assert!(text(&bytes).contains("1 x fries"));
+    eventually(|| async { print_jobs(&app, Some("printed")).await.len() == 1 }).await;
+}
+
+#[actix_rt::test]
+async fn tickets_can_be_written_to_files() {
+    // Arrange
+    let directory = std::env::temp_dir().join(Uuid::new_v4().to_string());
+    let mut configuration = printing_configuration();
+    configuration.printing.sink = PrinterKind::File;
+    configuration.printing.directory = directory.clone();
+    configure_database(&configuration.database).await;
+    let app = start_app_with(configuration, Dependencies::default()).await;
+
+    // Act
+    app.post_order(gen_body(5, "water", 1))
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Assert
+    eventually(|| async { print_jobs(&app, Some("printed")).await.len() == 1 }).await;
+    let id = print_jobs(&app, None).await[0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let bytes = std::fs::read(directory.join("bar").join(format!("{}.escpos", id))).unwrap();
+    assert!(text(&bytes).contains("TABLE 5"));
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[actix_rt::test]
+async fn a_ticket_is_claimed_rather_than_locked_while_it_prints() {
+    // Arrange
+    let configuration = printing_configuration();
+    let settings = configuration.printing.clone();
+    configure_database(&configuration.database).await;
+    let printer = Arc::new(SlowPrinter::default());
+    let app = start_app_with(
+        configuration,
+        Dependencies {
+            clock: Arc::new(FixedClock::new(noon())),
+            printer: Some(printer.clone()),
+            ..Dependencies::default()
+        },
+    )
+    .await;
+    app.post_order(gen_body(1, "cola", 1))
+        .await
+        .error_for_status()
+        .unwrap();
+    eventually(|| async { printer.started.load(Ordering::SeqCst) == 1 }).await;
+
+    // Act
+    let claimed = print_jobs(&app, None).await;
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    let unlocked = sqlx::query!("SELECT id FROM print_jobs FOR UPDATE NOWAIT")
+        .fetch_all(&mut *transaction)
+        .await;
+    transaction.rollback().await.unwrap();
+    let other = FakePrinter::new();
+    let skipped = print_due_tickets(&app.db_pool, &other, Tz::UTC, &settings, noon())
+        .await
+        .unwrap();
+    // Once the claim runs out another instance takes the ticket over
+    let later = noon() + settings.lease() + Duration::seconds(1);
+    let taken_over = print_due_tickets(&app.db_pool, &other, Tz::UTC, &settings, later)
+        .await
+        .unwrap();
+    printer.go.notify_one();
+    eventually(|| async { printer.finished.load(Ordering::SeqCst) == 1 }).await;
+
+    // Assert
+    assert_eq!(claimed[0]["status"], "printing");
+    assert!(claimed[0]["leased_until"].is_string());
+    assert!(unlocked.is_ok(), "The ticket was locked while it printed.");
+    assert_eq!(skipped, 0);
+    assert_eq!(taken_over, 1);
+    assert_eq!(other.printed().len(), 1);
+    // The slow printer's result came after the claim ran out, so it is not recorded
+    eventually(|| async {
+        let job = &print_jobs(&app, None).await[0];
+        job["status"] == "printed" && job["attempts"] == 1
+    })
+    .await;
+    let job = &print_jobs(&app, None).await[0];
+    assert_eq!(job["printed_at"], serde_json::json!(later));
+    assert_eq!(job["leased_until"], Value::Null);
+}
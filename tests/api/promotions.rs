@@ -0,0 +1,336 @@
+use crate::client::{
+    configure_database, gen_body, noon, start_app_with_clock, test_configuration, TestClient,
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use restaurant::clock::FixedClock;
+use restaurant::payments::Account;
+use secrecy::Secret;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+// 2026-10-20, the day after `noon`, is a Tuesday
+fn tuesday_noon() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 20, 12, 0, 0).unwrap()
+}
+
+// An app whose managers approve discounts with the code 4321
+async fn spawn_app_on(clock: Arc<FixedClock>) -> TestClient {
+    let mut configuration = test_configuration();
+    configuration.promotions.manager_code = Some(Secret::new("4321".to_string()));
+    configure_database(&configuration.database).await;
+    start_app_with_clock(configuration, clock).await
+}
+
+async fn spawn_app_at(now: DateTime<Utc>) -> TestClient {
+    spawn_app_on(Arc::new(FixedClock::new(now))).await
+}
+
+async fn session_bill(app: &TestClient, session_id: Uuid) -> Value {
+    let response = app.get_session_bill(session_id).await;
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+async fn error_code(response: reqwest::Response, status: u16) -> String {
+    assert_eq!(status, response.status().as_u16());
+    let error: Value = response.json().await.unwrap();
+    error["code"].as_str().unwrap_or_default().to_string()
+}
+
+#[actix_rt::test]
+async fn fries_are_two_for_one_on_tuesdays() {
+    // Arrange
+    let app = spawn_app_at(tuesday_noon()).await;
+    let session_id = app.seat_walk_in(2, 1).await;
+
+    // Act
+    app.post_order(gen_body(1, "fries", 2)).await;
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+
+    // Assert
+    let bill: Value = app.get_bill(1).await.json().await.unwrap();
+    let fries = bill["lines"][0]["unit_price_cents"].as_i64().unwrap();
+    let subtotal = bill["subtotal_cents"].as_i64().unwrap();
+    assert_eq!(bill["discounts"][0]["kind"], "promotion");
+    assert_eq!(bill["discounts"][0]["name"], "2-for-1 fries tuesdays");
+    assert_eq!(bill["discounts"][0]["amount_cents"], fries);
+    assert_eq!(bill["total_cents"], subtotal - fries);
+    assert_eq!(session_bill(&app, session_id).await, bill);
+}
+
+#[actix_rt::test]
+async fn fries_are_full_price_on_other_days() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+
+    // Act
+    app.post_order(gen_body(1, "fries", 2)).await;
+
+    // Assert
+    let bill: Value = app.get_bill(1).await.json().await.unwrap();
+    assert_eq!(bill["discounts"], json!([]));
+    assert_eq!(bill["total_cents"], bill["subtotal_cents"]);
+}
+
+#[actix_rt::test]
+async fn managers_set_up_and_end_promotions() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+    let three_for_two = json!({
+        "target": { "item": "hamburger" },
+        "min_quantity": 3,
+        "reward": { "buy_get_free": { "buy": 3, "free": 1 } },
+        "schedule": { "days": ["Mon"], "from": "11:00:00", "until": "15:00:00" }
+    });
+    let nothing_to_pay_for = json!({
+        "target": { "item": "hamburger" },
+        "min_quantity": 1,
+        "reward": { "buy_get_free": { "buy": 1, "free": 1 } }
+    });
+
+    // Act
+    let saved = app.put_promotion("burger-mondays", three_for_two).await;
+    let invalid = app.put_promotion("free-burgers", nothing_to_pay_for).await;
+    app.post_order(gen_body(1, "hamburger", 3)).await;
+    let during = app.get_bill(1).await.json::<Value>().await.unwrap();
+    let ended = app.delete_promotion("burger-mondays").await;
+    let after = app.get_bill(1).await.json::<Value>().await.unwrap();
+    let unknown = app.delete_promotion("burger-mondays").await;
+
+    // Assert
+    assert_eq!(200, saved.status().as_u16());
+    assert_eq!(400, invalid.status().as_u16());
+    let burger = during["lines"][0]["unit_price_cents"].as_i64().unwrap();
+    assert_eq!(during["discounts"][0]["kind"], "promotion");
+    assert_eq!(during["discounts"][0]["name"], "burger-mondays");
+    assert_eq!(during["discounts"][0]["amount_cents"], burger);
+
+    assert_eq!(200, ended.status().as_u16());
+    assert_eq!(after["discounts"], json!([]));
+    assert_eq!(404, unknown.status().as_u16());
+    // The promotions the restaurant started with are still there
+    let promotions: Vec<Value> = app.get_promotions().await.json().await.unwrap();
+    let names: Vec<&str> = promotions
+        .iter()
+        .map(|promotion| promotion["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        ["2-for-1 fries tuesdays", "10% off a round of 6 drinks"]
+    );
+}
+
+#[actix_rt::test]
+async fn a_voucher_is_a_line_on_the_bill_and_counts_towards_its_limit() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+    app.put_voucher("TENOFF", json!({ "percent_off": 10, "max_uses": 1 }))
+        .await
+        .error_for_status()
+        .unwrap();
+    let first = app.seat_walk_in(2, 1).await;
+    let second = app.seat_walk_in(2, 2).await;
+    app.post_order(gen_body(1, "hamburger", 2)).await;
+
+    // Act
+    let redeemed = app.redeem_voucher(first, "tenoff").await;
+    let used_up = app.redeem_voucher(second, "TENOFF").await;
+
+    // Assert
+    assert_eq!(201, redeemed.status().as_u16());
+    assert_eq!(error_code(used_up, 409).await, "voucher_used_up");
+
+    let bill = session_bill(&app, first).await;
+    let subtotal = bill["subtotal_cents"].as_i64().unwrap();
+    assert_eq!(bill["discounts"][0]["kind"], "voucher");
+    assert_eq!(bill["discounts"][0]["name"], "TENOFF");
+    assert_eq!(bill["discounts"][0]["amount_cents"], (subtotal + 5) / 10);
+
+    let account: Account = app.get_payments(first).await.json().await.unwrap();
+    assert_eq!(json!(account.total_cents), bill["total_cents"]);
+    let audit = sqlx::query!("SELECT details FROM audit_log WHERE action = 'voucher_redeemed'")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(audit.details["voucher_code"], "TENOFF");
+}
+
+#[actix_rt::test]
+async fn a_voucher_only_counts_once_per_party() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+    app.put_voucher("FIVER", json!({ "amount_off_cents": 500 }))
+        .await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.redeem_voucher(session_id, "FIVER").await;
+
+    // Act
+    let again = app.redeem_voucher(session_id, "FIVER").await;
+
+    // Assert
+    assert_eq!(error_code(again, 409).await, "voucher_already_applied");
+    let vouchers: Value = app.get_vouchers().await.json().await.unwrap();
+    assert_eq!(vouchers[0]["uses"], 1);
+}
+
+#[actix_rt::test]
+async fn expired_and_unknown_vouchers_are_rejected() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+    app.put_voucher(
+        "SUMMER",
+        json!({ "percent_off": 20, "valid_until": noon() - Duration::days(1) }),
+    )
+    .await;
+    let session_id = app.seat_walk_in(2, 1).await;
+
+    // Act
+    let expired = app.redeem_voucher(session_id, "SUMMER").await;
+    let unknown = app.redeem_voucher(session_id, "WINTER").await;
+
+    // Assert
+    assert_eq!(error_code(expired, 409).await, "voucher_expired");
+    assert_eq!(404, unknown.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn invalid_vouchers_cannot_be_created() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+    let test_cases = vec![
+        (
+            json!({ "percent_off": 10, "amount_off_cents": 500 }),
+            "both kinds of discount",
+        ),
+        (json!({}), "no discount"),
+        (json!({ "percent_off": 120 }), "more than everything off"),
+        (json!({ "amount_off_cents": 500, "max_uses": 0 }), "no uses"),
+    ];
+
+    for (voucher, description) in test_cases {
+        // Act
+        let response = app.put_voucher("BROKEN", voucher).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject a voucher with {}.",
+            description
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn large_manual_discounts_need_a_managers_code() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_on(clock.clone()).await;
+    let session_id = app.seat_walk_in(4, 3).await;
+    app.post_order(gen_body(3, "hamburger", 4)).await;
+
+    // Act
+    let small = app
+        .post_discount(
+            session_id,
+            json!({ "reason": "cold fries", "amount_off_cents": 500 }),
+        )
+        .await;
+    // Discounts are listed in the order they were given
+    clock.advance(Duration::minutes(1));
+    let unapproved = app
+        .post_discount(
+            session_id,
+            json!({ "reason": "long wait", "percent_off": 50 }),
+        )
+        .await;
+    let wrong_code = app
+        .post_discount(
+            session_id,
+            json!({ "reason": "long wait", "percent_off": 50, "manager_code": "1234" }),
+        )
+        .await;
+    let approved = app
+        .post_discount(
+            session_id,
+            json!({ "reason": "long wait", "percent_off": 50, "manager_code": "4321" }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(201, small.status().as_u16());
+    assert_eq!(error_code(unapproved, 403).await, "approval_required");
+    assert_eq!(error_code(wrong_code, 403).await, "approval_required");
+    assert_eq!(201, approved.status().as_u16());
+    let approved: Value = approved.json().await.unwrap();
+    assert_eq!(approved["manager_approved"], true);
+
+    let bill = session_bill(&app, session_id).await;
+    let subtotal = bill["subtotal_cents"].as_i64().unwrap();
+    assert_eq!(bill["discounts"][0]["amount_cents"], 500);
+    assert_eq!(bill["discounts"][1]["kind"], "manual");
+    assert_eq!(bill["discounts"][1]["name"], "long wait");
+    // Half of what was left after the first discount
+    assert_eq!(
+        bill["discounts"][1]["amount_cents"],
+        (subtotal - 500 + 1) / 2
+    );
+}
+
+#[actix_rt::test]
+async fn discounts_cannot_be_given_to_a_closed_session() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.close_session(session_id).await;
+
+    // Act
+    let discount = app
+        .post_discount(
+            session_id,
+            json!({ "reason": "cold fries", "amount_off_cents": 100 }),
+        )
+        .await;
+    let missing_reason = app
+        .post_discount(
+            Uuid::new_v4(),
+            json!({ "reason": " ", "amount_off_cents": 100 }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(error_code(discount, 409).await, "session_closed");
+    assert_eq!(400, missing_reason.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn a_large_percentage_needs_a_managers_code_before_anything_is_ordered() {
+    // Arrange
+    let app = spawn_app_at(noon()).await;
+    let session_id = app.seat_walk_in(4, 3).await;
+
+    // Act
+    // Worth nothing on an empty bill, but everything the party orders next
+    let everything = app
+        .post_discount(
+            session_id,
+            json!({ "reason": "long wait", "percent_off": 100 }),
+        )
+        .await;
+    let small = app
+        .post_discount(
+            session_id,
+            json!({ "reason": "long wait", "percent_off": 10 }),
+        )
+        .await;
+    app.post_order(gen_body(3, "hamburger", 4)).await;
+
+    // Assert
+    assert_eq!(error_code(everything, 403).await, "approval_required");
+    assert_eq!(201, small.status().as_u16());
+    let bill = session_bill(&app, session_id).await;
+    let subtotal = bill["subtotal_cents"].as_i64().unwrap();
+    assert_eq!(bill["discounts"].as_array().unwrap().len(), 1);
+    assert_eq!(bill["discounts"][0]["amount_cents"], (subtotal + 5) / 10);
+}
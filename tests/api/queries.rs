@@ -1,6 +1,13 @@
-use crate::client::{gen_multi_item_bodies, spawn_app};
+use crate::client::{
+    gen_body, gen_multi_item_bodies, noon, spawn_app, spawn_app_with, spawn_app_with_clock,
+};
 use crate::comparison::{check_response, gen_expected_result};
+use restaurant::clock::FixedClock;
 use restaurant::domain::DatabaseResponse;
+use restaurant::id_generator::SequentialIds;
+use restaurant::rng::{Rng, SeededRng};
+use restaurant::startup::Dependencies;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[actix_rt::test]
@@ -401,3 +408,74 @@ async fn successfully_retrieve_parallel_orders_for_a_specific_table() {
         }
     }
 }
+
+#[actix_rt::test]
+async fn query_item_matches_every_variant_unless_one_is_given() {
+    // Arrange
+    // Outside happy hour, so drinks are at their usual price
+    let app = spawn_app_with_clock(Arc::new(FixedClock::new(noon()))).await;
+    let table_no = 1;
+    let items = [
+        ("cola:small", 1),
+        ("cola:large", 2),
+        ("cola", 1),
+        ("water", 1),
+    ]
+    .to_vec();
+    let bodies = gen_multi_item_bodies(table_no, items);
+    assert!(app.post_parallel_orders(bodies).await);
+
+    // Act
+    let all_colas = app
+        .query_item(table_no, "cola".to_string())
+        .await
+        .json::<Vec<DatabaseResponse>>()
+        .await
+        .unwrap();
+    let large_colas = app
+        .query_item(table_no, "cola:large".to_string())
+        .await
+        .json::<Vec<DatabaseResponse>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(3, all_colas.len());
+    let mut variants: Vec<&str> = all_colas.iter().map(|s| s.variant.as_str()).collect();
+    variants.sort();
+    assert_eq!(variants, vec!["large", "medium", "small"]);
+
+    assert_eq!(1, large_colas.len());
+    assert_eq!(large_colas[0].variant, "large");
+    assert_eq!(large_colas[0].quantity, 2);
+    assert_eq!(large_colas[0].unit_price_cents, 300);
+}
+
+#[actix_rt::test]
+async fn orders_are_saved_with_the_injected_id_and_preparation_time() {
+    // Arrange
+    let app = spawn_app_with(Dependencies {
+        clock: Arc::new(FixedClock::new(noon())),
+        ids: Arc::new(SequentialIds::new()),
+        rng: Arc::new(SeededRng::new(7)),
+        ..Dependencies::default()
+    })
+    .await;
+    // A generator with the same seed makes the same choice the app will make
+    let expected_preparation_time = SeededRng::new(7).gen_range(5..15);
+
+    // Act
+    let response = app.post_order(gen_body(1, "hamburger", 1)).await;
+    assert!(response.status().is_success());
+    let saved = app
+        .query_id(Uuid::from_u128(1))
+        .await
+        .json::<Vec<DatabaseResponse>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(1, saved.len());
+    assert_eq!(saved[0].id, Uuid::from_u128(1));
+    assert_eq!(saved[0].preparation_time, expected_preparation_time);
+}
@@ -0,0 +1,222 @@
+use crate::client::{
+    gen_body, spawn_app_with_configuration, start_app, test_configuration, TestClient,
+};
+use restaurant::configuration::{RateLimit, RateLimitBackend, Settings};
+use restaurant::rate_limit::expire_idle_buckets;
+use secrecy::Secret;
+use std::time::Duration;
+
+// Two orders at once, then practically nothing for the rest of the test
+fn configuration_with_order_limit() -> Settings {
+    let mut configuration = test_configuration();
+    configuration.runtime.rate_limits = vec![RateLimit {
+        group: "orders".into(),
+        routes: vec!["/order".into()],
+        burst: 2,
+        per_second: 0.01,
+    }];
+    configuration
+}
+
+async fn post_order_with_api_key(app: &TestClient, api_key: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/order", &app.address))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Api-Key", api_key)
+        .body(gen_body(1, "hamburger", 1))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn post_order_forwarded_for(app: &TestClient, forwarded_for: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/order", &app.address))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Forwarded-For", forwarded_for)
+        .body(gen_body(1, "hamburger", 1))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn requests_beyond_the_burst_get_a_429_with_retry_after() {
+    // Arrange
+    let app = spawn_app_with_configuration(configuration_with_order_limit()).await;
+
+    // Act
+    let first = app.post_order(gen_body(1, "hamburger", 1)).await;
+    let second = app.post_order(gen_body(1, "fries", 1)).await;
+    let third = app.post_order(gen_body(1, "cola", 1)).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(200, second.status().as_u16());
+    assert_eq!(429, third.status().as_u16());
+    let retry_after: u64 = third.headers()["Retry-After"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after >= 1);
+    let body = third.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(body["retry_after_seconds"], retry_after);
+    assert!(body["request_id"].is_string());
+}
+
+#[actix_rt::test]
+async fn routes_outside_a_group_are_not_limited() {
+    // Arrange
+    let app = spawn_app_with_configuration(configuration_with_order_limit()).await;
+
+    for _ in 0..5 {
+        // Act
+        let response = app.query_all().await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16());
+    }
+}
+
+#[actix_rt::test]
+async fn each_api_key_has_its_own_bucket() {
+    // Arrange
+    let mut configuration = configuration_with_order_limit();
+    configuration.rate_limiting.api_keys = vec![
+        Secret::new("tablet-1".to_string()),
+        Secret::new("tablet-2".to_string()),
+    ];
+    let app = spawn_app_with_configuration(configuration).await;
+    for _ in 0..2 {
+        post_order_with_api_key(&app, "tablet-1").await;
+    }
+
+    // Act
+    let exhausted = post_order_with_api_key(&app, "tablet-1").await;
+    let other = post_order_with_api_key(&app, "tablet-2").await;
+
+    // Assert
+    assert_eq!(429, exhausted.status().as_u16());
+    assert_eq!(200, other.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn made_up_api_keys_do_not_get_a_bucket_of_their_own() {
+    // Arrange
+    let mut configuration = configuration_with_order_limit();
+    configuration.rate_limiting.api_keys = vec![Secret::new("tablet-1".to_string())];
+    let app = spawn_app_with_configuration(configuration).await;
+    for api_key in ["made-up-1", "made-up-2"] {
+        post_order_with_api_key(&app, api_key).await;
+    }
+
+    // Act
+    let made_up = post_order_with_api_key(&app, "made-up-3").await;
+    let without_key = app.post_order(gen_body(1, "fries", 1)).await;
+    let configured = post_order_with_api_key(&app, "tablet-1").await;
+
+    // Assert
+    assert_eq!(429, made_up.status().as_u16());
+    assert_eq!(429, without_key.status().as_u16());
+    assert_eq!(200, configured.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn forwarded_addresses_from_untrusted_clients_are_ignored() {
+    // Arrange
+    let app = spawn_app_with_configuration(configuration_with_order_limit()).await;
+    for forwarded_for in ["203.0.113.1", "203.0.113.2"] {
+        post_order_forwarded_for(&app, forwarded_for).await;
+    }
+
+    // Act
+    let response = post_order_forwarded_for(&app, "203.0.113.3").await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn a_trusted_proxy_passes_on_the_client_address() {
+    // Arrange
+    let mut configuration = configuration_with_order_limit();
+    configuration.rate_limiting.trusted_proxies = vec!["127.0.0.1".parse().unwrap()];
+    let app = spawn_app_with_configuration(configuration).await;
+    for _ in 0..2 {
+        post_order_forwarded_for(&app, "203.0.113.1").await;
+    }
+
+    // Act
+    let same_client = post_order_forwarded_for(&app, "203.0.113.1").await;
+    // The proxy appends the address it saw, whatever the client put in front of it
+    let spoofed = post_order_forwarded_for(&app, "198.51.100.7, 203.0.113.1").await;
+    let other_client = post_order_forwarded_for(&app, "203.0.113.2").await;
+
+    // Assert
+    assert_eq!(429, same_client.status().as_u16());
+    assert_eq!(429, spoofed.status().as_u16());
+    assert_eq!(200, other_client.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn limits_can_be_changed_at_runtime() {
+    // Arrange
+    let app = spawn_app_with_configuration(configuration_with_order_limit()).await;
+    for _ in 0..2 {
+        app.post_order(gen_body(1, "hamburger", 1)).await;
+    }
+
+    // Act
+    app.patch_runtime_settings(serde_json::json!({"rate_limits": []}))
+        .await;
+    let response = app.post_order(gen_body(1, "hamburger", 1)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn postgres_limits_are_shared_between_instances() {
+    // Arrange
+    let mut configuration = configuration_with_order_limit();
+    configuration.rate_limiting.backend = RateLimitBackend::Postgres;
+    let first_instance = spawn_app_with_configuration(configuration.clone()).await;
+    let second_instance = start_app(configuration).await;
+
+    // Act
+    let first = first_instance.post_order(gen_body(1, "hamburger", 1)).await;
+    let second = second_instance.post_order(gen_body(1, "fries", 1)).await;
+    let third = first_instance.post_order(gen_body(1, "cola", 1)).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(200, second.status().as_u16());
+    assert_eq!(429, third.status().as_u16());
+    assert!(third.headers().get("Retry-After").is_some());
+}
+
+#[actix_rt::test]
+async fn idle_postgres_buckets_are_expired() {
+    // Arrange
+    let mut configuration = configuration_with_order_limit();
+    configuration.rate_limiting.backend = RateLimitBackend::Postgres;
+    let app = spawn_app_with_configuration(configuration).await;
+    for _ in 0..2 {
+        app.post_order(gen_body(1, "hamburger", 1)).await;
+    }
+    sqlx::query!("UPDATE rate_limit_buckets SET updated_at = now() - interval '1 hour'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let expired = expire_idle_buckets(&app.db_pool, Duration::from_secs(600))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(expired, 1);
+    let response = app.post_order(gen_body(1, "hamburger", 1)).await;
+    assert_eq!(200, response.status().as_u16());
+}
@@ -0,0 +1,189 @@
+use crate::client::{
+    configure_database, gen_body, gen_body_with_modifiers, gen_takeaway_body, noon,
+    start_app_with_clock, test_configuration, TestClient,
+};
+use chrono::Duration;
+use restaurant::clock::FixedClock;
+use restaurant::configuration::ReceiptSettings;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// Receipts are compared byte for byte, run with `UPDATE_GOLDEN=1` to accept a new layout
+fn assert_golden(name: &str, actual: &[u8]) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "api", "golden", name]
+        .iter()
+        .collect();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).expect("Failed to write golden file.");
+        return;
+    }
+    let expected = std::fs::read(&path).expect("Failed to read golden file.");
+    assert!(
+        expected == actual,
+        "{} does not match, got:\n{}",
+        name,
+        String::from_utf8_lossy(actual)
+    );
+}
+
+async fn spawn_app_on(clock: Arc<FixedClock>) -> TestClient {
+    let mut configuration = test_configuration();
+    configuration.receipts = ReceiptSettings {
+        header: vec!["The Burger Place".to_string(), "Café & Grill".to_string()],
+        footer: vec!["Thank you for your visit!".to_string()],
+        width: 42,
+    };
+    configure_database(&configuration.database).await;
+    start_app_with_clock(configuration, clock).await
+}
+
+// Pays the whole bill in cash and closes the session
+async fn settle(app: &TestClient, session_id: Uuid) {
+    let bill: Value = app.get_session_bill(session_id).await.json().await.unwrap();
+    let total = bill["total_cents"].as_i64().unwrap();
+    app.post_payment(
+        session_id,
+        json!({ "tender": "cash", "amount_cents": total }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+    app.close_session(session_id)
+        .await
+        .error_for_status()
+        .unwrap();
+}
+
+async fn error_code(response: reqwest::Response, status: u16) -> String {
+    assert_eq!(status, response.status().as_u16());
+    let error: Value = response.json().await.unwrap();
+    error["code"].as_str().unwrap_or_default().to_string()
+}
+
+#[actix_rt::test]
+async fn receipts_match_the_golden_files() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_on(clock.clone()).await;
+    let session_id = app.seat_walk_in(2, 4).await;
+    // Lines and payments made at the same time could be listed in any order
+    app.post_order(gen_body_with_modifiers(
+        4,
+        "hamburger:double",
+        1,
+        &["extra cheese"],
+        "",
+    ))
+    .await;
+    clock.advance(Duration::minutes(1));
+    app.post_order(gen_takeaway_body(4, "fries", 2)).await;
+    clock.advance(Duration::minutes(1));
+    app.post_order(gen_body(4, "cola:large", 1)).await;
+    app.post_discount(
+        session_id,
+        json!({ "reason": "birthday", "amount_off_cents": 100 }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+    let bill: Value = app.get_session_bill(session_id).await.json().await.unwrap();
+    let total = bill["total_cents"].as_i64().unwrap();
+    app.post_payment(
+        session_id,
+        json!({ "tender": "card", "amount_cents": 1000, "tip_cents": 150 }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+    clock.advance(Duration::minutes(1));
+    // Cash rounded up to the next five, with change given
+    let cash = total - 1000;
+    app.post_payment(
+        session_id,
+        json!({
+            "tender": "cash",
+            "amount_cents": cash,
+            "tendered_cents": (cash / 500 + 1) * 500,
+        }),
+    )
+    .await
+    .error_for_status()
+    .unwrap();
+    clock.set(noon() + Duration::minutes(50));
+    app.close_session(session_id)
+        .await
+        .error_for_status()
+        .unwrap();
+
+    for (format, file, content_type) in [
+        ("text", "receipt.txt", "text/plain; charset=utf-8"),
+        ("html", "receipt.html", "text/html; charset=utf-8"),
+        ("escpos", "receipt.escpos", "application/octet-stream"),
+    ] {
+        // Act
+        let response = app.get_receipt(4, json!({ "format": format })).await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16(), "{}", format);
+        assert_eq!(
+            response.headers()["content-type"].to_str().unwrap(),
+            content_type
+        );
+        assert_golden(file, &response.bytes().await.unwrap());
+    }
+}
+
+#[actix_rt::test]
+async fn the_receipt_is_the_last_partys_unless_a_session_is_asked_for() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_on(clock.clone()).await;
+    let first = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_body(1, "water", 1)).await;
+    settle(&app, first).await;
+    clock.advance(Duration::minutes(30));
+    let second = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+    settle(&app, second).await;
+
+    // Act
+    let last = app.get_receipt(1, json!({})).await.text().await.unwrap();
+    let earlier = app
+        .get_receipt(1, json!({ "session_id": first }))
+        .await
+        .text()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(last.contains("1 x hamburger (single)"));
+    assert!(!last.contains("water"));
+    assert!(earlier.contains("1 x water"));
+}
+
+#[actix_rt::test]
+async fn there_is_no_receipt_until_the_party_has_left() {
+    // Arrange
+    let app = spawn_app_on(Arc::new(FixedClock::new(noon()))).await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_body(1, "fries", 1)).await;
+
+    // Act
+    let none_closed = app.get_receipt(1, json!({})).await;
+    let open = app
+        .get_receipt(1, json!({ "session_id": session_id }))
+        .await;
+    let other_table = app
+        .get_receipt(2, json!({ "session_id": session_id }))
+        .await;
+    settle(&app, session_id).await;
+    let unknown_format = app.get_receipt(1, json!({ "format": "pdf" })).await;
+
+    // Assert
+    assert_eq!(404, none_closed.status().as_u16());
+    assert_eq!(error_code(open, 409).await, "session_open");
+    assert_eq!(404, other_table.status().as_u16());
+    assert_eq!(400, unknown_format.status().as_u16());
+}
@@ -0,0 +1,37 @@
+use crate::client::{captured_log_lines, spawn_app, test_configuration};
+use restaurant::telemetry::REDACTED;
+use secrecy::{ExposeSecret, Secret};
+use uuid::Uuid;
+
+#[test]
+fn settings_debug_output_does_not_print_the_database_password() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.database.password = Secret::new(Uuid::new_v4().to_string());
+
+    // Act
+    let debug = format!("{:?}", configuration);
+
+    // Assert
+    assert!(!debug.contains(configuration.database.password.expose_secret()));
+    assert!(debug.contains("REDACTED"));
+}
+
+#[actix_rt::test]
+async fn denylisted_fields_are_redacted_from_logs() {
+    // Arrange
+    // Ensures the global subscriber is initialised
+    let _app = spawn_app().await;
+    let marker = Uuid::new_v4().to_string();
+    let phone = "07700 900123";
+
+    // Act
+    tracing::info!(marker = %marker, customer_phone = %phone, "Customer called");
+
+    // Assert
+    let lines = captured_log_lines(&marker);
+    assert_eq!(1, lines.len());
+    assert!(!lines[0].contains(phone));
+    let log = serde_json::from_str::<serde_json::Value>(&lines[0]).unwrap();
+    assert_eq!(log["customer_phone"], REDACTED);
+}
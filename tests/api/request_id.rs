@@ -0,0 +1,128 @@
+use crate::client::{captured_log_lines, gen_body, spawn_app};
+use uuid::Uuid;
+
+#[actix_rt::test]
+async fn a_supplied_request_id_is_echoed_in_the_response() {
+    // Arrange
+    let app = spawn_app().await;
+    let request_id = format!("tablet-{}", Uuid::new_v4());
+
+    // Act
+    let response = app
+        .post_order_with_request_id(gen_body(1, "hamburger", 1), &request_id)
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        Some(request_id.as_str()),
+        response
+            .headers()
+            .get("x-request-id")
+            .and_then(|h| h.to_str().ok())
+    );
+}
+
+#[actix_rt::test]
+async fn a_request_id_is_generated_when_none_is_supplied() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_order(gen_body(1, "hamburger", 1)).await;
+
+    // Assert
+    let request_id = response
+        .headers()
+        .get("x-request-id")
+        .and_then(|h| h.to_str().ok())
+        .expect("No request id in the response.");
+    assert!(Uuid::parse_str(request_id).is_ok());
+}
+
+#[actix_rt::test]
+async fn an_invalid_request_id_is_replaced() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_order_with_request_id(gen_body(1, "hamburger", 1), "not a valid id")
+        .await;
+
+    // Assert
+    let request_id = response
+        .headers()
+        .get("x-request-id")
+        .and_then(|h| h.to_str().ok())
+        .expect("No request id in the response.");
+    assert!(Uuid::parse_str(request_id).is_ok());
+}
+
+#[actix_rt::test]
+async fn error_responses_include_the_request_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let request_id = Uuid::new_v4().to_string();
+
+    // Act
+    let response = app
+        .post_order_with_request_id(gen_body(1, "unicorn", 1), &request_id)
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let body = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(body["request_id"], request_id.as_str());
+}
+
+#[actix_rt::test]
+async fn logs_are_correlated_with_the_request_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let request_id = Uuid::new_v4().to_string();
+
+    // Act
+    app.post_order_with_request_id(gen_body(1, "hamburger", 1), &request_id)
+        .await;
+
+    // Assert
+    let lines = captured_log_lines(&request_id);
+    // The root span, the handler and the database insert all carry the request id
+    for expected in [
+        "HTTP REQUEST",
+        "ADDING A NEW SUBSCRIBER",
+        "ADDING NEW ORDER TO DATABASE",
+    ] {
+        assert!(
+            lines.iter().any(|line| line.contains(expected)),
+            "No log line for {} carried the request id.",
+            expected
+        );
+    }
+    for line in lines {
+        let log = serde_json::from_str::<serde_json::Value>(&line).unwrap();
+        assert_eq!(log["request_id"], request_id.as_str());
+    }
+}
+
+#[actix_rt::test]
+async fn audit_records_carry_the_request_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let request_id = Uuid::new_v4().to_string();
+
+    // Act
+    app.post_order_with_request_id(gen_body(2, "fries", 3), &request_id)
+        .await;
+
+    // Assert
+    let saved = sqlx::query!("SELECT request_id, action, details FROM audit_log")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch audit record.");
+    assert_eq!(saved.request_id, request_id);
+    assert_eq!(saved.action, "order_placed");
+    assert_eq!(saved.details["item"], "fries");
+    assert_eq!(saved.details["quantity"], 3);
+}
@@ -0,0 +1,257 @@
+use crate::client::{gen_reservation, noon, spawn_app_with_clock, TestClient};
+use chrono::Duration;
+use restaurant::clock::FixedClock;
+use restaurant::reservations::Reservation;
+use restaurant::waitlist::WaitlistEntry;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn spawn_app_at_noon() -> TestClient {
+    spawn_app_with_clock(Arc::new(FixedClock::new(noon()))).await
+}
+
+async fn book(app: &TestClient, reservation: serde_json::Value) -> serde_json::Value {
+    let response = app.post_reservation(reservation).await;
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+fn id_of(value: &serde_json::Value) -> Uuid {
+    value["id"].as_str().unwrap().parse().unwrap()
+}
+
+async fn error_code(response: reqwest::Response) -> String {
+    assert_eq!(409, response.status().as_u16());
+    let error: serde_json::Value = response.json().await.unwrap();
+    error["code"].as_str().unwrap().to_string()
+}
+
+#[actix_rt::test]
+async fn parties_are_booked_at_the_smallest_free_table_they_fit_at() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let evening = noon() + Duration::hours(7);
+
+    // Act
+    let first = book(&app, gen_reservation(3, evening, None)).await;
+    let second = book(&app, gen_reservation(3, evening, None)).await;
+    let later = book(
+        &app,
+        gen_reservation(3, evening + Duration::minutes(90), None),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(first["table_no"], 3);
+    assert_eq!(second["table_no"], 4);
+    // The first booking has ended by then, so its table is free again
+    assert_eq!(later["table_no"], 3);
+    assert_eq!(first["status"], "booked");
+    assert_eq!(
+        first["ends_at"],
+        serde_json::json!(evening + Duration::minutes(90))
+    );
+}
+
+#[actix_rt::test]
+async fn overlapping_bookings_for_the_same_table_conflict() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let evening = noon() + Duration::hours(7);
+    book(&app, gen_reservation(2, evening, Some(1))).await;
+
+    // Act
+    let overlapping = app
+        .post_reservation(gen_reservation(2, evening + Duration::minutes(45), Some(1)))
+        .await;
+    let too_small = app
+        .post_reservation(gen_reservation(4, evening, Some(2)))
+        .await;
+
+    // Assert
+    assert_eq!(error_code(overlapping).await, "table_booked");
+    assert_eq!(error_code(too_small).await, "table_too_small");
+}
+
+#[actix_rt::test]
+async fn a_party_is_turned_away_when_every_table_they_fit_at_is_booked() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let evening = noon() + Duration::hours(7);
+    // Only table 8 seats eight
+    book(&app, gen_reservation(8, evening, None)).await;
+
+    // Act
+    let response = app
+        .post_reservation(gen_reservation(8, evening, None))
+        .await;
+
+    // Assert
+    assert_eq!(error_code(response).await, "no_table_available");
+}
+
+#[actix_rt::test]
+async fn bookings_that_could_never_work_are_rejected() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let mut bad_phone = gen_reservation(2, noon() + Duration::hours(7), None);
+    bad_phone["phone"] = "call me".into();
+    let test_cases = vec![
+        (
+            gen_reservation(2, noon() - Duration::hours(1), None),
+            "in the past",
+        ),
+        (
+            gen_reservation(2, noon() + Duration::minutes(20), None),
+            "between slots",
+        ),
+        (
+            gen_reservation(12, noon() + Duration::hours(7), None),
+            "too large a party",
+        ),
+        (
+            gen_reservation(2, noon() + Duration::hours(7), Some(99)),
+            "no such table",
+        ),
+        (bad_phone, "an invalid phone number"),
+    ];
+
+    for (reservation, description) in test_cases {
+        // Act
+        let response = app.post_reservation(reservation).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject a booking {}.",
+            description
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn seating_a_booking_opens_a_session_at_its_table() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let booking = book(
+        &app,
+        gen_reservation(2, noon() + Duration::minutes(30), None),
+    )
+    .await;
+
+    // Act
+    let response = app.seat_reservation(id_of(&booking)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let session: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(session["table_no"], booking["table_no"]);
+    assert_eq!(session["reservation_id"], booking["id"]);
+    assert_eq!(session["opened_at"], serde_json::json!(noon()));
+
+    let again = app.seat_reservation(id_of(&booking)).await;
+    assert_eq!(error_code(again).await, "reservation_not_booked");
+
+    let tables: Vec<serde_json::Value> = app.get_tables().await.json().await.unwrap();
+    let table = tables
+        .iter()
+        .find(|table| table["table_no"] == booking["table_no"])
+        .unwrap();
+    assert_eq!(table["session"]["id"], session["id"]);
+
+    let today: Vec<Reservation> = app
+        .get_reservations(Some("2026-10-19"))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(today.len(), 1);
+    assert_eq!(today[0].status, "seated");
+}
+
+#[actix_rt::test]
+async fn cancelling_a_booking_frees_its_table() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let evening = noon() + Duration::hours(7);
+    let booking = book(&app, gen_reservation(2, evening, Some(1))).await;
+
+    // Act
+    let response = app.cancel_reservation(id_of(&booking)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    book(&app, gen_reservation(2, evening, Some(1))).await;
+    let today: Vec<Reservation> = app.get_reservations(None).await.json().await.unwrap();
+    assert_eq!(today.len(), 1);
+    assert_eq!(
+        404,
+        app.cancel_reservation(Uuid::new_v4())
+            .await
+            .status()
+            .as_u16()
+    );
+}
+
+#[actix_rt::test]
+async fn walk_ins_are_quoted_a_wait_until_a_table_frees_up() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    // Table 8 is the only one seating eight, and its party sits down at noon
+    let booking = book(
+        &app,
+        gen_reservation(8, noon() + Duration::minutes(15), None),
+    )
+    .await;
+    app.seat_reservation(id_of(&booking)).await;
+
+    // Act
+    let couple: WaitlistEntry = app.post_waitlist(2).await.json().await.unwrap();
+    let first: WaitlistEntry = app.post_waitlist(8).await.json().await.unwrap();
+    let second: WaitlistEntry = app.post_waitlist(8).await.json().await.unwrap();
+
+    // Assert
+    assert_eq!(couple.quoted_wait_minutes, 0);
+    // Parties stay 90 minutes by default, and the first party of eight is ahead
+    assert_eq!(first.quoted_wait_minutes, 90);
+    assert_eq!(second.quoted_wait_minutes, 180);
+    let waiting: Vec<WaitlistEntry> = app.get_waitlist().await.json().await.unwrap();
+    let ids: Vec<Uuid> = waiting.iter().map(|entry| entry.id).collect();
+    assert_eq!(ids, vec![couple.id, first.id, second.id]);
+}
+
+#[actix_rt::test]
+async fn walk_ins_are_only_seated_at_free_tables_they_fit_at() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    book(
+        &app,
+        gen_reservation(2, noon() + Duration::minutes(30), Some(1)),
+    )
+    .await;
+    let couple: WaitlistEntry = app.post_waitlist(2).await.json().await.unwrap();
+    let family: WaitlistEntry = app.post_waitlist(5).await.json().await.unwrap();
+    let another_couple: WaitlistEntry = app.post_waitlist(2).await.json().await.unwrap();
+
+    // Act
+    let booked_soon = app.seat_from_waitlist(couple.id, 1).await;
+    let too_small = app.seat_from_waitlist(family.id, 3).await;
+    let seated = app.seat_from_waitlist(couple.id, 2).await;
+    let occupied = app.seat_from_waitlist(another_couple.id, 2).await;
+
+    // Assert
+    assert_eq!(error_code(booked_soon).await, "table_booked");
+    assert_eq!(error_code(too_small).await, "table_too_small");
+    assert_eq!(200, seated.status().as_u16());
+    let session: serde_json::Value = seated.json().await.unwrap();
+    assert_eq!(session["party_size"], 2);
+    assert_eq!(session["reservation_id"], serde_json::Value::Null);
+    assert_eq!(error_code(occupied).await, "table_occupied");
+
+    let left = app.leave_waitlist(family.id).await;
+    assert_eq!(200, left.status().as_u16());
+    let waiting: Vec<WaitlistEntry> = app.get_waitlist().await.json().await.unwrap();
+    let ids: Vec<Uuid> = waiting.iter().map(|entry| entry.id).collect();
+    assert_eq!(ids, vec![another_couple.id]);
+}
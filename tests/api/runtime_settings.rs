@@ -0,0 +1,243 @@
+use crate::client::{
+    next_event_containing, spawn_app, spawn_app_with_configuration, test_configuration,
+};
+use restaurant::configuration::ConfigurationSource;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[actix_rt::test]
+async fn runtime_settings_start_from_the_configuration() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_runtime_settings().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let settings = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(settings["preparation_time_multiplier"], 1.0);
+    assert_eq!(settings["log_level"], "info");
+}
+
+#[actix_rt::test]
+async fn a_partial_change_keeps_the_other_settings() {
+    // Arrange
+    let app = spawn_app().await;
+    app.patch_runtime_settings(serde_json::json!({"log_level": "debug"}))
+        .await;
+
+    // Act
+    let response = app
+        .patch_runtime_settings(serde_json::json!({"preparation_time_multiplier": 2.0}))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let settings = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(settings["log_level"], "debug");
+    assert_eq!(settings["preparation_time_multiplier"], 2.0);
+}
+
+#[actix_rt::test]
+async fn concurrent_partial_changes_keep_each_other() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for round in 1..=10 {
+        let multiplier = 1.0 + round as f64 / 10.0;
+        let log_level = if round % 2 == 0 { "debug" } else { "warn" };
+
+        // Act
+        let (first, second) = tokio::join!(
+            app.patch_runtime_settings(
+                serde_json::json!({"preparation_time_multiplier": multiplier})
+            ),
+            app.patch_runtime_settings(serde_json::json!({"log_level": log_level})),
+        );
+
+        // Assert
+        assert_eq!(200, first.status().as_u16());
+        assert_eq!(200, second.status().as_u16());
+        let settings = app
+            .get_runtime_settings()
+            .await
+            .json::<serde_json::Value>()
+            .await
+            .unwrap();
+        assert_eq!(settings["preparation_time_multiplier"], multiplier);
+        assert_eq!(settings["log_level"], log_level);
+    }
+}
+
+#[actix_rt::test]
+async fn invalid_runtime_settings_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"preparation_time_multiplier": -1.0}),
+            "a negative multiplier",
+        ),
+        (
+            serde_json::json!({"log_level": "info,="}),
+            "a malformed log filter",
+        ),
+        (
+            serde_json::json!({"happy_hour": true}),
+            "an unknown setting",
+        ),
+        (serde_json::json!(["fries"]), "a body that is not an object"),
+    ];
+
+    for (changes, description) in test_cases {
+        // Act
+        let response = app.patch_runtime_settings(changes).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject {}.",
+            description
+        );
+    }
+    let settings = app
+        .get_runtime_settings()
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(settings["preparation_time_multiplier"], 1.0);
+    assert_eq!(settings["log_level"], "info");
+}
+
+#[actix_rt::test]
+async fn changes_are_published_as_events() {
+    // Arrange
+    let app = spawn_app().await;
+    let mut events = app.get_events().await;
+    assert_eq!(
+        events.headers()["content-type"].to_str().unwrap(),
+        "text/event-stream"
+    );
+
+    // Act
+    app.patch_runtime_settings(serde_json::json!({"preparation_time_multiplier": 1.5}))
+        .await;
+
+    // Assert
+    let event = next_event_containing(&mut events, "runtime_settings_changed").await;
+    assert!(event.contains(r#""source":"admin""#));
+    assert!(event.contains(r#""preparation_time_multiplier":1.5"#));
+}
+
+#[actix_rt::test]
+async fn event_streams_end_with_a_reconnect_hint_on_shutdown() {
+    // Arrange
+    let app = spawn_app().await;
+    let mut events = app.get_events().await;
+
+    // Act
+    app.shutdown.trigger();
+
+    // Assert
+    let event = next_event_containing(&mut events, "event: shutdown").await;
+    assert!(event.contains("retry: "));
+    let end = actix_web::rt::time::timeout(Duration::from_secs(5), events.chunk())
+        .await
+        .expect("The event stream was not closed.");
+    assert!(matches!(end, Ok(None)));
+}
+
+#[actix_rt::test]
+async fn runtime_settings_are_reloaded_when_the_configuration_changes() {
+    // Arrange
+    // Work on a copy of the configuration so the files can be changed safely
+    let copy = TempDir::new().unwrap();
+    let directory = copy.path().to_path_buf();
+    for file in ["base.yaml", "local.yaml"] {
+        std::fs::copy(
+            std::path::Path::new("configuration").join(file),
+            directory.join(file),
+        )
+        .unwrap();
+    }
+    let mut configuration = test_configuration();
+    configuration
+        .application
+        .settings_reload_interval_milliseconds = 50;
+    let environment = configuration.source.as_ref().unwrap().environment.clone();
+    configuration.source = Some(ConfigurationSource {
+        directory: directory.clone(),
+        environment,
+    });
+    let app = spawn_app_with_configuration(configuration).await;
+    let mut events = app.get_events().await;
+
+    // Act
+    let local = std::fs::read_to_string(directory.join("local.yaml")).unwrap();
+    std::fs::write(
+        directory.join("local.yaml"),
+        format!("{}runtime:\n  preparation_time_multiplier: 3.0\n", local),
+    )
+    .unwrap();
+
+    // Assert
+    let event = next_event_containing(&mut events, "runtime_settings_changed").await;
+    assert!(event.contains(r#""source":"file""#));
+    let settings = app
+        .get_runtime_settings()
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(settings["preparation_time_multiplier"], 3.0);
+}
+
+#[actix_rt::test]
+async fn reloading_the_configuration_keeps_settings_the_files_did_not_change() {
+    // Arrange
+    let copy = TempDir::new().unwrap();
+    let directory = copy.path().to_path_buf();
+    for file in ["base.yaml", "local.yaml"] {
+        std::fs::copy(
+            std::path::Path::new("configuration").join(file),
+            directory.join(file),
+        )
+        .unwrap();
+    }
+    let mut configuration = test_configuration();
+    configuration
+        .application
+        .settings_reload_interval_milliseconds = 50;
+    let environment = configuration.source.as_ref().unwrap().environment.clone();
+    configuration.source = Some(ConfigurationSource {
+        directory: directory.clone(),
+        environment,
+    });
+    let app = spawn_app_with_configuration(configuration).await;
+    app.patch_runtime_settings(serde_json::json!({"preparation_time_multiplier": 2.0}))
+        .await;
+    let mut events = app.get_events().await;
+
+    // Act
+    let local = std::fs::read_to_string(directory.join("local.yaml")).unwrap();
+    std::fs::write(
+        directory.join("local.yaml"),
+        format!("{}runtime:\n  log_level: \"debug\"\n", local),
+    )
+    .unwrap();
+
+    // Assert
+    let event = next_event_containing(&mut events, "runtime_settings_changed").await;
+    assert!(event.contains(r#""source":"file""#));
+    let settings = app
+        .get_runtime_settings()
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(settings["log_level"], "debug");
+    assert_eq!(settings["preparation_time_multiplier"], 2.0);
+}
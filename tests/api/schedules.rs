@@ -0,0 +1,107 @@
+use crate::client::{
+    configure_database, gen_body, noon, spawn_app_with_clock, start_app_with_clock,
+    test_configuration,
+};
+use chrono::{Duration, TimeZone, Utc};
+use restaurant::clock::FixedClock;
+use restaurant::domain::DatabaseResponse;
+use std::sync::Arc;
+
+#[actix_rt::test]
+async fn breakfast_can_only_be_ordered_in_the_morning() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon() - Duration::hours(2)));
+    let app = spawn_app_with_clock(clock.clone()).await;
+
+    // Act
+    let morning = app.post_order(gen_body(1, "pancakes", 1)).await;
+    clock.set(noon());
+    let afternoon = app.post_order(gen_body(1, "pancakes", 1)).await;
+
+    // Assert
+    assert_eq!(200, morning.status().as_u16());
+    assert_eq!(409, afternoon.status().as_u16());
+    let error: serde_json::Value = afternoon.json().await.unwrap();
+    assert_eq!(error["code"], "not_served_now");
+}
+
+#[actix_rt::test]
+async fn happy_hour_prices_apply_to_orders_placed_during_happy_hour() {
+    // Arrange
+    let clock = Arc::new(FixedClock::new(noon() + Duration::hours(5)));
+    let app = spawn_app_with_clock(clock.clone()).await;
+
+    // Act
+    app.post_order(gen_body(1, "cola", 1)).await;
+    clock.set(noon() + Duration::hours(7));
+    app.post_order(gen_body(1, "cola", 1)).await;
+    let menu: serde_json::Value = app.get_menu().await.json().await.unwrap();
+
+    // Assert
+    let orders: Vec<DatabaseResponse> = app.query_table(1).await.json().await.unwrap();
+    let mut prices: Vec<i64> = orders.iter().map(|o| o.unit_price_cents).collect();
+    prices.sort();
+    assert_eq!(prices, vec![180, 250]);
+    // Happy hour is over, so the menu shows the usual price
+    assert_eq!(menu["items"][2]["current_price_cents"]["medium"], 250);
+}
+
+#[actix_rt::test]
+async fn orders_are_placed_at_the_time_on_the_clock() {
+    // Arrange
+    let app = spawn_app_with_clock(Arc::new(FixedClock::new(noon()))).await;
+
+    // Act
+    app.post_order(gen_body(1, "water", 1)).await;
+
+    // Assert
+    let saved = sqlx::query!("SELECT placed_at FROM orders")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.placed_at, noon());
+}
+
+#[actix_rt::test]
+async fn schedules_follow_the_configured_timezone() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.application.timezone = chrono_tz::Europe::Madrid;
+    configure_database(&configuration.database).await;
+    // 09:30 UTC is 11:30 in Madrid, after breakfast there
+    let clock = Arc::new(FixedClock::new(
+        Utc.with_ymd_and_hms(2026, 10, 19, 9, 30, 0).unwrap(),
+    ));
+    let app = start_app_with_clock(configuration, clock).await;
+
+    // Act
+    let response = app.post_order(gen_body(1, "pancakes", 1)).await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn happy_hour_prices_follow_the_configured_timezone() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.application.timezone = chrono_tz::Europe::Madrid;
+    configure_database(&configuration.database).await;
+    // 16:00 UTC is 18:00 in Madrid, during happy hour there
+    let clock = Arc::new(FixedClock::new(
+        Utc.with_ymd_and_hms(2026, 10, 19, 16, 0, 0).unwrap(),
+    ));
+    let app = start_app_with_clock(configuration, clock.clone()).await;
+
+    // Act
+    app.post_order(gen_body(1, "cola", 1)).await;
+    // 17:30 UTC is 19:30 in Madrid, after happy hour there
+    clock.set(Utc.with_ymd_and_hms(2026, 10, 19, 17, 30, 0).unwrap());
+    app.post_order(gen_body(1, "cola", 1)).await;
+
+    // Assert
+    let mut orders: Vec<DatabaseResponse> = app.query_table(1).await.json().await.unwrap();
+    orders.sort_by_key(|o| o.placed_at);
+    let prices: Vec<i64> = orders.iter().map(|o| o.unit_price_cents).collect();
+    assert_eq!(prices, vec![180, 250]);
+}
@@ -0,0 +1,69 @@
+use crate::client::{gen_body, spawn_app, spawn_app_with_configuration, test_configuration};
+use std::time::Duration;
+
+#[actix_rt::test]
+async fn readiness_fails_once_shutdown_starts() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.application.shutdown_drain_delay_milliseconds = 5000;
+    let app = spawn_app_with_configuration(configuration).await;
+
+    // Act
+    app.shutdown.trigger();
+    let response = app.health_ready().await;
+
+    // Assert
+    assert_eq!(503, response.status().as_u16());
+    let report = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(report["checks"]["server"]["status"], "failing");
+}
+
+#[actix_rt::test]
+async fn an_in_flight_order_completes_during_shutdown() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Hold a lock on the orders table so the order is still in flight when shutdown starts
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    sqlx::query("LOCK TABLE orders IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *transaction)
+        .await
+        .unwrap();
+
+    // Act
+    let in_flight = tokio::spawn({
+        let address = app.address.clone();
+        async move {
+            reqwest::Client::new()
+                .post(format!("{}/order", address))
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .body(gen_body(1, "hamburger", 1))
+                .send()
+                .await
+        }
+    });
+    actix_web::rt::time::sleep(Duration::from_millis(200)).await;
+    app.shutdown.trigger();
+    actix_web::rt::time::sleep(Duration::from_millis(200)).await;
+    transaction.rollback().await.unwrap();
+
+    let response = in_flight
+        .await
+        .unwrap()
+        .expect("The in-flight request was cut off.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT item FROM orders")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved order.");
+    assert_eq!(saved.item, "hamburger");
+
+    // The server no longer accepts new requests
+    let after_shutdown = reqwest::Client::new()
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await;
+    assert!(after_shutdown.is_err());
+}
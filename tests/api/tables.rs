@@ -0,0 +1,188 @@
+use crate::client::{gen_body, next_event_containing, noon, spawn_app_with_clock, TestClient};
+use restaurant::clock::FixedClock;
+use restaurant::domain::DatabaseResponse;
+use restaurant::sessions::{Merge, Transfer};
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn spawn_app_at_noon() -> TestClient {
+    spawn_app_with_clock(Arc::new(FixedClock::new(noon()))).await
+}
+
+async fn orders_at(app: &TestClient, table_no: i32) -> Vec<DatabaseResponse> {
+    app.query_table(table_no).await.json().await.unwrap()
+}
+
+async fn error_code(response: reqwest::Response) -> String {
+    assert_eq!(409, response.status().as_u16());
+    let error: serde_json::Value = response.json().await.unwrap();
+    error["code"].as_str().unwrap().to_string()
+}
+
+#[actix_rt::test]
+async fn a_transfer_moves_every_open_order_to_the_new_table() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    // Table 20 is the bar, which is not on the floor plan
+    app.post_order(gen_body(20, "cola", 2)).await;
+    app.post_order(gen_body(20, "fries", 1)).await;
+    app.post_order(gen_body(21, "water", 1)).await;
+    let mut events = app.get_events().await;
+
+    // Act
+    let response = app.transfer_table(20, 3).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let transfer: Transfer = response.json().await.unwrap();
+    assert_eq!(transfer.order_ids.len(), 2);
+    assert_eq!(transfer.session_id, None);
+    assert!(orders_at(&app, 20).await.is_empty());
+    assert_eq!(orders_at(&app, 3).await.len(), 2);
+    assert_eq!(orders_at(&app, 21).await.len(), 1);
+
+    let bill: serde_json::Value = app.get_bill(3).await.json().await.unwrap();
+    assert_eq!(bill["total_cents"], 2 * 250 + 350);
+
+    let event = next_event_containing(&mut events, "table_transferred").await;
+    assert!(event.contains(r#""to_table":3"#));
+    let audit = sqlx::query!("SELECT details FROM audit_log WHERE action = 'table_transferred'")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(audit.details["from_table"], 20);
+}
+
+#[actix_rt::test]
+async fn a_seated_party_takes_their_session_to_the_new_table() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_body(1, "hamburger", 2)).await;
+
+    // Act
+    let response = app.transfer_table(1, 2).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let orders = orders_at(&app, 2).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].session_id, Some(session_id));
+
+    let tables: Vec<serde_json::Value> = app.get_tables().await.json().await.unwrap();
+    assert_eq!(tables[0]["session"], serde_json::Value::Null);
+    assert_eq!(tables[1]["session"]["id"], session_id.to_string());
+}
+
+#[actix_rt::test]
+async fn orders_left_without_a_session_stay_behind_when_a_party_moves() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    // Ordered before anyone was seated, e.g. by an earlier party at the bar
+    app.post_order(gen_body(1, "water", 1)).await;
+    app.post_order(gen_body(4, "water", 1)).await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_body(1, "hamburger", 2)).await;
+    let into_session_id = app.seat_walk_in(2, 3).await;
+    let merging_id = app.seat_walk_in(2, 4).await;
+    app.post_order(gen_body(4, "cola", 1)).await;
+
+    // Act
+    let transfer = app.transfer_table(1, 2).await;
+    let merge = app.merge_sessions(merging_id, into_session_id).await;
+
+    // Assert
+    assert_eq!(200, transfer.status().as_u16());
+    assert_eq!(200, merge.status().as_u16());
+    for table_no in [1, 4] {
+        let stale = orders_at(&app, table_no).await;
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].item, "water");
+        assert_eq!(stale[0].session_id, None);
+    }
+    let moved = orders_at(&app, 2).await;
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].session_id, Some(session_id));
+    let bill: serde_json::Value = app.get_session_bill(session_id).await.json().await.unwrap();
+    assert_eq!(bill["lines"].as_array().unwrap().len(), 1);
+    assert_eq!(bill["lines"][0]["item"], "hamburger");
+    let merged = orders_at(&app, 3).await;
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].item, "cola");
+}
+
+#[actix_rt::test]
+async fn a_party_cannot_be_transferred_to_an_occupied_table() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    app.seat_walk_in(2, 1).await;
+    app.seat_walk_in(2, 2).await;
+    app.post_order(gen_body(1, "fries", 1)).await;
+
+    // Act
+    let occupied = app.transfer_table(1, 2).await;
+    let nothing = app.transfer_table(5, 6).await;
+    let itself = app.transfer_table(1, 1).await;
+
+    // Assert
+    assert_eq!(error_code(occupied).await, "table_occupied");
+    assert_eq!(orders_at(&app, 1).await.len(), 1);
+    assert_eq!(404, nothing.status().as_u16());
+    assert_eq!(400, itself.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn merging_sessions_combines_parties_and_their_orders() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let left = app.seat_walk_in(4, 3).await;
+    let right = app.seat_walk_in(3, 4).await;
+    app.post_order(gen_body(3, "hamburger", 2)).await;
+    app.post_order(gen_body(4, "water", 3)).await;
+    let mut events = app.get_events().await;
+
+    // Act
+    let response = app.merge_sessions(right, left).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let merge: Merge = response.json().await.unwrap();
+    assert_eq!(merge.table_no, 3);
+    assert_eq!(merge.party_size, 7);
+    assert!(orders_at(&app, 4).await.is_empty());
+    let orders = orders_at(&app, 3).await;
+    assert_eq!(orders.len(), 2);
+    assert!(orders.iter().all(|order| order.session_id == Some(left)));
+
+    let tables: Vec<serde_json::Value> = app.get_tables().await.json().await.unwrap();
+    assert_eq!(tables[2]["session"]["party_size"], 7);
+    assert_eq!(tables[3]["session"], serde_json::Value::Null);
+
+    let event = next_event_containing(&mut events, "sessions_merged").await;
+    assert!(event.contains(&left.to_string()));
+    let audit = sqlx::query!("SELECT details FROM audit_log WHERE action = 'sessions_merged'")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(audit.details["into_session_id"], left.to_string());
+}
+
+#[actix_rt::test]
+async fn closed_sessions_cannot_be_merged() {
+    // Arrange
+    let app = spawn_app_at_noon().await;
+    let first = app.seat_walk_in(2, 1).await;
+    let second = app.seat_walk_in(2, 2).await;
+    let third = app.seat_walk_in(2, 3).await;
+    app.merge_sessions(second, first).await;
+
+    // Act
+    let into_closed = app.merge_sessions(third, second).await;
+    let from_closed = app.merge_sessions(second, third).await;
+    let unknown = app.merge_sessions(third, Uuid::new_v4()).await;
+
+    // Assert
+    assert_eq!(error_code(into_closed).await, "session_closed");
+    assert_eq!(error_code(from_closed).await, "session_closed");
+    assert_eq!(404, unknown.status().as_u16());
+}
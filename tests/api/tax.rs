@@ -0,0 +1,197 @@
+use crate::client::{
+    configure_database, gen_body, gen_bundle_body, gen_takeaway_body, noon, spawn_app,
+    start_app_with_clock, test_configuration, TestClient,
+};
+use chrono::{Duration, NaiveDate};
+use restaurant::clock::FixedClock;
+use restaurant::configuration::TaxSettings;
+use restaurant::domain::{TaxClass, TaxRate, TaxRounding};
+use restaurant::payments::Account;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+fn rate(name: &str, class: TaxClass, percent: &str, effective_from: NaiveDate) -> TaxRate {
+    TaxRate {
+        name: name.to_string(),
+        class,
+        dining: None,
+        percent: percent.parse().unwrap(),
+        effective_from,
+    }
+}
+
+fn since_2020() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()
+}
+
+// Prices without tax, food at 10% and drinks at 21%
+fn exclusive_tax() -> TaxSettings {
+    TaxSettings {
+        jurisdiction: "ES".to_string(),
+        prices_include_tax: false,
+        rounding: TaxRounding::default(),
+        rates: vec![
+            rate("IVA reducido", TaxClass::Food, "10", since_2020()),
+            rate("IVA general", TaxClass::Drinks, "21", since_2020()),
+        ],
+    }
+}
+
+async fn spawn_app_taxed(tax: TaxSettings, clock: Arc<FixedClock>) -> TestClient {
+    let mut configuration = test_configuration();
+    configuration.tax = tax;
+    configure_database(&configuration.database).await;
+    start_app_with_clock(configuration, clock).await
+}
+
+async fn bill(app: &TestClient, table_no: i32) -> Value {
+    let response = app.get_bill(table_no).await;
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[actix_rt::test]
+async fn tax_included_in_prices_is_broken_down_on_the_bill() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+    app.post_order(gen_body(1, "cola", 1)).await;
+
+    // Assert
+    let bill = bill(&app, 1).await;
+    assert_eq!(bill["prices_include_tax"], true);
+    assert_eq!(bill["total_cents"], 1100);
+    // 1100 * 20 / 120 is 183.33
+    assert_eq!(
+        bill["tax"],
+        json!([{
+            "name": "VAT standard",
+            "percent": "20",
+            "taxable_cents": 917,
+            "tax_cents": 183,
+        }])
+    );
+    assert_eq!(bill["tax_cents"], 183);
+}
+
+#[actix_rt::test]
+async fn takeaway_food_is_taxed_at_its_own_rate() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    app.post_order(gen_takeaway_body(1, "hamburger", 1)).await;
+    app.post_order(gen_takeaway_body(1, "cola", 1)).await;
+    app.post_order(gen_body(1, "fries", 1)).await;
+
+    // Assert
+    let bill = bill(&app, 1).await;
+    assert_eq!(bill["lines"][0]["takeaway"], true);
+    assert_eq!(bill["lines"][2]["takeaway"], false);
+    assert_eq!(bill["tax"][0]["name"], "VAT zero");
+    assert_eq!(bill["tax"][0]["taxable_cents"], 850);
+    assert_eq!(bill["tax"][0]["tax_cents"], 0);
+    // The takeaway cola and the fries eaten in, 600 * 20 / 120
+    assert_eq!(bill["tax"][1]["name"], "VAT standard");
+    assert_eq!(bill["tax"][1]["tax_cents"], 100);
+
+    let orders: Value = app
+        .query_item(1, "hamburger".to_string())
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(orders[0]["takeaway"], true);
+}
+
+#[actix_rt::test]
+async fn tax_is_added_to_prices_that_do_not_include_it() {
+    // Arrange
+    let app = spawn_app_taxed(exclusive_tax(), Arc::new(FixedClock::new(noon()))).await;
+    let session_id = app.seat_walk_in(2, 1).await;
+
+    // Act
+    app.post_order(gen_body(1, "hamburger", 2)).await;
+    app.post_order(gen_body(1, "cola", 1)).await;
+
+    // Assert
+    let bill = bill(&app, 1).await;
+    assert_eq!(bill["subtotal_cents"], 1950);
+    assert_eq!(bill["tax"][0]["name"], "IVA reducido");
+    assert_eq!(bill["tax"][0]["percent"], "10");
+    assert_eq!(bill["tax"][0]["taxable_cents"], 1700);
+    assert_eq!(bill["tax"][0]["tax_cents"], 170);
+    // 52.5 cents rounds up
+    assert_eq!(bill["tax"][1]["tax_cents"], 53);
+    assert_eq!(bill["total_cents"], 1950 + 170 + 53);
+
+    let account: Account = app.get_payments(session_id).await.json().await.unwrap();
+    assert_eq!(account.total_cents, 2173);
+}
+
+#[actix_rt::test]
+async fn a_bundle_is_taxed_as_one_line() {
+    // Arrange
+    let app = spawn_app_taxed(exclusive_tax(), Arc::new(FixedClock::new(noon()))).await;
+
+    // Act
+    app.post_bundle_order(gen_bundle_body(1, "burger meal", 1, &[]))
+        .await;
+
+    // Assert
+    let bill = bill(&app, 1).await;
+    assert_eq!(bill["tax"].as_array().unwrap().len(), 1);
+    assert_eq!(bill["tax"][0]["taxable_cents"], 1200);
+    assert_eq!(bill["tax"][0]["tax_cents"], 120);
+}
+
+#[actix_rt::test]
+async fn discounts_lower_what_is_taxed() {
+    // Arrange
+    let app = spawn_app_taxed(exclusive_tax(), Arc::new(FixedClock::new(noon()))).await;
+    let session_id = app.seat_walk_in(2, 1).await;
+    app.post_order(gen_body(1, "hamburger", 1)).await;
+    app.post_order(gen_body(1, "water", 1)).await;
+
+    // Act
+    app.post_discount(
+        session_id,
+        json!({ "reason": "long wait", "percent_off": 10 }),
+    )
+    .await;
+
+    // Assert
+    let bill = bill(&app, 1).await;
+    // 10% of the 1000 cents comes off each line in proportion
+    assert_eq!(bill["discounts"][0]["amount_cents"], 100);
+    assert_eq!(bill["tax"][0]["taxable_cents"], 765);
+    assert_eq!(bill["tax"][0]["tax_cents"], 77);
+    assert_eq!(bill["tax"][1]["taxable_cents"], 135);
+    assert_eq!(bill["tax"][1]["tax_cents"], 28);
+    assert_eq!(bill["total_cents"], 900 + 77 + 28);
+}
+
+#[actix_rt::test]
+async fn lines_are_taxed_at_the_rate_in_effect_when_ordered() {
+    // Arrange
+    let tuesday = NaiveDate::from_ymd_opt(2026, 10, 20).unwrap();
+    let mut tax = exclusive_tax();
+    tax.rates
+        .push(rate("IVA reducido", TaxClass::Food, "12", tuesday));
+    let clock = Arc::new(FixedClock::new(noon()));
+    let app = spawn_app_taxed(tax, clock.clone()).await;
+
+    // Act
+    app.post_order(gen_body(1, "fries", 1)).await;
+    clock.advance(Duration::days(1));
+    app.post_order(gen_body(1, "fries", 1)).await;
+
+    // Assert
+    let bill = bill(&app, 1).await;
+    assert_eq!(bill["tax"][0]["percent"], "10");
+    assert_eq!(bill["tax"][0]["tax_cents"], 35);
+    assert_eq!(bill["tax"][1]["percent"], "12");
+    assert_eq!(bill["tax"][1]["tax_cents"], 42);
+}
@@ -0,0 +1,128 @@
+use actix_web::{test, web, App};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use restaurant::configuration::OtlpSettings;
+use restaurant::request_id::RequestIdRootSpanBuilder;
+use restaurant::routes::health_live;
+use restaurant::telemetry::{get_tracer_provider, get_user};
+use tracing_actix_web::TracingLogger;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Trace id of the W3C `traceparent` sent by a hypothetical upstream caller
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+// A stand-in for an OTLP collector accepting spans over HTTP
+async fn start_collector() -> MockServer {
+    let collector = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/traces"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1..)
+        .mount(&collector)
+        .await;
+    collector
+}
+
+fn tracer_provider_for(collector: &MockServer, redacted_fields: &[String]) -> TracerProvider {
+    get_tracer_provider(
+        &OtlpSettings {
+            endpoint: collector.uri(),
+            sampling_ratio: 1.0,
+            service_name: "restaurant-test".into(),
+        },
+        redacted_fields,
+    )
+    .expect("Failed to build the tracer provider.")
+}
+
+#[actix_rt::test]
+async fn request_spans_are_exported_to_the_collector_with_the_callers_trace_id() {
+    // Arrange
+    let collector = start_collector().await;
+    let tracer_provider = tracer_provider_for(&collector, &[]);
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    // Only this test sees the exporting subscriber, the global one is left untouched
+    let user = get_user(
+        "test".into(),
+        "info".into(),
+        std::io::sink,
+        Some(&tracer_provider),
+    );
+    let guard = tracing::subscriber::set_default(user);
+
+    let app = test::init_service(
+        App::new()
+            .wrap(TracingLogger::<RequestIdRootSpanBuilder>::new())
+            .route("/health/live", web::get().to(health_live)),
+    )
+    .await;
+
+    // Act
+    let request = test::TestRequest::get()
+        .uri("/health/live")
+        .insert_header((
+            "traceparent",
+            format!("00-{}-00f067aa0ba902b7-01", TRACE_ID),
+        ))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    let status = response.status();
+    // The request span only closes, and becomes exportable, once the response is dropped
+    drop(response);
+    drop(guard);
+
+    for flushed in tracer_provider.force_flush() {
+        flushed.expect("Failed to export spans.");
+    }
+
+    // Assert
+    assert!(status.is_success());
+    let exported = collector.received_requests().await.unwrap();
+    // The payload is protobuf, where the trace id is stored as raw bytes
+    let trace_id = u128::from_str_radix(TRACE_ID, 16).unwrap().to_be_bytes();
+    assert!(exported
+        .iter()
+        .any(|r| r.body.windows(trace_id.len()).any(|w| w == trace_id)));
+}
+
+#[actix_rt::test]
+async fn denylisted_fields_are_redacted_before_spans_are_exported() {
+    // Arrange
+    let collector = start_collector().await;
+    let tracer_provider = tracer_provider_for(
+        &collector,
+        &["password".to_string(), "customer_name".to_string()],
+    );
+    let user = get_user(
+        "test".into(),
+        "info".into(),
+        std::io::sink,
+        Some(&tracer_provider),
+    );
+
+    // Act
+    tracing::subscriber::with_default(user, || {
+        let span = tracing::info_span!("Signing in", password = "hunter2", table_no = 4);
+        let _entered = span.enter();
+        tracing::info!(order_customer_name = "Ada Lovelace", "Booking a table");
+    });
+    for flushed in tracer_provider.force_flush() {
+        flushed.expect("Failed to export spans.");
+    }
+
+    // Assert
+    let exported: Vec<u8> = collector
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .flat_map(|r| r.body)
+        .collect();
+    let contains = |text: &str| exported.windows(text.len()).any(|w| w == text.as_bytes());
+    assert!(contains("Signing in"));
+    assert!(contains("[REDACTED]"));
+    assert!(!contains("hunter2"));
+    assert!(!contains("Ada Lovelace"));
+}
@@ -0,0 +1,90 @@
+use crate::client::{gen_body, spawn_app_with_configuration, test_configuration};
+
+#[actix_rt::test]
+async fn order_returns_503_when_a_statement_times_out() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.database.statement_timeout_milliseconds = Some(200);
+    let app = spawn_app_with_configuration(configuration).await;
+
+    // Hold a lock on the orders table so the insert cannot complete
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    sqlx::query("LOCK TABLE orders IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *transaction)
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.post_order(gen_body(1, "hamburger", 1)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    assert!(response.headers().get("Retry-After").is_some());
+
+    transaction.rollback().await.unwrap();
+}
+
+#[actix_rt::test]
+async fn queries_return_503_when_no_connection_is_available() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.database.max_connections = 1;
+    configuration.database.acquire_timeout_milliseconds = 200;
+    configuration.database.statement_timeout_milliseconds = Some(2000);
+    let app = spawn_app_with_configuration(configuration).await;
+
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    sqlx::query("LOCK TABLE orders IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *transaction)
+        .await
+        .unwrap();
+
+    // Act
+
+    // The first request holds the only connection while it waits on the lock
+    let blocked = app.post_order(gen_body(1, "hamburger", 1));
+    let starved = async {
+        actix_web::rt::time::sleep(std::time::Duration::from_millis(100)).await;
+        app.query_all().await
+    };
+    let (_, response) = tokio::join!(blocked, starved);
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    assert_eq!(
+        response
+            .headers()
+            .get("Retry-After")
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        "1"
+    );
+
+    transaction.rollback().await.unwrap();
+}
+
+#[actix_rt::test]
+async fn queries_return_503_when_a_statement_times_out() {
+    // Arrange
+    let mut configuration = test_configuration();
+    configuration.database.statement_timeout_milliseconds = Some(200);
+    let app = spawn_app_with_configuration(configuration).await;
+
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    sqlx::query("LOCK TABLE orders IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *transaction)
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.query_all().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    assert!(response.headers().get("Retry-After").is_some());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["retry_after_seconds"], 1);
+
+    transaction.rollback().await.unwrap();
+}